@@ -0,0 +1,401 @@
+//! Command-line tool for inspecting and converting GBX files.
+
+use gbx::map::{UidError, UidMode, Validation};
+use gbx::read::Header;
+use gbx::{Block, Ghost, Item, Map};
+use std::env;
+use std::fmt::Debug;
+use std::fs;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: gbx <command> [arguments]
+
+Commands:
+    info <file>                                    Print a summary of the header of any GBX file
+    dump [--json] <file>                           Print the full contents of a map, item or block
+    extract-thumbnail <map> <output>               Write the thumbnail of a map as JPEG
+    extract-embedded <map> <directory>             Extract the embedded files of a map
+    set-name [--force] <map> <name> [output]       Set the name of a map
+    set-medals [--force] <map> <bronze> <silver> <gold> <author> [output]
+                                                   Set the medal times of a map in milliseconds
+    recompress [--force] [--uncompressed] <map> [output]
+                                                   Rewrite a map with a (un)compressed body
+    validate <map>                                 Check the UID, map type and embedded files of a map
+
+Maps with content which cannot be written yet, such as items, MediaTracker clips or validation ghosts,
+are only rewritten with --force.";
+
+/// Error of a command, printed to stderr.
+enum Error {
+    /// Invalid command-line arguments.
+    Usage(String),
+    /// Failure while executing the command.
+    Failed(String),
+}
+
+impl<E: std::error::Error> From<E> for Error {
+    fn from(err: E) -> Self {
+        Self::Failed(format!("{err}"))
+    }
+}
+
+type Result<T = ()> = std::result::Result<T, Error>;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Error::Usage(message)) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            ExitCode::from(2)
+        }
+        Err(Error::Failed(message)) => {
+            eprintln!("error: {message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result {
+    let (command, args) = args
+        .split_first()
+        .ok_or_else(|| Error::Usage(String::from("missing command")))?;
+
+    let (flags, args): (Vec<&str>, Vec<&str>) = args
+        .iter()
+        .map(|arg| arg.as_str())
+        .partition(|arg| arg.starts_with("--"));
+
+    match command.as_str() {
+        "info" => info(&flags, &args),
+        "dump" => dump(&flags, &args),
+        "extract-thumbnail" => extract_thumbnail(&flags, &args),
+        "extract-embedded" => extract_embedded(&flags, &args),
+        "set-name" => set_name(&flags, &args),
+        "set-medals" => set_medals(&flags, &args),
+        "recompress" => recompress(&flags, &args),
+        "validate" => validate(&flags, &args),
+        "help" | "--help" | "-h" => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        _ => Err(Error::Usage(format!("unknown command `{command}`"))),
+    }
+}
+
+/// Check that all `flags` are `allowed` and that the number of `args` is in `min..=max`.
+fn check_args(flags: &[&str], allowed: &[&str], args: &[&str], min: usize, max: usize) -> Result {
+    if let Some(flag) = flags.iter().find(|flag| !allowed.contains(flag)) {
+        return Err(Error::Usage(format!("unknown flag `{flag}`")));
+    }
+
+    if args.len() < min {
+        return Err(Error::Usage(String::from("missing arguments")));
+    }
+
+    if args.len() > max {
+        return Err(Error::Usage(String::from("too many arguments")));
+    }
+
+    Ok(())
+}
+
+fn read_map(path: &str) -> Result<Map> {
+    Ok(Map::reader().read_from_file(path)?)
+}
+
+/// Check that rewriting `map` does not lose content, unless `flags` contain `--force`.
+fn check_rewrite(map: &Map, flags: &[&str]) -> Result {
+    let mut dropped = vec![];
+
+    if !map.items.is_empty() {
+        dropped.push(format!("{} item(s)", map.items.len()));
+    }
+
+    let num_clips = [&map.intro_media, &map.podium_media, &map.ambiance_media]
+        .iter()
+        .filter(|clip| clip.is_some())
+        .count()
+        + [&map.in_game_media, &map.end_race_media]
+            .iter()
+            .filter(|clip_group| clip_group.is_some())
+            .count();
+    if num_clips > 0 {
+        dropped.push(format!("{num_clips} MediaTracker clip(s)"));
+    }
+
+    if map
+        .validation
+        .as_ref()
+        .map_or(false, |validation| validation.ghost.is_some())
+    {
+        dropped.push(String::from("a validation ghost"));
+    }
+
+    if dropped.is_empty() {
+        return Ok(());
+    }
+
+    let message = format!(
+        "map contains content which is dropped when writing: {}",
+        dropped.join(", ")
+    );
+
+    if flags.contains(&"--force") {
+        eprintln!("warning: {message}");
+        return Ok(());
+    }
+
+    Err(Error::Failed(format!(
+        "{message}, use --force to rewrite it anyway"
+    )))
+}
+
+fn write_map(map: &Map, path: &str, uid: UidMode, compress_body: bool) -> Result {
+    Ok(map
+        .writer_with_uid(uid)
+        .compress_body(compress_body)
+        .write_to_file(path)?)
+}
+
+fn is_block_path(path: &str) -> bool {
+    path.to_ascii_lowercase().ends_with(".block.gbx")
+}
+
+fn class_name(class_id: u32) -> &'static str {
+    match class_id {
+        0x03043000 => "Map",
+        0x03092000 => "Ghost",
+        0x2E002000 => "Item or Block",
+        0x09003000 => "Crystal",
+        _ => "unknown",
+    }
+}
+
+fn info(flags: &[&str], args: &[&str]) -> Result {
+    check_args(flags, &[], args, 1, 1)?;
+
+    let header = Header::read_from_file(args[0])?;
+    let body = if header.is_body_compressed {
+        "compressed"
+    } else {
+        "uncompressed"
+    };
+
+    println!("Version:       {}", header.version);
+    println!("Body:          {body}");
+    println!(
+        "Class:         {:08X} ({})",
+        header.class_id,
+        class_name(header.class_id)
+    );
+    println!("Header chunks:");
+    for chunk in &header.chunks {
+        let heavy = if chunk.is_heavy { ", heavy" } else { "" };
+        println!("    {:08X} ({} bytes{heavy})", chunk.id, chunk.size);
+    }
+    println!("Nodes:         {}", header.num_nodes);
+
+    match header.class_id {
+        0x03043000 => {
+            let map = Map::reader().body(false).read_from_file(args[0])?;
+
+            println!("Name:          {}", map.name);
+            println!("UID:           {}", map.uid().unwrap_or_default());
+            println!("Author:        {} ({})", map.author_name, map.author_uid);
+            println!("Map type:      {}", map.map_type);
+            println!("Checkpoints:   {}", map.num_cps);
+            if let Some(num_laps) = map.num_laps {
+                println!("Laps:          {num_laps}");
+            }
+            if let Some(ref validation) = map.validation {
+                println!(
+                    "Medals:        {} / {} / {} / {}",
+                    validation.author_time,
+                    validation.gold_time,
+                    validation.silver_time,
+                    validation.bronze_time
+                );
+            }
+        }
+        0x2E002000 if is_block_path(args[0]) => {
+            let block = Block::reader().read_from_file(args[0])?;
+
+            println!("Archetype:     {}", block.archetype);
+            println!("Variants:      {}", block.variants.len());
+        }
+        0x2E002000 => {
+            let item = Item::reader().read_from_file(args[0])?;
+
+            println!("Materials:     {}", item.model.materials.len());
+        }
+        0x03092000 => {
+            let ghost = Ghost::reader().user_data(false).read_from_file(args[0])?;
+
+            println!("Login:         {}", ghost.login);
+            match ghost.race_time {
+                Some(race_time) => println!("Race time:     {race_time}"),
+                None => println!("Race time:     did not finish"),
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn print_dump<T: Debug + serde::Serialize>(node: &T, json: bool) -> Result {
+    if json {
+        println!("{}", serde_json::to_string_pretty(node)?);
+    } else {
+        println!("{node:#?}");
+    }
+
+    Ok(())
+}
+
+fn dump(flags: &[&str], args: &[&str]) -> Result {
+    check_args(flags, &["--json"], args, 1, 1)?;
+
+    let json = flags.contains(&"--json");
+    let bytes = fs::read(args[0])?;
+
+    match Header::read_from(bytes.as_slice())?.class_id {
+        0x03043000 => print_dump(&Map::reader().read_from(bytes.as_slice())?, json),
+        0x2E002000 if is_block_path(args[0]) => {
+            print_dump(&Block::reader().read_from(bytes.as_slice())?, json)
+        }
+        0x2E002000 => print_dump(&Item::reader().read_from(bytes.as_slice())?, json),
+        class_id => Err(Error::Failed(format!(
+            "cannot dump files of class {class_id:08X}"
+        ))),
+    }
+}
+
+fn extract_thumbnail(flags: &[&str], args: &[&str]) -> Result {
+    check_args(flags, &[], args, 2, 2)?;
+
+    let map = Map::reader().body(false).read_from_file(args[0])?;
+    let thumbnail = map
+        .thumbnail
+        .ok_or_else(|| Error::Failed(String::from("map has no thumbnail")))?;
+
+    Ok(fs::write(args[1], thumbnail)?)
+}
+
+fn extract_embedded(flags: &[&str], args: &[&str]) -> Result {
+    check_args(flags, &[], args, 2, 2)?;
+
+    let map = read_map(args[0])?;
+    let embedded_files = map
+        .embedded_files
+        .ok_or_else(|| Error::Failed(String::from("map has no embedded files")))?;
+
+    for file in embedded_files.files()? {
+        println!("{}", file.path);
+    }
+
+    Ok(embedded_files.extract(args[1])?)
+}
+
+fn set_name(flags: &[&str], args: &[&str]) -> Result {
+    check_args(flags, &["--force"], args, 2, 3)?;
+
+    let mut map = read_map(args[0])?;
+    check_rewrite(&map, flags)?;
+    map.name = String::from(args[1]);
+
+    write_map(&map, args.get(2).unwrap_or(&args[0]), UidMode::Keep, true)
+}
+
+fn parse_time(arg: &str) -> Result<u32> {
+    arg.parse()
+        .map_err(|_| Error::Usage(format!("invalid time `{arg}`")))
+}
+
+fn set_medals(flags: &[&str], args: &[&str]) -> Result {
+    check_args(flags, &["--force"], args, 5, 6)?;
+
+    let bronze_time = parse_time(args[1])?;
+    let silver_time = parse_time(args[2])?;
+    let gold_time = parse_time(args[3])?;
+    let author_time = parse_time(args[4])?;
+
+    if !(author_time <= gold_time && gold_time <= silver_time && silver_time <= bronze_time) {
+        return Err(Error::Usage(String::from(
+            "medal times must be ordered author <= gold <= silver <= bronze",
+        )));
+    }
+
+    let mut map = read_map(args[0])?;
+    check_rewrite(&map, flags)?;
+    let ghost = map
+        .validation
+        .take()
+        .and_then(|validation| validation.ghost);
+    map.validation = Some(Validation {
+        bronze_time,
+        silver_time,
+        gold_time,
+        author_time,
+        ghost,
+    });
+
+    write_map(&map, args.get(5).unwrap_or(&args[0]), UidMode::Keep, true)
+}
+
+fn recompress(flags: &[&str], args: &[&str]) -> Result {
+    check_args(flags, &["--force", "--uncompressed"], args, 1, 2)?;
+
+    let map = read_map(args[0])?;
+    check_rewrite(&map, flags)?;
+    let compress_body = !flags.contains(&"--uncompressed");
+
+    write_map(
+        &map,
+        args.get(1).unwrap_or(&args[0]),
+        UidMode::Keep,
+        compress_body,
+    )
+}
+
+fn validate(flags: &[&str], args: &[&str]) -> Result {
+    check_args(flags, &[], args, 1, 1)?;
+
+    let map = read_map(args[0])?;
+    let mut problems = vec![];
+
+    match map.validate_uid() {
+        Ok(_) => {}
+        Err(UidError::Missing) => problems.push(String::from("map has no uid")),
+        Err(err) => problems.push(format!("invalid uid: {err}")),
+    }
+
+    if let Err(err) = map.validate_map_type() {
+        problems.push(format!("invalid {}: {err}", map.map_type));
+    }
+
+    let dependencies = map.dependencies();
+    for id in &dependencies.missing {
+        problems.push(format!("missing embedded model {id}"));
+    }
+    for path in &dependencies.unused {
+        println!("warning: unused embedded file {path}");
+    }
+
+    if problems.is_empty() {
+        println!("ok");
+        return Ok(());
+    }
+
+    for problem in &problems {
+        println!("{problem}");
+    }
+
+    Err(Error::Failed(format!(
+        "found {} problem(s)",
+        problems.len()
+    )))
+}
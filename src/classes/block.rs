@@ -6,6 +6,7 @@ use std::io::{Read, Seek};
 
 /// Type corresponding to the file extension `Block.Gbx`.
 #[derive(Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Block {
     /// ID of the block info archetype.
     pub archetype: Id,
@@ -1,9 +1,10 @@
-use crate::read::{self, ReadBodyChunk, Reader};
+use crate::read::{self, ReadBodyChunk, Reader, ReaderBuilder};
 use std::borrow::BorrowMut;
 use std::io::{Read, Seek};
 
 /// Entity record.
 #[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EntityRecord;
 
 impl EntityRecord {
@@ -35,10 +36,33 @@ impl EntityRecord {
 }
 
 /// Type corresponding to the file extension `Ghost.Gbx`.
-#[derive(Clone, Default, Debug)]
-pub struct Ghost;
+#[derive(Clone, PartialEq, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ghost {
+    /// Race time of the ghost in milliseconds, `None` if the ghost did not finish.
+    pub race_time: Option<u32>,
+    /// Login of the player who drove the ghost.
+    pub login: String,
+}
 
 impl Ghost {
+    /// Get a ghost reader.
+    ///
+    /// The user data of ghost files is not supported and must not be read.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # || -> gbx::read::Result<()> {
+    /// let ghost = gbx::Ghost::reader()
+    ///     .user_data(false)
+    ///     .read_from_file("MyGhost.Ghost.Gbx")?;
+    /// # Ok(()) };
+    /// ```
+    pub fn reader() -> ReaderBuilder<Self> {
+        ReaderBuilder::new(Self::default, 0x03092000, vec![], Self::body_chunks())
+    }
+
     pub(crate) fn read<R, I, N>(r: &mut Reader<R, I, N>) -> read::Result<Self>
     where
         R: Read + Seek,
@@ -47,48 +71,56 @@ impl Ghost {
     {
         let mut ghost = Self::default();
 
-        read::read_body(
-            &mut ghost,
-            r,
-            vec![
-                (0x0303F006, ReadBodyChunk::Read(Self::read_chunk_0303f006)),
-                (0x0303F007, ReadBodyChunk::Skip),
-                (
-                    0x03092000,
-                    ReadBodyChunk::ReadSkippable(Self::read_chunk_03092000),
-                ),
-                (0x03092005, ReadBodyChunk::Skip),
-                (0x03092008, ReadBodyChunk::Skip),
-                (0x0309200A, ReadBodyChunk::Skip),
-                (0x0309200B, ReadBodyChunk::Skip),
-                (0x0309200C, ReadBodyChunk::Read(Self::read_chunk_0309200c)),
-                (0x0309200E, ReadBodyChunk::Read(Self::read_chunk_0309200e)),
-                (0x0309200F, ReadBodyChunk::Read(Self::read_chunk_0309200f)),
-                (0x03092010, ReadBodyChunk::Read(Self::read_chunk_03092010)),
-                (0x03092013, ReadBodyChunk::Skip),
-                (0x03092014, ReadBodyChunk::Skip),
-                (0x0309201A, ReadBodyChunk::Skip),
-                (0x0309201B, ReadBodyChunk::Skip),
-                (0x0309201C, ReadBodyChunk::Read(Self::read_chunk_0309201c)),
-                (0x0309201D, ReadBodyChunk::Skip),
-                (0x03092022, ReadBodyChunk::Skip),
-                (0x03092023, ReadBodyChunk::Skip),
-                (0x03092024, ReadBodyChunk::Skip),
-                (0x03092025, ReadBodyChunk::Skip),
-                (0x03092026, ReadBodyChunk::Skip),
-                (0x03092027, ReadBodyChunk::Skip),
-                (0x03092028, ReadBodyChunk::Skip),
-                (0x03092029, ReadBodyChunk::Skip),
-                (0x0309202A, ReadBodyChunk::Skip),
-                (0x0309202B, ReadBodyChunk::Skip),
-                (0x0309202C, ReadBodyChunk::Skip),
-                (0x0309202D, ReadBodyChunk::Skip),
-            ],
-        )?;
+        read::read_body(&mut ghost, r, Self::body_chunks())?;
 
         Ok(ghost)
     }
 
+    fn body_chunks<R, I, N>() -> Vec<(u32, ReadBodyChunk<Self, R, I, N>)>
+    where
+        R: Read + Seek,
+        I: BorrowMut<read::IdState>,
+        N: BorrowMut<read::NodeState>,
+    {
+        vec![
+            (0x0303F006, ReadBodyChunk::Read(Self::read_chunk_0303f006)),
+            (0x0303F007, ReadBodyChunk::Skip),
+            (
+                0x03092000,
+                ReadBodyChunk::ReadSkippable(Self::read_chunk_03092000),
+            ),
+            (
+                0x03092005,
+                ReadBodyChunk::ReadSkippable(Self::read_chunk_03092005),
+            ),
+            (0x03092008, ReadBodyChunk::Skip),
+            (0x0309200A, ReadBodyChunk::Skip),
+            (0x0309200B, ReadBodyChunk::Skip),
+            (0x0309200C, ReadBodyChunk::Read(Self::read_chunk_0309200c)),
+            (0x0309200E, ReadBodyChunk::Read(Self::read_chunk_0309200e)),
+            (0x0309200F, ReadBodyChunk::Read(Self::read_chunk_0309200f)),
+            (0x03092010, ReadBodyChunk::Read(Self::read_chunk_03092010)),
+            (0x03092013, ReadBodyChunk::Skip),
+            (0x03092014, ReadBodyChunk::Skip),
+            (0x0309201A, ReadBodyChunk::Skip),
+            (0x0309201B, ReadBodyChunk::Skip),
+            (0x0309201C, ReadBodyChunk::Read(Self::read_chunk_0309201c)),
+            (0x0309201D, ReadBodyChunk::Skip),
+            (0x03092022, ReadBodyChunk::Skip),
+            (0x03092023, ReadBodyChunk::Skip),
+            (0x03092024, ReadBodyChunk::Skip),
+            (0x03092025, ReadBodyChunk::Skip),
+            (0x03092026, ReadBodyChunk::Skip),
+            (0x03092027, ReadBodyChunk::Skip),
+            (0x03092028, ReadBodyChunk::Skip),
+            (0x03092029, ReadBodyChunk::Skip),
+            (0x0309202A, ReadBodyChunk::Skip),
+            (0x0309202B, ReadBodyChunk::Skip),
+            (0x0309202C, ReadBodyChunk::Skip),
+            (0x0309202D, ReadBodyChunk::Skip),
+        ]
+    }
+
     fn read_chunk_0303f006<R, I, N>(&mut self, r: &mut Reader<R, I, N>) -> read::Result<()>
     where
         R: Read,
@@ -144,6 +176,20 @@ impl Ghost {
         Ok(())
     }
 
+    fn read_chunk_03092005<R, I, N>(&mut self, r: &mut Reader<R, I, N>) -> read::Result<()>
+    where
+        R: Read,
+    {
+        let race_time = r.u32()?;
+        self.race_time = if race_time == 0xFFFFFFFF {
+            None
+        } else {
+            Some(race_time)
+        };
+
+        Ok(())
+    }
+
     fn read_chunk_0309200c<R, I, N>(&mut self, r: &mut Reader<R, I, N>) -> read::Result<()>
     where
         R: Read,
@@ -166,7 +212,7 @@ impl Ghost {
     where
         R: Read,
     {
-        r.string()?;
+        self.login = r.string()?;
 
         Ok(())
     }
@@ -6,6 +6,7 @@ use std::io::{Read, Seek};
 
 /// Type corresponding to the file extension `Item.Gbx`.
 #[derive(Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Item {
     /// Model of the item.
     pub model: Model,
@@ -0,0 +1,212 @@
+use super::geometry::{BLOCK_SIZE, GRID_Y_OFFSET};
+use super::{BlockType, Map, Validation};
+use crate::types::Vec3;
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+
+/// A problem of a map found by [`Map::check`].
+#[derive(Clone, PartialEq, Debug)]
+pub enum Problem {
+    /// [`Map::num_cps`] does not match the number of placed checkpoints.
+    CheckpointCount {
+        /// Value of [`Map::num_cps`].
+        expected: u32,
+        /// Number of placed checkpoints, counting each linked group once.
+        actual: u32,
+    },
+    /// The map has no start waypoint.
+    MissingStart,
+    /// The map has no finish waypoint.
+    MissingFinish,
+    /// The map has laps but no multilap waypoint.
+    MissingStartFinish,
+    /// A linked checkpoint group contains only a single checkpoint.
+    UnlinkedCheckpointGroup {
+        /// Group number.
+        group: u32,
+    },
+    /// A block lies outside of [`Map::size`].
+    BlockOutOfBounds {
+        /// Index into [`Map::blocks`].
+        index: usize,
+    },
+    /// Two blocks of the same model are placed at the same coord and direction.
+    DuplicateBlock {
+        /// Index of the first block into [`Map::blocks`].
+        first: usize,
+        /// Index of the second block into [`Map::blocks`].
+        second: usize,
+    },
+    /// An item has a skin without a skin file.
+    MissingSkinFile {
+        /// Index into [`Map::items`].
+        index: usize,
+    },
+    /// The medal times are not ordered bronze ≥ silver ≥ gold ≥ author.
+    UnorderedMedals,
+    /// A MediaTracker trigger lies outside of [`Map::size`].
+    TriggerOutOfBounds {
+        /// Name of the clip group, e.g. `in game`.
+        clip_group: &'static str,
+        /// Index of the clip inside of the clip group.
+        clip: usize,
+        /// Coord of the trigger.
+        coord: Vec3<u32>,
+    },
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::CheckpointCount { expected, actual } => {
+                write!(f, "map has {actual} checkpoints but num_cps is {expected}")
+            }
+            Self::MissingStart => write!(f, "map has no start"),
+            Self::MissingFinish => write!(f, "map has no finish"),
+            Self::MissingStartFinish => write!(f, "map has laps but no multilap"),
+            Self::UnlinkedCheckpointGroup { group } => {
+                write!(f, "linked checkpoint group {group} has only one checkpoint")
+            }
+            Self::BlockOutOfBounds { index } => write!(f, "block {index} is outside of the map"),
+            Self::DuplicateBlock { first, second } => {
+                write!(f, "block {second} is a duplicate of block {first}")
+            }
+            Self::MissingSkinFile { index } => write!(f, "item {index} has a skin without file"),
+            Self::UnorderedMedals => write!(f, "medal times are not ordered"),
+            Self::TriggerOutOfBounds {
+                clip_group,
+                clip,
+                ref coord,
+            } => write!(
+                f,
+                "{clip_group} clip {clip} has a trigger at ({}, {}, {}) outside of the map",
+                coord.x, coord.y, coord.z
+            ),
+        }
+    }
+}
+
+fn are_medals_ordered(validation: &Validation) -> bool {
+    validation.bronze_time >= validation.silver_time
+        && validation.silver_time >= validation.gold_time
+        && validation.gold_time >= validation.author_time
+}
+
+impl Map {
+    /// Check the map for common problems, e.g. before uploading it.
+    ///
+    /// Returns an empty list if no problems were found.
+    /// Blocks are assumed to occupy a single grid cell.
+    pub fn check(&self) -> Vec<Problem> {
+        let mut problems = vec![];
+
+        let summary = self.waypoint_summary();
+
+        if summary.num_checkpoints != self.num_cps {
+            problems.push(Problem::CheckpointCount {
+                expected: self.num_cps,
+                actual: summary.num_checkpoints,
+            });
+        }
+
+        if !summary.has_start() {
+            problems.push(Problem::MissingStart);
+        }
+
+        if !summary.has_finish() {
+            problems.push(Problem::MissingFinish);
+        }
+
+        if self.num_laps.is_some() && summary.num_start_finishes == 0 {
+            problems.push(Problem::MissingStartFinish);
+        }
+
+        for (&group, &count) in &summary.linked_groups {
+            if count < 2 {
+                problems.push(Problem::UnlinkedCheckpointGroup { group });
+            }
+        }
+
+        let mut placed = HashMap::new();
+
+        for (index, block) in self.blocks.iter().enumerate() {
+            let is_in_bounds = match *block {
+                BlockType::Normal(ref block) => {
+                    (block.coord.x as u32) < self.size.x
+                        && (block.coord.y as u32) < self.size.y
+                        && (block.coord.z as u32) < self.size.z
+                }
+                BlockType::Free(ref free_block) => {
+                    let pos = &free_block.pos;
+                    let y = pos.y / BLOCK_SIZE.y + GRID_Y_OFFSET as f32;
+
+                    (0.0..=self.size.x as f32 * BLOCK_SIZE.x).contains(&pos.x)
+                        && (0.0..=self.size.y as f32).contains(&y)
+                        && (0.0..=self.size.z as f32 * BLOCK_SIZE.z).contains(&pos.z)
+                }
+            };
+
+            if !is_in_bounds {
+                problems.push(Problem::BlockOutOfBounds { index });
+            }
+
+            if let BlockType::Normal(ref block) = *block {
+                let key = (block.model_id.as_str(), block.coord, block.dir);
+
+                if let Some(&first) = placed.get(&key) {
+                    problems.push(Problem::DuplicateBlock {
+                        first,
+                        second: index,
+                    });
+                } else {
+                    placed.insert(key, index);
+                }
+            }
+        }
+
+        for (index, item) in self.items.iter().enumerate() {
+            if let Some(ref skin) = item.skin {
+                let has_file = skin
+                    .skin
+                    .as_ref()
+                    .map_or(false, |skin| !skin.path().as_os_str().is_empty());
+
+                if !has_file {
+                    problems.push(Problem::MissingSkinFile { index });
+                }
+            }
+        }
+
+        if let Some(ref validation) = self.validation {
+            if !are_medals_ordered(validation) {
+                problems.push(Problem::UnorderedMedals);
+            }
+        }
+
+        let clip_groups = [
+            ("in game", &self.in_game_media),
+            ("end race", &self.end_race_media),
+        ];
+
+        for (clip_group_name, clip_group) in clip_groups {
+            let clip_group = match *clip_group {
+                Some(ref clip_group) => clip_group,
+                None => continue,
+            };
+
+            for (clip, trigger) in clip_group.clips.iter().enumerate() {
+                for coord in &trigger.coords {
+                    if coord.x >= self.size.x || coord.y >= self.size.y || coord.z >= self.size.z {
+                        problems.push(Problem::TriggerOutOfBounds {
+                            clip_group: clip_group_name,
+                            clip,
+                            coord: coord.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        problems
+    }
+}
@@ -0,0 +1,204 @@
+use super::{BlockType, Map};
+use crate::{FileRef, Id};
+
+/// Kind of a model placed in a map.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ModelKind {
+    /// Block or free block model.
+    Block,
+    /// Item model.
+    Item,
+}
+
+/// A block or item model placed in a map.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModelDependency {
+    /// Kind of the model.
+    pub kind: ModelKind,
+    /// ID of the model.
+    pub id: Id,
+    /// Collection of the model, `26` for Stadium.
+    ///
+    /// Blocks belong to the collection of the map decoration.
+    pub collection: u32,
+    /// Optional author of the model, e.g. `Nadeo`.
+    ///
+    /// Custom blocks have the author of their embedded file, other blocks the author of the map decoration.
+    pub author: Option<Id>,
+}
+
+impl ModelDependency {
+    /// `true` if the model is a custom block or item, which needs to be embedded in the map.
+    ///
+    /// The ID of a custom model is the internal path of its GBX file,
+    /// followed by `_CustomBlock` for custom blocks.
+    pub fn is_custom(&self) -> bool {
+        self.file_path().to_ascii_lowercase().ends_with(".gbx")
+    }
+
+    /// Internal path of the file of the model.
+    fn file_path(&self) -> &str {
+        self.id.strip_suffix("_CustomBlock").unwrap_or(&self.id)
+    }
+
+    /// `true` if the embedded file at `path` contains this model.
+    fn is_embedded_at(&self, path: &str) -> bool {
+        is_path_suffix(path, self.file_path())
+    }
+}
+
+/// Dependencies of a map.
+///
+/// Returned by [`Map::dependencies`].
+#[derive(Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MapDependencies {
+    /// All distinct block and item models placed in the map, in order of their first placement.
+    pub models: Vec<ModelDependency>,
+    /// All files referenced by the map: skins, music and the texture mod.
+    pub files: Vec<FileRef>,
+    /// IDs of all custom models which are not embedded in the map.
+    pub missing: Vec<Id>,
+    /// Paths of all embedded files which are not referenced by the map.
+    pub unused: Vec<Id>,
+}
+
+/// A dependency listed in the XML header of a map.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dependency {
+    /// Internal path of the file, e.g. `Skins\Any\Advertisement\MyLogo.dds`.
+    pub file: String,
+    /// Optional URL from where the file can be downloaded.
+    pub url: Option<String>,
+}
+
+/// `true` if `suffix` are the last components of `path`,
+/// ignoring ASCII case and the kind of path separator.
+fn is_path_suffix(path: &str, suffix: &str) -> bool {
+    let normalize = |s: &str| s.replace('\\', "/").to_ascii_lowercase();
+    let (path, suffix) = (normalize(path), normalize(suffix));
+
+    path == suffix || path.ends_with(&format!("/{suffix}"))
+}
+
+impl Map {
+    /// Collect all dependencies of the map and check them against the embedded files.
+    ///
+    /// # Examples
+    ///
+    /// Check that all custom items are embedded before publishing a map.
+    /// ```no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let map = gbx::Map::reader().read_from_file("MyMap.Map.Gbx")?;
+    /// let dependencies = map.dependencies();
+    ///
+    /// for id in &dependencies.missing {
+    ///     println!("{id} is not embedded");
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub fn dependencies(&self) -> MapDependencies {
+        let mut dependencies = MapDependencies::default();
+
+        let embedded_paths: &[Id] = self
+            .embedded_files
+            .as_ref()
+            .map_or(&[], |embedded_files| &embedded_files.paths);
+        let embedded_authors: &[Option<Id>] = self
+            .embedded_files
+            .as_ref()
+            .map_or(&[], |embedded_files| &embedded_files.authors);
+
+        let block_models = self.blocks.iter().map(|block| {
+            let mut model = ModelDependency {
+                kind: ModelKind::Block,
+                id: Id::clone(block.model_id()),
+                collection: 26,
+                author: Some(Id::clone(&self.decoration_author)),
+            };
+
+            if model.is_custom() {
+                model.author = embedded_paths
+                    .iter()
+                    .position(|path| model.is_embedded_at(path))
+                    .and_then(|index| embedded_authors.get(index).cloned().flatten());
+            }
+
+            model
+        });
+        let item_models = self.items.iter().map(|item| ModelDependency {
+            kind: ModelKind::Item,
+            id: Id::clone(&item.model_id),
+            collection: item.collection,
+            author: item.author.clone(),
+        });
+
+        for model in block_models.chain(item_models) {
+            if !dependencies.models.contains(&model) {
+                dependencies.models.push(model);
+            }
+        }
+
+        let texture_mod = self.texture_mod.clone().map(FileRef::External);
+        let skins = self
+            .blocks
+            .iter()
+            .filter_map(BlockType::skin)
+            .chain(self.items.iter().filter_map(|item| item.skin.as_ref()))
+            .flat_map(|skin| skin.skin.iter().chain(skin.effect.iter()));
+
+        for file_ref in skins.chain(self.music.iter()).chain(texture_mod.iter()) {
+            if !dependencies
+                .files
+                .iter()
+                .any(|other| other.path() == file_ref.path())
+            {
+                dependencies.files.push(file_ref.clone());
+            }
+        }
+
+        dependencies.missing = dependencies
+            .models
+            .iter()
+            .filter(|model| model.is_custom())
+            .filter(|model| !embedded_paths.iter().any(|path| model.is_embedded_at(path)))
+            .map(|model| Id::clone(&model.id))
+            .collect();
+
+        dependencies.unused = embedded_paths
+            .iter()
+            .filter(|path| {
+                !dependencies
+                    .models
+                    .iter()
+                    .any(|model| model.is_embedded_at(path))
+                    && !dependencies
+                        .files
+                        .iter()
+                        .any(|file_ref| is_path_suffix(path, &file_ref.path().to_string_lossy()))
+            })
+            .cloned()
+            .collect();
+
+        dependencies
+    }
+
+    /// Dependencies listed in the XML header when writing the map.
+    ///
+    /// The dependencies are generated from the external file references of the map,
+    /// the dependencies read from the file are available in [`XmlHeader::deps`](super::XmlHeader::deps).
+    pub fn header_deps(&self) -> Vec<Dependency> {
+        self.dependencies()
+            .files
+            .into_iter()
+            .filter_map(FileRef::external)
+            .map(|file_ref| Dependency {
+                file: file_ref.path.to_string_lossy().into_owned(),
+                url: Some(file_ref.locator_url).filter(|url| !url.is_empty()),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,800 @@
+use super::{Block, BlockType, Direction, FreeBlock, Item, Map, Validation, WaypointProperty};
+use crate::ghost::Ghost;
+use crate::types::{Id, Vec3};
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Display};
+
+/// Placement of a block or item inside of a map.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Placement {
+    /// Block placed on the grid.
+    Grid {
+        /// Coordinate of the block.
+        coord: Vec3<u8>,
+        /// Direction of the block.
+        dir: Direction,
+    },
+    /// Free block or item placed at an absolute position.
+    Free {
+        /// Absolute position.
+        pos: Vec3<f32>,
+        /// Yaw rotation.
+        yaw: f32,
+        /// Pitch rotation.
+        pitch: f32,
+        /// Roll rotation.
+        roll: f32,
+    },
+}
+
+impl Placement {
+    fn key(&self) -> PlacementKey {
+        match *self {
+            Self::Grid { ref coord, dir } => {
+                PlacementKey::Grid([coord.x, coord.y, coord.z, dir as u8])
+            }
+            Self::Free {
+                ref pos,
+                yaw,
+                pitch,
+                roll,
+            } => PlacementKey::Free(
+                [pos.x, pos.y, pos.z, yaw, pitch, roll].map(|value| value.to_bits()),
+            ),
+        }
+    }
+}
+
+impl Display for Placement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Grid { ref coord, dir } => {
+                write!(f, "({}, {}, {}) {dir:?}", coord.x, coord.y, coord.z)
+            }
+            Self::Free {
+                ref pos,
+                yaw,
+                pitch,
+                roll,
+            } => write!(
+                f,
+                "({}, {}, {}) yaw {yaw} pitch {pitch} roll {roll}",
+                pos.x, pos.y, pos.z
+            ),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash)]
+enum PlacementKey {
+    Grid([u8; 4]),
+    Free([u32; 6]),
+}
+
+/// Medal times of a map in milliseconds.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MedalTimes {
+    /// Bronze medal time.
+    pub bronze: u32,
+    /// Silver medal time.
+    pub silver: u32,
+    /// Gold medal time.
+    pub gold: u32,
+    /// Author medal time.
+    pub author: u32,
+}
+
+impl From<&Validation> for MedalTimes {
+    fn from(validation: &Validation) -> Self {
+        Self {
+            bronze: validation.bronze_time,
+            silver: validation.silver_time,
+            gold: validation.gold_time,
+            author: validation.author_time,
+        }
+    }
+}
+
+impl Display for MedalTimes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "author {} gold {} silver {} bronze {}",
+            self.author, self.gold, self.silver, self.bronze
+        )
+    }
+}
+
+/// Kind of a placed object.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ObjectKind {
+    /// Block or free block.
+    Block,
+    /// Item.
+    Item,
+}
+
+impl Display for ObjectKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Block => f.write_str("block"),
+            Self::Item => f.write_str("item"),
+        }
+    }
+}
+
+/// A single change between two maps.
+///
+/// Returned by [`diff`].
+#[derive(Clone, PartialEq, Debug)]
+pub enum Change {
+    /// A metadata field of the map changed, e.g. the name.
+    ///
+    /// The old and new values are debug formatted.
+    MetadataChanged {
+        /// Name of the field, e.g. `name`.
+        field: &'static str,
+        /// Old value.
+        old: String,
+        /// New value.
+        new: String,
+    },
+    /// The medal times of the map changed.
+    MedalsChanged {
+        /// Old medal times, `None` if the map was not validated.
+        old: Option<MedalTimes>,
+        /// New medal times, `None` if the map is not validated.
+        new: Option<MedalTimes>,
+    },
+    /// A block or item was added.
+    Added {
+        /// Kind of the object.
+        kind: ObjectKind,
+        /// ID of the model.
+        model_id: Id,
+        /// Placement of the object.
+        placement: Placement,
+    },
+    /// A block or item was removed.
+    Removed {
+        /// Kind of the object.
+        kind: ObjectKind,
+        /// ID of the model.
+        model_id: Id,
+        /// Placement of the object.
+        placement: Placement,
+    },
+    /// A block or item was moved.
+    Moved {
+        /// Kind of the object.
+        kind: ObjectKind,
+        /// ID of the model.
+        model_id: Id,
+        /// Old placement of the object.
+        from: Placement,
+        /// New placement of the object.
+        to: Placement,
+    },
+    /// Properties like the color, skin or variant of a block or item changed.
+    Changed {
+        /// Kind of the object.
+        kind: ObjectKind,
+        /// ID of the model.
+        model_id: Id,
+        /// Placement of the object.
+        placement: Placement,
+    },
+    /// The waypoint property of a block or item changed.
+    WaypointChanged {
+        /// Kind of the object.
+        kind: ObjectKind,
+        /// ID of the model.
+        model_id: Id,
+        /// Placement of the object.
+        placement: Placement,
+        /// Old waypoint property.
+        old: Option<WaypointProperty>,
+        /// New waypoint property.
+        new: Option<WaypointProperty>,
+    },
+    /// A MediaTracker clip was added.
+    MediaAdded {
+        /// Name of the clip, e.g. `intro`.
+        clip: &'static str,
+    },
+    /// A MediaTracker clip was removed.
+    MediaRemoved {
+        /// Name of the clip, e.g. `intro`.
+        clip: &'static str,
+    },
+    /// A MediaTracker clip changed.
+    MediaChanged {
+        /// Name of the clip, e.g. `intro`.
+        clip: &'static str,
+    },
+    /// The zone genealogies of some ground columns changed.
+    ZonesChanged {
+        /// Coordinates `x`, `z` of the changed columns.
+        columns: Vec<(u32, u32)>,
+    },
+    /// A file was embedded.
+    EmbeddedFileAdded {
+        /// Internal path of the file.
+        path: String,
+    },
+    /// An embedded file was removed.
+    EmbeddedFileRemoved {
+        /// Internal path of the file.
+        path: String,
+    },
+    /// The content of an embedded file changed.
+    EmbeddedFileChanged {
+        /// Internal path of the file.
+        ///
+        /// Empty if an archive is invalid and only the raw archives could be compared.
+        path: String,
+    },
+}
+
+fn fmt_medals(medals: &Option<MedalTimes>) -> String {
+    match *medals {
+        Some(ref medals) => medals.to_string(),
+        None => String::from("none"),
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::MetadataChanged {
+                field,
+                ref old,
+                ref new,
+            } => write!(f, "{field} changed: {old} -> {new}"),
+            Self::MedalsChanged { ref old, ref new } => write!(
+                f,
+                "medals changed: {} -> {}",
+                fmt_medals(old),
+                fmt_medals(new)
+            ),
+            Self::Added {
+                kind,
+                ref model_id,
+                ref placement,
+            } => write!(f, "{kind} added: {model_id} at {placement}"),
+            Self::Removed {
+                kind,
+                ref model_id,
+                ref placement,
+            } => write!(f, "{kind} removed: {model_id} at {placement}"),
+            Self::Moved {
+                kind,
+                ref model_id,
+                ref from,
+                ref to,
+            } => write!(f, "{kind} moved: {model_id} from {from} to {to}"),
+            Self::Changed {
+                kind,
+                ref model_id,
+                ref placement,
+            } => write!(f, "{kind} changed: {model_id} at {placement}"),
+            Self::WaypointChanged {
+                kind,
+                ref model_id,
+                ref placement,
+                ref old,
+                ref new,
+            } => write!(
+                f,
+                "{kind} waypoint changed: {model_id} at {placement}: {old:?} -> {new:?}"
+            ),
+            Self::MediaAdded { clip } => write!(f, "{clip} media added"),
+            Self::MediaRemoved { clip } => write!(f, "{clip} media removed"),
+            Self::MediaChanged { clip } => write!(f, "{clip} media changed"),
+            Self::ZonesChanged { ref columns } => {
+                write!(f, "zones changed at {} column(s)", columns.len())
+            }
+            Self::EmbeddedFileAdded { ref path } => write!(f, "embedded file added: {path}"),
+            Self::EmbeddedFileRemoved { ref path } => write!(f, "embedded file removed: {path}"),
+            Self::EmbeddedFileChanged { ref path } => write!(f, "embedded file changed: {path}"),
+        }
+    }
+}
+
+/// Changes between two maps.
+///
+/// Returned by [`diff`].
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct MapDiff {
+    /// All changes, metadata changes first,
+    /// followed by block, item, media, zone and embedded file changes.
+    pub changes: Vec<Change>,
+}
+
+impl MapDiff {
+    /// `true` if there are no changes.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl Display for MapDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{change}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// ID, collection and author of a model.
+type ModelKey<'a> = (&'a str, Option<u32>, Option<&'a str>);
+
+/// A placed block, free block or item.
+#[derive(Clone, Copy)]
+enum Object<'a> {
+    Block(&'a Block),
+    FreeBlock(&'a FreeBlock),
+    Item(&'a Item),
+}
+
+impl<'a> Object<'a> {
+    fn kind(self) -> ObjectKind {
+        match self {
+            Self::Block(_) | Self::FreeBlock(_) => ObjectKind::Block,
+            Self::Item(_) => ObjectKind::Item,
+        }
+    }
+
+    /// Key which identifies the model of the object: its ID, collection and author.
+    fn model_key(self) -> ModelKey<'a> {
+        match self {
+            Self::Block(_) | Self::FreeBlock(_) => (self.model_id().as_str(), None, None),
+            Self::Item(item) => (
+                item.model_id.as_str(),
+                Some(item.collection),
+                item.author.as_ref().map(|author| author.as_str()),
+            ),
+        }
+    }
+
+    fn model_id(self) -> &'a Id {
+        match self {
+            Self::Block(block) => &block.model_id,
+            Self::FreeBlock(free_block) => &free_block.model_id,
+            Self::Item(item) => &item.model_id,
+        }
+    }
+
+    fn placement(self) -> Placement {
+        match self {
+            Self::Block(block) => Placement::Grid {
+                coord: block.coord,
+                dir: block.dir,
+            },
+            Self::FreeBlock(free_block) => Placement::Free {
+                pos: free_block.pos.clone(),
+                yaw: free_block.yaw,
+                pitch: free_block.pitch,
+                roll: free_block.roll,
+            },
+            Self::Item(item) => Placement::Free {
+                pos: item.pos.clone(),
+                yaw: item.yaw,
+                pitch: item.pitch,
+                roll: item.roll,
+            },
+        }
+    }
+
+    fn waypoint_property(self) -> Option<&'a WaypointProperty> {
+        match self {
+            Self::Block(block) => block.waypoint_property.as_ref(),
+            Self::FreeBlock(free_block) => free_block.waypoint_property.as_ref(),
+            Self::Item(item) => item.waypoint_property.as_ref(),
+        }
+    }
+
+    /// `true` if all properties except the placement and waypoint property are equal.
+    fn has_same_properties(self, other: Self) -> bool {
+        match (self, other) {
+            (Self::Block(a), Self::Block(b)) => {
+                a.is_ground == b.is_ground
+                    && a.skin == b.skin
+                    && a.variant_index == b.variant_index
+                    && a.is_ghost == b.is_ghost
+                    && a.color == b.color
+                    && a.lightmap_quality == b.lightmap_quality
+            }
+            (Self::FreeBlock(a), Self::FreeBlock(b)) => {
+                a.skin == b.skin && a.color == b.color && a.lightmap_quality == b.lightmap_quality
+            }
+            (Self::Item(a), Self::Item(b)) => {
+                a.collection == b.collection
+                    && a.author == b.author
+                    && a.variant_index == b.variant_index
+                    && a.pivot_pos == b.pivot_pos
+                    && a.color == b.color
+                    && a.anim_offset == b.anim_offset
+                    && a.lightmap_quality == b.lightmap_quality
+                    && a.skin == b.skin
+            }
+            _ => false,
+        }
+    }
+}
+
+fn block_objects(blocks: &[BlockType]) -> Vec<Object<'_>> {
+    blocks
+        .iter()
+        .map(|block| match *block {
+            BlockType::Normal(ref block) => Object::Block(block),
+            BlockType::Free(ref free_block) => Object::FreeBlock(free_block),
+        })
+        .collect()
+}
+
+fn item_objects(items: &[Item]) -> Vec<Object<'_>> {
+    items.iter().map(Object::Item).collect()
+}
+
+/// Push the changes of two matched objects.
+fn diff_matched(changes: &mut Vec<Change>, old: Object, new: Object) {
+    if !old.has_same_properties(new) {
+        changes.push(Change::Changed {
+            kind: new.kind(),
+            model_id: new.model_id().clone(),
+            placement: new.placement(),
+        });
+    }
+
+    if old.waypoint_property() != new.waypoint_property() {
+        changes.push(Change::WaypointChanged {
+            kind: new.kind(),
+            model_id: new.model_id().clone(),
+            placement: new.placement(),
+            old: old.waypoint_property().cloned(),
+            new: new.waypoint_property().cloned(),
+        });
+    }
+}
+
+/// Push the changes between the `old` and `new` objects.
+///
+/// Objects with the same model and placement are matched first,
+/// remaining objects with the same model are matched in order as moved objects.
+/// Models are identified by their ID, collection and author.
+fn diff_objects(changes: &mut Vec<Change>, old: &[Object], new: &[Object]) {
+    let mut old_by_key: HashMap<(ModelKey, PlacementKey), Vec<usize>> = HashMap::new();
+    for (index, object) in old.iter().enumerate().rev() {
+        old_by_key
+            .entry((object.model_key(), object.placement().key()))
+            .or_default()
+            .push(index);
+    }
+
+    let mut is_old_matched = vec![false; old.len()];
+    let mut unmatched_new = vec![];
+
+    for &object in new {
+        let old_index = old_by_key
+            .get_mut(&(object.model_key(), object.placement().key()))
+            .and_then(|indices| indices.pop());
+
+        match old_index {
+            Some(old_index) => {
+                is_old_matched[old_index] = true;
+                diff_matched(changes, old[old_index], object);
+            }
+            None => unmatched_new.push(object),
+        }
+    }
+
+    let mut unmatched_old_by_model: HashMap<ModelKey, Vec<(usize, Object)>> = HashMap::new();
+    for (index, (&object, _)) in old
+        .iter()
+        .zip(&is_old_matched)
+        .enumerate()
+        .rev()
+        .filter(|(_, (_, &is_matched))| !is_matched)
+    {
+        unmatched_old_by_model
+            .entry(object.model_key())
+            .or_default()
+            .push((index, object));
+    }
+
+    let mut added = vec![];
+
+    for object in unmatched_new {
+        let old_object = unmatched_old_by_model
+            .get_mut(&object.model_key())
+            .and_then(|objects| objects.pop());
+
+        match old_object {
+            Some((_, old_object)) => {
+                changes.push(Change::Moved {
+                    kind: object.kind(),
+                    model_id: object.model_id().clone(),
+                    from: old_object.placement(),
+                    to: object.placement(),
+                });
+                diff_matched(changes, old_object, object);
+            }
+            None => added.push(Change::Added {
+                kind: object.kind(),
+                model_id: object.model_id().clone(),
+                placement: object.placement(),
+            }),
+        }
+    }
+
+    changes.append(&mut added);
+
+    let mut removed: Vec<(usize, Object)> =
+        unmatched_old_by_model.into_values().flatten().collect();
+    removed.sort_unstable_by_key(|&(index, _)| index);
+
+    for (_, object) in removed {
+        changes.push(Change::Removed {
+            kind: object.kind(),
+            model_id: object.model_id().clone(),
+            placement: object.placement(),
+        });
+    }
+}
+
+fn diff_metadata<T: PartialEq + Debug>(
+    changes: &mut Vec<Change>,
+    field: &'static str,
+    old: &T,
+    new: &T,
+) {
+    if old != new {
+        changes.push(Change::MetadataChanged {
+            field,
+            old: format!("{old:?}"),
+            new: format!("{new:?}"),
+        });
+    }
+}
+
+fn diff_media<T: PartialEq>(
+    changes: &mut Vec<Change>,
+    clip: &'static str,
+    old: &Option<T>,
+    new: &Option<T>,
+) {
+    match (old, new) {
+        (None, Some(_)) => changes.push(Change::MediaAdded { clip }),
+        (Some(_), None) => changes.push(Change::MediaRemoved { clip }),
+        (Some(old), Some(new)) if old != new => changes.push(Change::MediaChanged { clip }),
+        _ => {}
+    }
+}
+
+fn diff_zones(changes: &mut Vec<Change>, old: &Map, new: &Map) {
+    let size_x = old.size.x.max(new.size.x);
+    let size_z = old.size.z.max(new.size.z);
+
+    let columns: Vec<(u32, u32)> = (0..size_x)
+        .flat_map(|x| (0..size_z).map(move |z| (x, z)))
+        .filter(|&(x, z)| old.zone_genealogy(x, z) != new.zone_genealogy(x, z))
+        .collect();
+
+    if !columns.is_empty() {
+        changes.push(Change::ZonesChanged { columns });
+    }
+}
+
+fn diff_embedded_files(changes: &mut Vec<Change>, old: &Map, new: &Map) {
+    let files = |map: &Map| match map.embedded_files {
+        Some(ref embedded_files) => embedded_files.files().map(|files| {
+            files
+                .into_iter()
+                .map(|file| (file.path, file.data))
+                .collect::<Vec<_>>()
+        }),
+        None => Ok(vec![]),
+    };
+
+    let (old_files, new_files) = match (files(old), files(new)) {
+        (Ok(old_files), Ok(new_files)) => (old_files, new_files),
+        _ => {
+            let old_archive = old.embedded_files.as_ref().map(|files| &files.archive);
+            let new_archive = new.embedded_files.as_ref().map(|files| &files.archive);
+
+            if old_archive != new_archive {
+                changes.push(Change::EmbeddedFileChanged {
+                    path: String::new(),
+                });
+            }
+
+            return;
+        }
+    };
+
+    for (path, data) in &new_files {
+        match old_files.iter().find(|(old_path, _)| old_path == path) {
+            None => changes.push(Change::EmbeddedFileAdded { path: path.clone() }),
+            Some((_, old_data)) if old_data != data => {
+                changes.push(Change::EmbeddedFileChanged { path: path.clone() })
+            }
+            _ => {}
+        }
+    }
+
+    for (path, _) in &old_files {
+        if !new_files.iter().any(|(new_path, _)| new_path == path) {
+            changes.push(Change::EmbeddedFileRemoved { path: path.clone() });
+        }
+    }
+}
+
+/// Compute the changes from the `old` to the `new` version of a map.
+///
+/// Blocks and items are matched by their model and placement.
+/// Remaining blocks and items of the same model are reported as moved.
+///
+/// # Examples
+///
+/// ```
+/// # use gbx::Map;
+/// let old = Map::default();
+/// let mut new = Map::default();
+/// new.name = String::from("New name");
+///
+/// let diff = gbx::map::diff(&old, &new);
+/// assert_eq!(diff.to_string(), "name changed: \"\" -> \"New name\"\n");
+/// ```
+pub fn diff(old: &Map, new: &Map) -> MapDiff {
+    let mut changes = vec![];
+
+    diff_metadata(&mut changes, "name", &old.name, &new.name);
+    diff_metadata(
+        &mut changes,
+        "author_name",
+        &old.author_name,
+        &new.author_name,
+    );
+    diff_metadata(&mut changes, "author_uid", &old.author_uid, &new.author_uid);
+    diff_metadata(
+        &mut changes,
+        "author_zone",
+        &old.author_zone,
+        &new.author_zone,
+    );
+    diff_metadata(
+        &mut changes,
+        "decoration_author",
+        &old.decoration_author,
+        &new.decoration_author,
+    );
+    diff_metadata(&mut changes, "map_kind", &old.map_kind, &new.map_kind);
+    diff_metadata(&mut changes, "is_locked", &old.is_locked, &new.is_locked);
+    if old.password != new.password {
+        let mask = |password: u32| match password {
+            0 => String::from("None"),
+            _ => String::from("********"),
+        };
+
+        changes.push(Change::MetadataChanged {
+            field: "password",
+            old: mask(old.password),
+            new: mask(new.password),
+        });
+    }
+    diff_metadata(&mut changes, "cost", &old.cost, &new.cost);
+    diff_metadata(&mut changes, "num_cps", &old.num_cps, &new.num_cps);
+    diff_metadata(&mut changes, "num_laps", &old.num_laps, &new.num_laps);
+    diff_metadata(&mut changes, "no_stadium", &old.no_stadium, &new.no_stadium);
+    diff_metadata(&mut changes, "day_time", &old.day_time, &new.day_time);
+    diff_metadata(&mut changes, "size", &old.size, &new.size);
+    diff_metadata(&mut changes, "map_type", &old.map_type, &new.map_type);
+    diff_metadata(&mut changes, "map_style", &old.map_style, &new.map_style);
+    diff_metadata(&mut changes, "title_id", &old.title_id, &new.title_id);
+    diff_metadata(
+        &mut changes,
+        "texture_mod",
+        &old.texture_mod,
+        &new.texture_mod,
+    );
+    diff_metadata(&mut changes, "music", &old.music, &new.music);
+    diff_metadata(
+        &mut changes,
+        "script_metadata",
+        &old.script_metadata,
+        &new.script_metadata,
+    );
+
+    if old.thumbnail != new.thumbnail {
+        let size = |thumbnail: &Option<Vec<u8>>| match *thumbnail {
+            Some(ref thumbnail) => format!("{} bytes", thumbnail.len()),
+            None => String::from("None"),
+        };
+
+        changes.push(Change::MetadataChanged {
+            field: "thumbnail",
+            old: size(&old.thumbnail),
+            new: size(&new.thumbnail),
+        });
+    }
+
+    diff_metadata(
+        &mut changes,
+        "author_score",
+        &old.author_score,
+        &new.author_score,
+    );
+
+    let old_ghost = old
+        .validation
+        .as_ref()
+        .and_then(|validation| validation.ghost.as_ref());
+    let new_ghost = new
+        .validation
+        .as_ref()
+        .and_then(|validation| validation.ghost.as_ref());
+    if old_ghost != new_ghost {
+        let summary = |ghost: Option<&Ghost>| match ghost {
+            Some(ghost) => match ghost.race_time {
+                Some(race_time) => format!("{race_time} ms by {:?}", ghost.login),
+                None => format!("unfinished by {:?}", ghost.login),
+            },
+            None => String::from("None"),
+        };
+
+        changes.push(Change::MetadataChanged {
+            field: "validation_ghost",
+            old: summary(old_ghost),
+            new: summary(new_ghost),
+        });
+    }
+
+    let old_medals = old.validation.as_ref().map(MedalTimes::from);
+    let new_medals = new.validation.as_ref().map(MedalTimes::from);
+    if old_medals != new_medals {
+        changes.push(Change::MedalsChanged {
+            old: old_medals,
+            new: new_medals,
+        });
+    }
+
+    diff_objects(
+        &mut changes,
+        &block_objects(&old.blocks),
+        &block_objects(&new.blocks),
+    );
+    diff_objects(
+        &mut changes,
+        &item_objects(&old.items),
+        &item_objects(&new.items),
+    );
+
+    diff_media(&mut changes, "intro", &old.intro_media, &new.intro_media);
+    diff_media(&mut changes, "podium", &old.podium_media, &new.podium_media);
+    diff_media(
+        &mut changes,
+        "in game",
+        &old.in_game_media,
+        &new.in_game_media,
+    );
+    diff_media(
+        &mut changes,
+        "end race",
+        &old.end_race_media,
+        &new.end_race_media,
+    );
+    diff_media(
+        &mut changes,
+        "ambiance",
+        &old.ambiance_media,
+        &new.ambiance_media,
+    );
+
+    diff_zones(&mut changes, old, new);
+    diff_embedded_files(&mut changes, old, new);
+
+    MapDiff { changes }
+}
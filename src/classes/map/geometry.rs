@@ -0,0 +1,295 @@
+use super::{Block, Direction, FreeBlock, Item};
+use crate::types::Vec3;
+use std::f32::consts::FRAC_PI_2;
+
+/// Size of a single grid cell in world units.
+pub const BLOCK_SIZE: Vec3<f32> = Vec3::new(32.0, 8.0, 32.0);
+
+/// Grid Y coordinate of the world origin.
+///
+/// A block at grid height 8 is placed at world height 0.
+pub const GRID_Y_OFFSET: u8 = 8;
+
+/// Tolerance used when snapping world positions and rotations to the grid.
+const EPSILON: f32 = 1e-3;
+
+/// A row-major 3x3 rotation matrix.
+///
+/// The columns of the matrix are the local X, Y and Z axes in world space.
+pub type Matrix3 = [[f32; 3]; 3];
+
+/// A unit quaternion `[x, y, z, w]`.
+pub type Quaternion = [f32; 4];
+
+/// World position of the corner of the grid cell at `coord`.
+pub fn grid_to_world(coord: Vec3<u8>) -> Vec3<f32> {
+    Vec3::new(
+        coord.x as f32 * BLOCK_SIZE.x,
+        (coord.y as f32 - GRID_Y_OFFSET as f32) * BLOCK_SIZE.y,
+        coord.z as f32 * BLOCK_SIZE.z,
+    )
+}
+
+/// Coordinate of the grid cell containing the world position `pos`.
+///
+/// Returns `None` if the position lies outside of the grid.
+pub fn world_to_grid(pos: &Vec3<f32>) -> Option<Vec3<u8>> {
+    let cell = |value: f32, size: f32, offset: u8| {
+        let cell = (value / size + EPSILON).floor() + offset as f32;
+
+        if (0.0..=u8::MAX as f32).contains(&cell) {
+            Some(cell as u8)
+        } else {
+            None
+        }
+    };
+
+    Some(Vec3::new(
+        cell(pos.x, BLOCK_SIZE.x, 0)?,
+        cell(pos.y, BLOCK_SIZE.y, GRID_Y_OFFSET)?,
+        cell(pos.z, BLOCK_SIZE.z, 0)?,
+    ))
+}
+
+/// Rotation matrix of the Euler angles `yaw`, `pitch` and `roll` in radians.
+///
+/// The game applies yaw around the Y axis, pitch around the X axis and then roll around the Z axis.
+pub fn euler_to_matrix(yaw: f32, pitch: f32, roll: f32) -> Matrix3 {
+    let (sy, cy) = yaw.sin_cos();
+    let (sp, cp) = pitch.sin_cos();
+    let (sr, cr) = roll.sin_cos();
+
+    // M = R_yaw * R_pitch * R_roll
+    [
+        [cy * cr + sy * sp * sr, sy * sp * cr - cy * sr, sy * cp],
+        [cp * sr, cp * cr, -sp],
+        [cy * sp * sr - sy * cr, sy * sr + cy * sp * cr, cy * cp],
+    ]
+}
+
+/// Euler angles `(yaw, pitch, roll)` in radians of the rotation matrix `matrix`.
+///
+/// If the pitch is ±90° the roll is always zero.
+pub fn matrix_to_euler(matrix: &Matrix3) -> (f32, f32, f32) {
+    let pitch = (-matrix[1][2]).clamp(-1.0, 1.0).asin();
+
+    if matrix[1][2].abs() < 1.0 - 1e-6 {
+        let yaw = matrix[0][2].atan2(matrix[2][2]);
+        let roll = matrix[1][0].atan2(matrix[1][1]);
+
+        (yaw, pitch, roll)
+    } else {
+        let yaw = (-matrix[2][0]).atan2(matrix[0][0]);
+
+        (yaw, pitch, 0.0)
+    }
+}
+
+/// Quaternion of the Euler angles `yaw`, `pitch` and `roll` in radians.
+///
+/// See [`euler_to_matrix`] for the order of the rotations.
+pub fn euler_to_quaternion(yaw: f32, pitch: f32, roll: f32) -> Quaternion {
+    let (sy, cy) = (yaw * 0.5).sin_cos();
+    let (sp, cp) = (pitch * 0.5).sin_cos();
+    let (sr, cr) = (roll * 0.5).sin_cos();
+
+    // q = q_yaw * q_pitch * q_roll
+    [
+        cy * sp * cr + sy * cp * sr,
+        sy * cp * cr - cy * sp * sr,
+        cy * cp * sr - sy * sp * cr,
+        cy * cp * cr + sy * sp * sr,
+    ]
+}
+
+/// Rotation matrix of the unit quaternion `quaternion`.
+pub fn quaternion_to_matrix(quaternion: &Quaternion) -> Matrix3 {
+    let [x, y, z, w] = *quaternion;
+
+    [
+        [
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y - z * w),
+            2.0 * (x * z + y * w),
+        ],
+        [
+            2.0 * (x * y + z * w),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z - x * w),
+        ],
+        [
+            2.0 * (x * z - y * w),
+            2.0 * (y * z + x * w),
+            1.0 - 2.0 * (x * x + y * y),
+        ],
+    ]
+}
+
+/// Euler angles `(yaw, pitch, roll)` in radians of the unit quaternion `quaternion`.
+pub fn quaternion_to_euler(quaternion: &Quaternion) -> (f32, f32, f32) {
+    matrix_to_euler(&quaternion_to_matrix(quaternion))
+}
+
+/// Rotate the vector `v` by the rotation matrix `matrix`.
+fn rotate(matrix: &Matrix3, v: &Vec3<f32>) -> Vec3<f32> {
+    let row = |row: &[f32; 3]| row[0] * v.x + row[1] * v.y + row[2] * v.z;
+
+    Vec3::new(row(&matrix[0]), row(&matrix[1]), row(&matrix[2]))
+}
+
+/// Offset from the minimum corner of the cells covered by a rotated block to the origin of the block.
+///
+/// `size` is the size of the block in grid cells when facing north.
+/// Blocks are rotated around the center of their footprint.
+fn pivot_offset(yaw: f32, size: Vec3<u8>) -> Vec3<f32> {
+    let center = Vec3::new(
+        size.x as f32 * BLOCK_SIZE.x / 2.0,
+        0.0,
+        size.z as f32 * BLOCK_SIZE.z / 2.0,
+    );
+    let matrix = euler_to_matrix(yaw, 0.0, 0.0);
+    let rotated = rotate(&matrix, &center);
+
+    // Half extents of the rotated footprint, which is centered on `rotated`.
+    let half = Vec3::new(
+        matrix[0][0].abs() * center.x + matrix[0][2].abs() * center.z,
+        0.0,
+        matrix[2][0].abs() * center.x + matrix[2][2].abs() * center.z,
+    );
+
+    half - rotated
+}
+
+impl Direction {
+    /// Yaw rotation in radians of a block facing this direction.
+    pub fn yaw(self) -> f32 {
+        -(u8::from(self) as f32) * FRAC_PI_2
+    }
+
+    /// Direction of the yaw rotation `yaw` in radians.
+    ///
+    /// Returns `None` if `yaw` is not a multiple of 90°.
+    pub fn from_yaw(yaw: f32) -> Option<Self> {
+        let steps = (-yaw / FRAC_PI_2).round();
+
+        if (-yaw / FRAC_PI_2 - steps).abs() > EPSILON {
+            return None;
+        }
+
+        Self::try_from(steps.rem_euclid(4.0) as u8).ok()
+    }
+}
+
+impl Block {
+    /// World position of the corner of the grid cell of the block.
+    pub fn world_pos(&self) -> Vec3<f32> {
+        grid_to_world(self.coord)
+    }
+
+    /// Size of the block in grid cells as placed, starting at [`Block::coord`].
+    ///
+    /// `size` is the size of the block model in grid cells when facing north,
+    /// its width and depth are swapped for blocks facing east or west.
+    /// Sizes of zero are treated as one.
+    pub fn footprint(&self, size: Vec3<u8>) -> Vec3<u8> {
+        let size = Vec3::new(size.x.max(1), size.y.max(1), size.z.max(1));
+
+        match self.dir {
+            Direction::North | Direction::South => size,
+            Direction::East | Direction::West => Vec3::new(size.z, size.y, size.x),
+        }
+    }
+
+    /// Convert the block into a free block at the same place.
+    ///
+    /// `size` is the size of the block model in grid cells when facing north,
+    /// which is needed to find the origin of rotated blocks spanning multiple cells.
+    ///
+    /// Free blocks cannot be ghosts, ground blocks or use a variant,
+    /// so `is_ghost`, `is_ground` and `variant_index` are dropped.
+    pub fn to_free_block(&self, size: Vec3<u8>) -> FreeBlock {
+        let yaw = self.dir.yaw();
+
+        FreeBlock {
+            model_id: self.model_id.clone(),
+            skin: self.skin.clone(),
+            waypoint_property: self.waypoint_property.clone(),
+            pos: self.world_pos() + pivot_offset(yaw, size),
+            yaw,
+            pitch: 0.0,
+            roll: 0.0,
+            color: self.color,
+            lightmap_quality: self.lightmap_quality,
+        }
+    }
+}
+
+impl FreeBlock {
+    /// Rotation matrix of the free block.
+    pub fn rotation_matrix(&self) -> Matrix3 {
+        euler_to_matrix(self.yaw, self.pitch, self.roll)
+    }
+
+    /// Rotation quaternion of the free block.
+    pub fn rotation_quaternion(&self) -> Quaternion {
+        euler_to_quaternion(self.yaw, self.pitch, self.roll)
+    }
+
+    /// Convert the free block into a block at the same place.
+    ///
+    /// `size` is the size of the block model in grid cells when facing north.
+    /// Returns `None` if the free block does not snap to the grid,
+    /// i.e. if it is pitched or rolled, its yaw is not a multiple of 90°
+    /// or its position is not on the corner of a grid cell.
+    /// See [`Block::to_free_block`] for the inverse.
+    pub fn to_block(&self, size: Vec3<u8>) -> Option<Block> {
+        if self.pitch.abs() > EPSILON || self.roll.abs() > EPSILON {
+            return None;
+        }
+
+        let dir = Direction::from_yaw(self.yaw)?;
+        let corner = self.pos.clone() - pivot_offset(dir.yaw(), size);
+        let coord = world_to_grid(&corner)?;
+        let snapped = grid_to_world(coord);
+
+        let is_on_grid = (corner.x - snapped.x).abs() <= EPSILON
+            && (corner.y - snapped.y).abs() <= EPSILON
+            && (corner.z - snapped.z).abs() <= EPSILON;
+
+        if !is_on_grid {
+            return None;
+        }
+
+        Some(Block {
+            model_id: self.model_id.clone(),
+            dir,
+            coord,
+            skin: self.skin.clone(),
+            waypoint_property: self.waypoint_property.clone(),
+            color: self.color,
+            lightmap_quality: self.lightmap_quality,
+            ..Default::default()
+        })
+    }
+}
+
+impl Item {
+    /// Rotation matrix of the item.
+    pub fn rotation_matrix(&self) -> Matrix3 {
+        euler_to_matrix(self.yaw, self.pitch, self.roll)
+    }
+
+    /// Rotation quaternion of the item.
+    pub fn rotation_quaternion(&self) -> Quaternion {
+        euler_to_quaternion(self.yaw, self.pitch, self.roll)
+    }
+
+    /// Set the rotation of the item from a rotation matrix.
+    pub fn set_rotation_matrix(&mut self, matrix: &Matrix3) {
+        let (yaw, pitch, roll) = matrix_to_euler(matrix);
+
+        self.yaw = yaw;
+        self.pitch = pitch;
+        self.roll = roll;
+    }
+}
@@ -0,0 +1,174 @@
+use super::Dependency;
+use crate::read;
+use quick_xml::events::{BytesStart, Event};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Attributes of the XML header of a map.
+#[derive(Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct XmlHeader {
+    /// Version of the game executable which saved the map, e.g. `3.3.0`.
+    pub exe_version: Option<String>,
+    /// Build of the game executable which saved the map, e.g. `2023-01-26_15_32`.
+    pub exe_build: Option<String>,
+    /// Title of the map, e.g. `TMStadium`.
+    pub title: Option<String>,
+    /// Version of the lightmap.
+    pub lightmap: Option<u32>,
+    /// UID of the map.
+    pub uid: Option<String>,
+    /// Name of the map.
+    pub name: Option<String>,
+    /// Unique ID of the map author.
+    pub author: Option<String>,
+    /// Zone of the map author.
+    pub author_zone: Option<String>,
+    /// Environment of the map, e.g. `Stadium`.
+    pub environment: Option<String>,
+    /// Mood of the map, e.g. `Day`.
+    pub mood: Option<String>,
+    /// Type of the map, e.g. `Race`.
+    pub kind: Option<String>,
+    /// Script type of the map, e.g. `TrackMania\TM_Race`.
+    pub map_type: Option<String>,
+    /// Style of the map.
+    pub map_style: Option<String>,
+    /// `true` if the map is validated.
+    pub is_validated: Option<bool>,
+    /// Number of laps.
+    pub num_laps: Option<u32>,
+    /// Display cost of the map.
+    pub display_cost: Option<u32>,
+    /// Name of the texture mod.
+    pub texture_mod: Option<String>,
+    /// `true` if the map has ghost blocks.
+    pub has_ghost_blocks: Option<bool>,
+    /// ID of the player model.
+    pub player_model: Option<String>,
+    /// Bronze medal time in milliseconds, `None` if not set.
+    pub bronze_time: Option<u32>,
+    /// Silver medal time in milliseconds, `None` if not set.
+    pub silver_time: Option<u32>,
+    /// Gold medal time in milliseconds, `None` if not set.
+    pub gold_time: Option<u32>,
+    /// Author medal time in milliseconds, `None` if not set.
+    pub author_time: Option<u32>,
+    /// Author score.
+    pub author_score: Option<u32>,
+    /// Dependencies of the map.
+    ///
+    /// When writing, the dependencies are generated by [`Map::header_deps`](super::Map::header_deps).
+    pub deps: Vec<Dependency>,
+    /// All attributes of all elements by element name, including unknown elements.
+    ///
+    /// If an element occurs multiple times, only the attributes of the last occurence are kept.
+    pub attributes: HashMap<String, HashMap<String, String>>,
+}
+
+/// Parse a numeric attribute, `None` if it is missing or invalid.
+fn parse_attribute<T: FromStr>(attributes: &HashMap<String, String>, name: &str) -> Option<T> {
+    attributes.get(name)?.trim().parse().ok()
+}
+
+fn parse_bool_attribute(attributes: &HashMap<String, String>, name: &str) -> Option<bool> {
+    parse_attribute::<i32>(attributes, name).map(|value| value != 0)
+}
+
+/// Parse a medal time, where `-1` means that the time is not set.
+fn parse_time_attribute(attributes: &HashMap<String, String>, name: &str) -> Option<u32> {
+    parse_attribute::<i64>(attributes, name).and_then(|time| u32::try_from(time).ok())
+}
+
+fn element_attributes(element: &BytesStart) -> read::Result<(String, HashMap<String, String>)> {
+    let name = String::from_utf8_lossy(element.local_name().as_ref()).into_owned();
+    let attributes = element
+        .attributes()
+        .map(|attribute| {
+            let attribute = attribute.map_err(|err| read::Error(format!("{err}")))?;
+            let key = String::from_utf8_lossy(attribute.key.local_name().as_ref()).into_owned();
+            let value = attribute
+                .unescape_value()
+                .map_err(|err| read::Error(format!("{err}")))?
+                .into_owned();
+
+            Ok((key, value))
+        })
+        .collect::<read::Result<_>>()?;
+
+    Ok((name, attributes))
+}
+
+impl XmlHeader {
+    /// Parse the XML header.
+    ///
+    /// Elements may occur in any order, unknown elements, comments and missing attributes are ignored.
+    /// Numeric attributes which cannot be parsed are `None`, but kept in [`XmlHeader::attributes`].
+    /// Returns an error if `xml` is not well-formed.
+    pub fn parse(xml: &str) -> read::Result<Self> {
+        let mut header = Self::default();
+        let mut xml_reader = quick_xml::Reader::from_str(xml);
+
+        loop {
+            let element = match xml_reader
+                .read_event()
+                .map_err(|err| read::Error(format!("invalid xml header: {err}")))?
+            {
+                Event::Start(element) | Event::Empty(element) => element,
+                Event::Eof => break,
+                _ => continue,
+            };
+
+            let (name, attributes) = element_attributes(&element)?;
+
+            match name.as_str() {
+                "header" => {
+                    header.exe_version = attributes.get("exever").cloned();
+                    header.exe_build = attributes.get("exebuild").cloned();
+                    header.title = attributes.get("title").cloned();
+                    header.lightmap = parse_attribute(&attributes, "lightmap");
+                }
+                "ident" => {
+                    header.uid = attributes.get("uid").cloned();
+                    header.name = attributes.get("name").cloned();
+                    header.author = attributes.get("author").cloned();
+                    header.author_zone = attributes.get("authorzone").cloned();
+                }
+                "desc" => {
+                    header.environment = attributes.get("envir").cloned();
+                    header.mood = attributes.get("mood").cloned();
+                    header.kind = attributes.get("type").cloned();
+                    header.map_type = attributes.get("maptype").cloned();
+                    header.map_style = attributes.get("mapstyle").cloned();
+                    header.is_validated = parse_bool_attribute(&attributes, "validated");
+                    header.num_laps = parse_attribute(&attributes, "nblaps");
+                    header.display_cost = parse_attribute(&attributes, "displaycost");
+                    header.texture_mod = attributes.get("mod").cloned();
+                    header.has_ghost_blocks = parse_bool_attribute(&attributes, "hasghostblocks");
+                }
+                "playermodel" => {
+                    header.player_model = attributes.get("id").cloned();
+                }
+                "times" => {
+                    header.bronze_time = parse_time_attribute(&attributes, "bronze");
+                    header.silver_time = parse_time_attribute(&attributes, "silver");
+                    header.gold_time = parse_time_attribute(&attributes, "gold");
+                    header.author_time = parse_time_attribute(&attributes, "authortime");
+                    header.author_score = parse_attribute(&attributes, "authorscore");
+                }
+                "dep" => {
+                    header.deps.push(Dependency {
+                        file: attributes.get("file").cloned().unwrap_or_default(),
+                        url: attributes.get("url").filter(|url| !url.is_empty()).cloned(),
+                    });
+                    continue;
+                }
+                _ => {}
+            }
+
+            header.attributes.insert(name, attributes);
+        }
+
+        Ok(header)
+    }
+}
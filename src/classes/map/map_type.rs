@@ -0,0 +1,184 @@
+use super::{Map, RoyalOrder, WaypointProperty};
+use std::error;
+use std::fmt::{self, Display};
+
+/// Script type of a map.
+#[derive(Clone, PartialEq, Eq, Hash, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MapType {
+    /// Race map, `TrackMania\TM_Race`.
+    #[default]
+    Race,
+    /// Royal map, `TrackMania\TM_Royal`.
+    Royal,
+    /// Platform map, `TrackMania\TM_Platform`.
+    Platform,
+    /// Stunt map, `TrackMania\TM_Stunt`.
+    Stunt,
+    /// Any other map type, e.g. of a custom game mode.
+    Custom(String),
+}
+
+impl MapType {
+    /// Script name of the map type, e.g. `TrackMania\TM_Race`.
+    pub fn as_str(&self) -> &str {
+        match *self {
+            Self::Race => "TrackMania\\TM_Race",
+            Self::Royal => "TrackMania\\TM_Royal",
+            Self::Platform => "TrackMania\\TM_Platform",
+            Self::Stunt => "TrackMania\\TM_Stunt",
+            Self::Custom(ref map_type) => map_type,
+        }
+    }
+
+    /// `true` if maps of this type need an author time to be valid.
+    pub fn needs_author_time(&self) -> bool {
+        matches!(*self, Self::Race | Self::Royal)
+    }
+}
+
+impl From<String> for MapType {
+    fn from(map_type: String) -> Self {
+        match map_type.as_str() {
+            "TrackMania\\TM_Race" => Self::Race,
+            "TrackMania\\TM_Royal" => Self::Royal,
+            "TrackMania\\TM_Platform" => Self::Platform,
+            "TrackMania\\TM_Stunt" => Self::Stunt,
+            _ => Self::Custom(map_type),
+        }
+    }
+}
+
+impl From<&str> for MapType {
+    fn from(map_type: &str) -> Self {
+        Self::from(String::from(map_type))
+    }
+}
+
+impl Display for MapType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Reason why a map is not valid for its map type.
+///
+/// Returned by [`Map::validate_map_type`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MapTypeError {
+    /// The map has no start waypoint.
+    MissingStart,
+    /// The map has no finish waypoint.
+    MissingFinish,
+    /// The map has no author time, but the map type needs one.
+    MissingAuthorTime,
+    /// A start or finish waypoint of a royal map has no order.
+    UnorderedRoyalWaypoint,
+    /// A royal map has no start waypoint of the given order.
+    MissingRoyalStart(RoyalOrder),
+    /// A royal map has no finish waypoint of the given order.
+    MissingRoyalFinish(RoyalOrder),
+}
+
+impl Display for MapTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::MissingStart => write!(f, "map has no start"),
+            Self::MissingFinish => write!(f, "map has no finish"),
+            Self::MissingAuthorTime => write!(f, "map has no author time"),
+            Self::UnorderedRoyalWaypoint => write!(f, "royal waypoint has no order"),
+            Self::MissingRoyalStart(order) => write!(f, "royal map has no {order:?} start"),
+            Self::MissingRoyalFinish(order) => write!(f, "royal map has no {order:?} finish"),
+        }
+    }
+}
+
+impl error::Error for MapTypeError {}
+
+const ROYAL_ORDERS: [RoyalOrder; 5] = [
+    RoyalOrder::White,
+    RoyalOrder::Green,
+    RoyalOrder::Blue,
+    RoyalOrder::Red,
+    RoyalOrder::Black,
+];
+
+impl Map {
+    /// Check whether the map satisfies the requirements of its [`Map::map_type`].
+    ///
+    /// Race, royal, platform and stunt maps need a start and a finish, multilap waypoints count as both.
+    /// Race and royal maps also need an author time.
+    /// Royal maps need a start and a finish of each [`RoyalOrder`] instead, and all of their start
+    /// and finish waypoints must have an order.
+    /// Custom map types are not checked.
+    pub fn validate_map_type(&self) -> Result<(), MapTypeError> {
+        if let MapType::Custom(_) = self.map_type {
+            return Ok(());
+        }
+
+        let waypoints: Vec<&WaypointProperty> = self
+            .blocks
+            .iter()
+            .filter_map(|block| block.waypoint_property())
+            .chain(
+                self.items
+                    .iter()
+                    .filter_map(|item| item.waypoint_property.as_ref()),
+            )
+            .collect();
+
+        let has_start = |order: Option<RoyalOrder>| {
+            waypoints.iter().any(|waypoint| match **waypoint {
+                WaypointProperty::Start { order: o }
+                | WaypointProperty::StartFinish { order: o } => order.is_none() || o == order,
+                _ => false,
+            })
+        };
+        let has_finish = |order: Option<RoyalOrder>| {
+            waypoints.iter().any(|waypoint| match **waypoint {
+                WaypointProperty::Finish { order: o }
+                | WaypointProperty::StartFinish { order: o } => order.is_none() || o == order,
+                _ => false,
+            })
+        };
+
+        if !has_start(None) {
+            return Err(MapTypeError::MissingStart);
+        }
+
+        if !has_finish(None) {
+            return Err(MapTypeError::MissingFinish);
+        }
+
+        if self.map_type == MapType::Royal {
+            let is_unordered = waypoints.iter().any(|waypoint| {
+                matches!(
+                    **waypoint,
+                    WaypointProperty::Start { order: None }
+                        | WaypointProperty::Finish { order: None }
+                        | WaypointProperty::StartFinish { order: None }
+                )
+            });
+
+            if is_unordered {
+                return Err(MapTypeError::UnorderedRoyalWaypoint);
+            }
+
+            for order in ROYAL_ORDERS {
+                if !has_start(Some(order)) {
+                    return Err(MapTypeError::MissingRoyalStart(order));
+                }
+
+                if !has_finish(Some(order)) {
+                    return Err(MapTypeError::MissingRoyalFinish(order));
+                }
+            }
+        }
+
+        if self.map_type.needs_author_time() && self.validation.is_none() {
+            return Err(MapTypeError::MissingAuthorTime);
+        }
+
+        Ok(())
+    }
+}
@@ -1,16 +1,83 @@
 use super::Rgb;
 use crate::Vec3;
+use num_enum::TryFromPrimitive;
+use std::ops::Range;
+
+/// Interpolation mode between two keys.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum Interpolation {
+    /// No interpolation, the value of the key is held until the next key.
+    None,
+    /// Smooth interpolation using the tangents of the keys.
+    #[default]
+    Hermite,
+    /// Linear interpolation.
+    Linear,
+    /// Smooth interpolation using fixed tangents.
+    FixedTangent,
+}
 
 /// Media block effect key.
-#[derive(Clone, Debug)]
-pub struct Effect;
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Effect {
+    /// Time of the key in seconds. [0.0, ∞)
+    pub time: f32,
+    /// Position on the screen. [-1.0, 1.0]
+    pub position: [f32; 2],
+    /// Rotation in radians.
+    pub rotation: f32,
+    /// Scale on the screen.
+    pub scale: [f32; 2],
+    /// Opacity. [0.0, 1.0]
+    pub opacity: f32,
+    /// Depth. [0.0, 1.0]
+    pub depth: f32,
+}
+
+/// Color parameters of a color media block key.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ColorParams {
+    /// Amount of color inversion. [0.0, 1.0]
+    pub inverse: f32,
+    /// Hue. [0.0, 1.0]
+    pub hue: f32,
+    /// Saturation. [-1.0, 1.0]
+    pub saturation: f32,
+    /// Brightness. [-1.0, 1.0]
+    pub brightness: f32,
+    /// Contrast. [-1.0, 1.0]
+    pub contrast: f32,
+    /// Color which is blended.
+    pub color: Rgb,
+}
 
 /// Color media block key.
-#[derive(Clone, Debug)]
-pub struct Color;
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Color {
+    /// Time of the key in seconds. [0.0, ∞)
+    pub time: f32,
+    /// Intensity of the effect. [0.0, 1.0]
+    pub intensity: f32,
+    /// Blend factor between the near and the far color parameters.
+    pub blend_z: f32,
+    /// Distance of the near color parameters.
+    pub distance: f32,
+    /// Distance of the far color parameters.
+    pub far_distance: f32,
+    /// Color parameters near the camera.
+    pub near: ColorParams,
+    /// Color parameters far from the camera.
+    pub far: ColorParams,
+}
 
 /// Time media block key.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Time {
     /// Time of the key in seconds. [0.0, ∞)
     pub time: f32,
@@ -18,20 +85,154 @@ pub struct Time {
     pub tangent: f32,
 }
 
+/// 2D or 3D triangles media block key.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Triangles {
+    /// Time of the key in seconds. [0.0, ∞)
+    pub time: f32,
+    /// Position of every vertex of the media block at this key.
+    pub positions: Vec<Vec3<f32>>,
+}
+
+/// Orbital camera media block key.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrbitalCamera {
+    /// Time of the key in seconds. [0.0, ∞)
+    pub time: f32,
+    /// Position the camera orbits around, relative to the anchor if any.
+    pub center: Vec3<f32>,
+    /// Horizontal angle of the camera around the center in radians.
+    pub horizontal_angle: f32,
+    /// Vertical angle of the camera around the center in radians.
+    pub vertical_angle: f32,
+    /// Distance of the camera to the center.
+    pub distance: f32,
+    /// Vertical field of view of the camera in degrees.
+    pub fov: f32,
+    /// Distance of the near clipping plane.
+    pub z_near: f32,
+    /// `true` if the camera also follows the rotation of the anchor.
+    pub anchor_rotation: bool,
+    /// Optional index of the player the camera is anchored to, `Some(0)` is the local player.
+    pub anchor: Option<u32>,
+    /// `true` if the anchor is visible.
+    pub show_anchor: bool,
+    /// Optional index of the player the camera is targeting, `Some(0)` is the local player.
+    pub target: Option<u32>,
+    /// Position the camera is targeting, relative to the target if any.
+    pub target_position: Vec3<f32>,
+}
+
+/// Path camera media block key.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PathCamera {
+    /// Time of the key in seconds. [0.0, ∞)
+    pub time: f32,
+    /// Absolute position of the camera.
+    pub position: Vec3<f32>,
+    /// Pitch rotation of the camera in radians.
+    pub pitch: f32,
+    /// Yaw rotation of the camera in radians.
+    pub yaw: f32,
+    /// Roll rotation of the camera in radians.
+    pub roll: f32,
+    /// Vertical field of view of the camera in degrees.
+    pub fov: f32,
+    /// Distance of the near clipping plane.
+    pub z_near: f32,
+    /// `true` if the camera also follows the rotation of the anchor.
+    pub anchor_rotation: bool,
+    /// Optional index of the player the camera is anchored to, `Some(0)` is the local player.
+    pub anchor: Option<u32>,
+    /// `true` if the anchor is visible.
+    pub show_anchor: bool,
+    /// Optional index of the player the camera is targeting, `Some(0)` is the local player.
+    pub target: Option<u32>,
+    /// Absolute position the camera is targeting, relative to the target if any.
+    pub target_position: Vec3<f32>,
+    /// Weight of the key on the path.
+    pub weight: f32,
+    /// Incoming tangent of the position per second for hermite interpolation.
+    pub left_tangent: Vec3<f32>,
+    /// Outgoing tangent of the position per second for hermite interpolation.
+    pub right_tangent: Vec3<f32>,
+}
+
+/// Tangents per second of the values of a custom camera key for hermite interpolation.
+#[derive(Clone, PartialEq, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CameraTangent {
+    /// Tangent of the position.
+    pub position: Vec3<f32>,
+    /// Tangent of the pitch rotation.
+    pub pitch: f32,
+    /// Tangent of the yaw rotation.
+    pub yaw: f32,
+    /// Tangent of the roll rotation.
+    pub roll: f32,
+    /// Tangent of the vertical field of view.
+    pub fov: f32,
+    /// Tangent of the target position.
+    pub target_position: Vec3<f32>,
+    /// Tangent of the distance of the near clipping plane.
+    pub z_near: f32,
+}
+
 /// Custom camera media block key.
-#[derive(Clone, Debug)]
-pub struct CustomCamera;
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CustomCamera {
+    /// Time of the key in seconds. [0.0, ∞)
+    pub time: f32,
+    /// Interpolation towards the next key.
+    pub interpolation: Interpolation,
+    /// `true` if the camera also follows the rotation of the anchor.
+    pub anchor_rotation: bool,
+    /// Optional index of the player the camera is anchored to, `Some(0)` is the local player.
+    pub anchor: Option<u32>,
+    /// `true` if the anchor is visible.
+    pub show_anchor: bool,
+    /// Optional index of the player the camera is targeting, `Some(0)` is the local player.
+    pub target: Option<u32>,
+    /// Position of the camera, relative to the anchor if any.
+    pub position: Vec3<f32>,
+    /// Pitch rotation of the camera in radians.
+    pub pitch: f32,
+    /// Yaw rotation of the camera in radians.
+    pub yaw: f32,
+    /// Roll rotation of the camera in radians.
+    pub roll: f32,
+    /// Vertical field of view of the camera in degrees.
+    pub fov: f32,
+    /// Position the camera is targeting, relative to the target if any.
+    pub target_position: Vec3<f32>,
+    /// Distance of the near clipping plane.
+    pub z_near: f32,
+    /// Incoming tangents for hermite interpolation.
+    pub left_tangent: CameraTangent,
+    /// Outgoing tangents for hermite interpolation.
+    pub right_tangent: CameraTangent,
+}
 
 /// Camera shake effect media block key.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CameraShakeEffect {
+    /// Time of the key in seconds. [0.0, ∞)
+    pub time: f32,
     pub intensity: f32,
     pub speed: f32,
 }
 
 /// Music volume media block key.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MusicVolume {
+    /// Time of the key in seconds. [0.0, ∞)
+    pub time: f32,
     /// Music volume. [0.0, 1.0]
     pub music_volume: f32,
     /// Sound volume. [0.0, 1.0]
@@ -39,8 +240,11 @@ pub struct MusicVolume {
 }
 
 /// Sound media block key.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sound {
+    /// Time of the key in seconds. [0.0, ∞)
+    pub time: f32,
     /// Volume of the sound. [0.0, 1.0]
     pub volume: f32,
     /// Absolute position of the sound.
@@ -48,7 +252,8 @@ pub struct Sound {
 }
 
 /// Transition fade media block key.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TransitionFade {
     /// Time of the key in seconds. [0.0, ∞)
     pub time: f32,
@@ -56,17 +261,21 @@ pub struct TransitionFade {
 }
 
 /// Depth of field fade media block key.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DepthOfField {
     /// Time of the key in seconds. [0.0, ∞)
     pub time: f32,
     pub focus_distance: f32,
     pub lens_size: f32,
+    /// Optional index of the player which is focused, `Some(0)` is the local player.
+    pub target: Option<u32>,
     pub target_position: Vec3<f32>,
 }
 
 /// Tone mapping media block key.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ToneMapping {
     /// Time of the key in seconds. [0.0, ∞)
     pub time: f32,
@@ -76,15 +285,19 @@ pub struct ToneMapping {
 }
 
 /// Bloom media block key.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Bloom {
+    /// Time of the key in seconds. [0.0, ∞)
+    pub time: f32,
     pub intensity: f32,
     pub streaks_intensity: f32,
     pub streaks_attenuation: f32,
 }
 
 /// Time speed media block key.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TimeSpeed {
     /// Time of the key in seconds. [0.0, ∞)
     pub time: f32,
@@ -92,7 +305,8 @@ pub struct TimeSpeed {
 }
 
 /// Dirty lens media block key.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DirtyLens {
     /// Time of the key in seconds. [0.0, ∞)
     pub time: f32,
@@ -100,7 +314,8 @@ pub struct DirtyLens {
 }
 
 /// Color grading media block key.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ColorGrading {
     /// Time of the key in seconds. [0.0, ∞)
     pub time: f32,
@@ -108,7 +323,8 @@ pub struct ColorGrading {
 }
 
 /// Fog media block key.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Fog {
     /// Time of the key in seconds. [0.0, ∞)
     pub time: f32,
@@ -120,3 +336,350 @@ pub struct Fog {
     pub cloud_opacity: f32,
     pub cloud_speed: f32,
 }
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn lerp_vec3(a: &Vec3<f32>, b: &Vec3<f32>, t: f32) -> Vec3<f32> {
+    Vec3::new(lerp(a.x, b.x, t), lerp(a.y, b.y, t), lerp(a.z, b.z, t))
+}
+
+fn lerp_rgb(a: &Rgb, b: &Rgb, t: f32) -> Rgb {
+    Rgb {
+        red: lerp(a.red, b.red, t),
+        green: lerp(a.green, b.green, t),
+        blue: lerp(a.blue, b.blue, t),
+    }
+}
+
+/// Cubic hermite interpolation between `p0` and `p1` by the factor `t` in [0.0, 1.0].
+///
+/// The tangents `m0` and `m1` are per second and are scaled by the `span` of the keys in seconds.
+fn hermite(p0: f32, m0: f32, p1: f32, m1: f32, t: f32, span: f32) -> f32 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    let h00 = 2.0 * t3 - 3.0 * t2 + 1.0;
+    let h10 = t3 - 2.0 * t2 + t;
+    let h01 = -2.0 * t3 + 3.0 * t2;
+    let h11 = t3 - t2;
+
+    h00 * p0 + (h10 * m0 + h11 * m1) * span + h01 * p1
+}
+
+fn hermite_vec3(
+    p0: &Vec3<f32>,
+    m0: &Vec3<f32>,
+    p1: &Vec3<f32>,
+    m1: &Vec3<f32>,
+    t: f32,
+    span: f32,
+) -> Vec3<f32> {
+    Vec3::new(
+        hermite(p0.x, m0.x, p1.x, m1.x, t, span),
+        hermite(p0.y, m0.y, p1.y, m1.y, t, span),
+        hermite(p0.z, m0.z, p1.z, m1.z, t, span),
+    )
+}
+
+/// A key which can be interpolated.
+pub(crate) trait Key: Clone {
+    /// Time of the key in seconds.
+    fn time(&self) -> f32;
+
+    /// Mutable time of the key in seconds.
+    fn time_mut(&mut self) -> &mut f32;
+
+    /// Interpolate between `self` and the next key `next` by the factor `t` in [0.0, 1.0].
+    fn interpolate(&self, next: &Self, t: f32) -> Self;
+}
+
+/// Sample the given `keys` at `time`.
+///
+/// Before the first key and after the last key the value of the first and last key is held.
+/// Returns `None` if there are no keys.
+pub(crate) fn sample<K: Key>(keys: &[K], time: f32) -> Option<K> {
+    let first = keys.first()?;
+
+    let mut key = match keys.iter().position(|key| key.time() > time) {
+        Some(0) => first.clone(),
+        Some(index) => {
+            let prev = &keys[index - 1];
+            let next = &keys[index];
+            let span = next.time() - prev.time();
+            let t = if span > 0.0 {
+                (time - prev.time()) / span
+            } else {
+                0.0
+            };
+
+            prev.interpolate(next, t)
+        }
+        None => keys.last()?.clone(),
+    };
+
+    *key.time_mut() = time;
+
+    Some(key)
+}
+
+/// Trim the given `keys` to `range` using `sample` to create keys at the boundaries of the range.
+///
+/// Returns `false` if no keys remain.
+pub(crate) fn trim<K, F>(keys: &mut Vec<K>, range: &Range<f32>, sample: F) -> bool
+where
+    K: Key,
+    F: Fn(&[K], f32) -> Option<K>,
+{
+    let (first, last) = match (keys.first(), keys.last()) {
+        (Some(first), Some(last)) => (first.time(), last.time()),
+        _ => return false,
+    };
+
+    let overlaps = if first < last {
+        range.start < last && first < range.end
+    } else {
+        range.contains(&first)
+    };
+
+    if !overlaps {
+        keys.clear();
+        return false;
+    }
+
+    let start = range.start.max(first);
+    let end = range.end.min(last);
+    let start_key = sample(keys, start);
+    let end_key = sample(keys, end);
+
+    keys.retain(|key| start < key.time() && key.time() < end);
+    keys.splice(0..0, start_key);
+
+    if end > start {
+        keys.extend(end_key);
+    }
+
+    true
+}
+
+macro_rules! impl_key {
+    ($type:ident, |$a:ident, $b:ident, $t:ident| $interpolate:expr) => {
+        impl Key for $type {
+            fn time(&self) -> f32 {
+                self.time
+            }
+
+            fn time_mut(&mut self) -> &mut f32 {
+                &mut self.time
+            }
+
+            fn interpolate(&self, next: &Self, t: f32) -> Self {
+                let ($a, $b, $t) = (self, next, t);
+                $interpolate
+            }
+        }
+    };
+}
+
+impl_key!(Effect, |a, b, t| Effect {
+    time: lerp(a.time, b.time, t),
+    position: [
+        lerp(a.position[0], b.position[0], t),
+        lerp(a.position[1], b.position[1], t),
+    ],
+    rotation: lerp(a.rotation, b.rotation, t),
+    scale: [
+        lerp(a.scale[0], b.scale[0], t),
+        lerp(a.scale[1], b.scale[1], t),
+    ],
+    opacity: lerp(a.opacity, b.opacity, t),
+    depth: lerp(a.depth, b.depth, t),
+});
+
+impl ColorParams {
+    fn interpolate(&self, next: &Self, t: f32) -> Self {
+        Self {
+            inverse: lerp(self.inverse, next.inverse, t),
+            hue: lerp(self.hue, next.hue, t),
+            saturation: lerp(self.saturation, next.saturation, t),
+            brightness: lerp(self.brightness, next.brightness, t),
+            contrast: lerp(self.contrast, next.contrast, t),
+            color: lerp_rgb(&self.color, &next.color, t),
+        }
+    }
+}
+
+impl_key!(Color, |a, b, t| Color {
+    time: lerp(a.time, b.time, t),
+    intensity: lerp(a.intensity, b.intensity, t),
+    blend_z: lerp(a.blend_z, b.blend_z, t),
+    distance: lerp(a.distance, b.distance, t),
+    far_distance: lerp(a.far_distance, b.far_distance, t),
+    near: a.near.interpolate(&b.near, t),
+    far: a.far.interpolate(&b.far, t),
+});
+
+impl_key!(Time, |a, b, t| Time {
+    time: lerp(a.time, b.time, t),
+    time_value: lerp(a.time_value, b.time_value, t),
+    tangent: lerp(a.tangent, b.tangent, t),
+});
+
+impl_key!(Triangles, |a, b, t| Triangles {
+    time: lerp(a.time, b.time, t),
+    positions: a
+        .positions
+        .iter()
+        .zip(&b.positions)
+        .map(|(a, b)| lerp_vec3(a, b, t))
+        .collect(),
+});
+
+impl_key!(OrbitalCamera, |a, b, t| OrbitalCamera {
+    time: lerp(a.time, b.time, t),
+    center: lerp_vec3(&a.center, &b.center, t),
+    horizontal_angle: lerp(a.horizontal_angle, b.horizontal_angle, t),
+    vertical_angle: lerp(a.vertical_angle, b.vertical_angle, t),
+    distance: lerp(a.distance, b.distance, t),
+    fov: lerp(a.fov, b.fov, t),
+    z_near: lerp(a.z_near, b.z_near, t),
+    target_position: lerp_vec3(&a.target_position, &b.target_position, t),
+    ..a.clone()
+});
+
+impl_key!(PathCamera, |a, b, t| PathCamera {
+    time: lerp(a.time, b.time, t),
+    position: hermite_vec3(
+        &a.position,
+        &a.right_tangent,
+        &b.position,
+        &b.left_tangent,
+        t,
+        b.time - a.time,
+    ),
+    pitch: lerp(a.pitch, b.pitch, t),
+    yaw: lerp(a.yaw, b.yaw, t),
+    roll: lerp(a.roll, b.roll, t),
+    fov: lerp(a.fov, b.fov, t),
+    z_near: lerp(a.z_near, b.z_near, t),
+    target_position: lerp_vec3(&a.target_position, &b.target_position, t),
+    weight: lerp(a.weight, b.weight, t),
+    left_tangent: lerp_vec3(&a.left_tangent, &b.left_tangent, t),
+    right_tangent: lerp_vec3(&a.right_tangent, &b.right_tangent, t),
+    ..a.clone()
+});
+
+impl_key!(CustomCamera, |a, b, t| match a.interpolation {
+    Interpolation::None => a.clone(),
+    Interpolation::Linear => CustomCamera {
+        time: lerp(a.time, b.time, t),
+        position: lerp_vec3(&a.position, &b.position, t),
+        pitch: lerp(a.pitch, b.pitch, t),
+        yaw: lerp(a.yaw, b.yaw, t),
+        roll: lerp(a.roll, b.roll, t),
+        fov: lerp(a.fov, b.fov, t),
+        target_position: lerp_vec3(&a.target_position, &b.target_position, t),
+        z_near: lerp(a.z_near, b.z_near, t),
+        ..a.clone()
+    },
+    Interpolation::Hermite | Interpolation::FixedTangent => {
+        let (m0, m1) = (&a.right_tangent, &b.left_tangent);
+        let span = b.time - a.time;
+
+        CustomCamera {
+            time: lerp(a.time, b.time, t),
+            position: hermite_vec3(
+                &a.position,
+                &m0.position,
+                &b.position,
+                &m1.position,
+                t,
+                span,
+            ),
+            pitch: hermite(a.pitch, m0.pitch, b.pitch, m1.pitch, t, span),
+            yaw: hermite(a.yaw, m0.yaw, b.yaw, m1.yaw, t, span),
+            roll: hermite(a.roll, m0.roll, b.roll, m1.roll, t, span),
+            fov: hermite(a.fov, m0.fov, b.fov, m1.fov, t, span),
+            target_position: hermite_vec3(
+                &a.target_position,
+                &m0.target_position,
+                &b.target_position,
+                &m1.target_position,
+                t,
+                span,
+            ),
+            z_near: hermite(a.z_near, m0.z_near, b.z_near, m1.z_near, t, span),
+            ..a.clone()
+        }
+    }
+});
+
+impl_key!(CameraShakeEffect, |a, b, t| CameraShakeEffect {
+    time: lerp(a.time, b.time, t),
+    intensity: lerp(a.intensity, b.intensity, t),
+    speed: lerp(a.speed, b.speed, t),
+});
+
+impl_key!(MusicVolume, |a, b, t| MusicVolume {
+    time: lerp(a.time, b.time, t),
+    music_volume: lerp(a.music_volume, b.music_volume, t),
+    sound_volume: lerp(a.sound_volume, b.sound_volume, t),
+});
+
+impl_key!(Sound, |a, b, t| Sound {
+    time: lerp(a.time, b.time, t),
+    volume: lerp(a.volume, b.volume, t),
+    position: lerp_vec3(&a.position, &b.position, t),
+});
+
+impl_key!(TransitionFade, |a, b, t| TransitionFade {
+    time: lerp(a.time, b.time, t),
+    opacity: lerp(a.opacity, b.opacity, t),
+});
+
+impl_key!(DepthOfField, |a, b, t| DepthOfField {
+    time: lerp(a.time, b.time, t),
+    focus_distance: lerp(a.focus_distance, b.focus_distance, t),
+    lens_size: lerp(a.lens_size, b.lens_size, t),
+    target: a.target,
+    target_position: lerp_vec3(&a.target_position, &b.target_position, t),
+});
+
+impl_key!(ToneMapping, |a, b, t| ToneMapping {
+    time: lerp(a.time, b.time, t),
+    exposure: lerp(a.exposure, b.exposure, t),
+    max_hdr: lerp(a.max_hdr, b.max_hdr, t),
+    light_trail_scale: lerp(a.light_trail_scale, b.light_trail_scale, t),
+});
+
+impl_key!(Bloom, |a, b, t| Bloom {
+    time: lerp(a.time, b.time, t),
+    intensity: lerp(a.intensity, b.intensity, t),
+    streaks_intensity: lerp(a.streaks_intensity, b.streaks_intensity, t),
+    streaks_attenuation: lerp(a.streaks_attenuation, b.streaks_attenuation, t),
+});
+
+impl_key!(TimeSpeed, |a, b, t| TimeSpeed {
+    time: lerp(a.time, b.time, t),
+    speed: lerp(a.speed, b.speed, t),
+});
+
+impl_key!(DirtyLens, |a, b, t| DirtyLens {
+    time: lerp(a.time, b.time, t),
+    intensity: lerp(a.intensity, b.intensity, t),
+});
+
+impl_key!(ColorGrading, |a, b, t| ColorGrading {
+    time: lerp(a.time, b.time, t),
+    intensity: lerp(a.intensity, b.intensity, t),
+});
+
+impl_key!(Fog, |a, b, t| Fog {
+    time: lerp(a.time, b.time, t),
+    intensity: lerp(a.intensity, b.intensity, t),
+    sky_intensity: lerp(a.sky_intensity, b.sky_intensity, t),
+    distance: lerp(a.distance, b.distance, t),
+    color: lerp_rgb(&a.color, &b.color, t),
+    cloud_opacity: lerp(a.cloud_opacity, b.cloud_opacity, t),
+    cloud_speed: lerp(a.cloud_speed, b.cloud_speed, t),
+});
@@ -3,16 +3,42 @@ pub mod key;
 
 use crate::ghost::EntityRecord;
 use crate::read::{self, Reader};
-use crate::{FileRef, InternalFileRef, Rgb};
+use crate::{FileRef, Id, InternalFileRef, Rgb, Vec3};
+use key::Key;
 use num_enum::TryFromPrimitive;
 use std::borrow::BorrowMut;
 use std::io::{Read, Seek};
+use std::ops::Range;
+
+fn optional_index(index: u32) -> Option<u32> {
+    (index != 0xFFFFFFFF).then_some(index)
+}
+
+fn read_rgb<R, I, N>(r: &mut Reader<R, I, N>) -> read::Result<Rgb>
+where
+    R: Read,
+{
+    let red = r.f32()?;
+    let green = r.f32()?;
+    let blue = r.f32()?;
+
+    Ok(Rgb { red, green, blue })
+}
 
 /// Effect of a media block.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Effect {
     /// Keys of the effect.
     pub keys: Vec<key::Effect>,
+    /// `true` if the effect is centered on the screen.
+    pub is_centered: bool,
+    /// Color blend mode of the effect.
+    pub color_blend_mode: u32,
+    /// `true` if the effect is continuous.
+    pub is_continuous: bool,
+    /// `true` if the keys of the effect are interpolated.
+    pub is_interpolated: bool,
 }
 
 impl Effect {
@@ -22,35 +48,66 @@ impl Effect {
     {
         r.chunk_id(0x07010005)?;
         let keys = r.list(|r| {
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
+            let time = r.f32()?;
+            let position = r.vec2f32()?;
+            let rotation = r.f32()?;
+            let scale = r.vec2f32()?;
+            let opacity = r.f32()?;
+            let depth = r.f32()?;
             r.u32()?;
             r.u32()?;
             r.u32()?;
             r.u32()?;
 
-            Ok(key::Effect)
+            Ok(key::Effect {
+                time,
+                position,
+                rotation,
+                scale,
+                opacity,
+                depth,
+            })
         })?;
-        r.u32()?;
-        r.u32()?;
-        r.u32()?;
-        r.u32()?;
+        let is_centered = r.bool32()?;
+        let color_blend_mode = r.u32()?;
+        let is_continuous = r.bool32()?;
+        let is_interpolated = r.bool32()?;
 
         r.node_end()?;
 
-        Ok(Self { keys })
+        Ok(Self {
+            keys,
+            is_centered,
+            color_blend_mode,
+            is_continuous,
+            is_interpolated,
+        })
     }
 }
 
+/// Color of a vertex of a triangles media block.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VertexColor {
+    /// Color of the vertex.
+    pub color: Rgb,
+    /// Opacity of the vertex. [0.0, 1.0]
+    pub opacity: f32,
+}
+
 /// 2D or 3D triangles media block.
-#[derive(Clone)]
-pub struct Triangles;
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Triangles {
+    /// Keys of the media block.
+    ///
+    /// Every key contains a position for each vertex in `vertex_colors`.
+    pub keys: Vec<key::Triangles>,
+    /// Color of every vertex.
+    pub vertex_colors: Vec<VertexColor>,
+    /// Vertex indices of every triangle.
+    pub triangles: Vec<[u32; 3]>,
+}
 
 impl Triangles {
     pub(crate) fn read<R, I, N>(r: &mut Reader<R, I, N>) -> read::Result<Self>
@@ -58,38 +115,24 @@ impl Triangles {
         R: Read + Seek,
     {
         r.chunk_id(0x03029001)?;
-        let _keys = r.list(|r| {
-            r.u32()?;
-
-            Ok(())
-        })?;
+        let times = r.list(|r| r.f32())?;
         let num_keys = r.u32()?;
         let num_vertices = r.u32()?;
-        r.repeat(num_keys as usize, |r| {
-            r.repeat(num_vertices as usize, |r| {
-                r.u32()?;
-                r.u32()?;
-                r.u32()?;
-
-                Ok(())
-            })?;
-
-            Ok(())
+        let positions = r.repeat(num_keys as usize, |r| {
+            r.repeat(num_vertices as usize, |r| r.vec3f32())
         })?;
-        r.list(|r| {
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
+        let vertex_colors = r.list(|r| {
+            let color = read_rgb(r)?;
+            let opacity = r.f32()?;
 
-            Ok(())
+            Ok(VertexColor { color, opacity })
         })?;
-        r.list(|r| {
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
+        let triangles = r.list(|r| {
+            let a = r.u32()?;
+            let b = r.u32()?;
+            let c = r.u32()?;
 
-            Ok(())
+            Ok([a, b, c])
         })?;
         r.u32()?;
         r.u32()?;
@@ -101,12 +144,29 @@ impl Triangles {
 
         r.skip_optional_chunk(0x03029002)?;
 
-        Ok(Self)
+        if times.len() != positions.len() {
+            return Err(read::Error(String::from(
+                "number of triangles keys does not match",
+            )));
+        }
+
+        let keys = times
+            .into_iter()
+            .zip(positions)
+            .map(|(time, positions)| key::Triangles { time, positions })
+            .collect();
+
+        Ok(Self {
+            keys,
+            vertex_colors,
+            triangles,
+        })
     }
 }
 
 /// Color media block.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Color {
     /// Keys of the media block.
     pub keys: Vec<key::Color>,
@@ -119,46 +179,63 @@ impl Color {
     {
         r.chunk_id(0x03080003)?;
         let keys = r.list(|r| {
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
+            let time = r.f32()?;
+            let intensity = r.f32()?;
+            let blend_z = r.f32()?;
+            let distance = r.f32()?;
+            let far_distance = r.f32()?;
+            let near = read_color_params(r)?;
+            let far = read_color_params(r)?;
 
-            Ok(key::Color)
+            Ok(key::Color {
+                time,
+                intensity,
+                blend_z,
+                distance,
+                far_distance,
+                near,
+                far,
+            })
         })?;
 
         Ok(Self { keys })
     }
 }
 
+fn read_color_params<R, I, N>(r: &mut Reader<R, I, N>) -> read::Result<key::ColorParams>
+where
+    R: Read,
+{
+    let inverse = r.f32()?;
+    let hue = r.f32()?;
+    let saturation = r.f32()?;
+    let brightness = r.f32()?;
+    let contrast = r.f32()?;
+    let color = read_rgb(r)?;
+    r.u32()?;
+    r.u32()?;
+    r.u32()?;
+    r.u32()?;
+
+    Ok(key::ColorParams {
+        inverse,
+        hue,
+        saturation,
+        brightness,
+        contrast,
+        color,
+    })
+}
+
 /// Motion blur media block.
-#[derive(Clone)]
-pub struct MotionBlur;
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MotionBlur {
+    /// Start time of the block in seconds. [0.0, ∞)
+    pub start_time: f32,
+    /// End time of the block in seconds. [0.0, ∞)
+    pub end_time: f32,
+}
 
 impl MotionBlur {
     pub(crate) fn read<R, I, N>(r: &mut Reader<R, I, N>) -> read::Result<Self>
@@ -166,16 +243,39 @@ impl MotionBlur {
         R: Read,
     {
         r.chunk_id(0x03082000)?;
-        r.u32()?;
-        r.u32()?;
+        let start_time = r.f32()?;
+        let end_time = r.f32()?;
 
-        Ok(Self)
+        Ok(Self {
+            start_time,
+            end_time,
+        })
     }
 }
 
 /// Player camera media block.
-#[derive(Clone)]
-pub struct PlayerCamera;
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlayerCamera {
+    /// Start time of the block in seconds. [0.0, ∞)
+    pub start_time: f32,
+    /// End time of the block in seconds. [0.0, ∞)
+    pub end_time: f32,
+    /// Index of the game camera which is used.
+    pub camera: u32,
+    /// Optional index of the player which is followed, `Some(0)` is the local player.
+    pub target: Option<u32>,
+    /// Position of the camera relative to the player.
+    pub position: Vec3<f32>,
+    /// Pitch rotation of the camera in radians.
+    pub pitch: f32,
+    /// Yaw rotation of the camera in radians.
+    pub yaw: f32,
+    /// Roll rotation of the camera in radians.
+    pub roll: f32,
+    /// Vertical field of view of the camera in degrees.
+    pub fov: f32,
+}
 
 impl PlayerCamera {
     pub(crate) fn read<R, I, N>(r: &mut Reader<R, I, N>) -> read::Result<Self>
@@ -184,17 +284,15 @@ impl PlayerCamera {
     {
         r.chunk_id(0x03084007)?;
         r.u32()?;
-        r.u32()?;
-        r.u32()?;
-        r.u32()?;
-        r.u32()?;
-        r.u32()?;
-        r.u32()?;
-        r.u32()?;
-        r.u32()?;
-        r.u32()?;
-        r.u32()?;
-        r.u32()?;
+        let start_time = r.f32()?;
+        let end_time = r.f32()?;
+        let camera = r.u32()?;
+        let target = optional_index(r.u32()?);
+        let position = r.vec3f32()?;
+        let pitch = r.f32()?;
+        let yaw = r.f32()?;
+        let roll = r.f32()?;
+        let fov = r.f32()?;
         r.u32()?;
         r.u32()?;
         r.u32()?;
@@ -205,12 +303,23 @@ impl PlayerCamera {
         r.u32()?;
         r.u32()?;
 
-        Ok(Self)
+        Ok(Self {
+            start_time,
+            end_time,
+            camera,
+            target,
+            position,
+            pitch,
+            yaw,
+            roll,
+            fov,
+        })
     }
 }
 
 /// Time media block.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Time {
     /// Keys of the media block.
     pub keys: Vec<key::Time>,
@@ -239,8 +348,12 @@ impl Time {
 }
 
 /// Orbital camera media block
-#[derive(Clone)]
-pub struct OrbitalCamera;
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrbitalCamera {
+    /// Keys of the media block.
+    pub keys: Vec<key::OrbitalCamera>,
+}
 
 impl OrbitalCamera {
     pub(crate) fn read<R, I, N>(r: &mut Reader<R, I, N>) -> read::Result<Self>
@@ -249,34 +362,48 @@ impl OrbitalCamera {
     {
         r.chunk_id(0x030A0001)?;
         r.u32()?;
-        r.list(|r| {
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
+        let keys = r.list(|r| {
+            let time = r.f32()?;
+            let center = r.vec3f32()?;
+            let horizontal_angle = r.f32()?;
+            let vertical_angle = r.f32()?;
+            let distance = r.f32()?;
+            let fov = r.f32()?;
+            let z_near = r.f32()?;
+            let anchor_rotation = r.bool32()?;
+            let anchor = optional_index(r.u32()?);
+            let show_anchor = r.bool32()?;
+            let target = optional_index(r.u32()?);
+            let target_position = r.vec3f32()?;
             r.u8()?;
 
-            Ok(())
+            Ok(key::OrbitalCamera {
+                time,
+                center,
+                horizontal_angle,
+                vertical_angle,
+                distance,
+                fov,
+                z_near,
+                anchor_rotation,
+                anchor,
+                show_anchor,
+                target,
+                target_position,
+            })
         })?;
 
-        Ok(Self)
+        Ok(Self { keys })
     }
 }
 
 /// Path camera media block.
-#[derive(Clone)]
-pub struct PathCamera;
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PathCamera {
+    /// Keys of the media block.
+    pub keys: Vec<key::PathCamera>,
+}
 
 impl PathCamera {
     pub(crate) fn read<R, I, N>(r: &mut Reader<R, I, N>) -> read::Result<Self>
@@ -285,40 +412,49 @@ impl PathCamera {
     {
         r.chunk_id(0x030A1003)?;
         r.u32()?; // 5
-        let _keys = r.list(|r| {
-            r.u32()?; // 0
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
+        let keys = r.list(|r| {
+            let time = r.f32()?;
+            let position = r.vec3f32()?;
+            let pitch = r.f32()?;
+            let yaw = r.f32()?;
+            let roll = r.f32()?;
+            let fov = r.f32()?;
+            let z_near = r.f32()?;
+            let anchor_rotation = r.bool32()?;
+            let anchor = optional_index(r.u32()?);
+            let show_anchor = r.bool32()?;
+            let target = optional_index(r.u32()?);
+            let target_position = r.vec3f32()?;
+            let weight = r.f32()?;
+            let left_tangent = r.vec3f32()?;
+            let right_tangent = r.vec3f32()?;
 
-            Ok(())
+            Ok(key::PathCamera {
+                time,
+                position,
+                pitch,
+                yaw,
+                roll,
+                fov,
+                z_near,
+                anchor_rotation,
+                anchor,
+                show_anchor,
+                target,
+                target_position,
+                weight,
+                left_tangent,
+                right_tangent,
+            })
         })?;
 
-        Ok(Self)
+        Ok(Self { keys })
     }
 }
 
 /// Custom camera media block.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CustomCamera {
     /// Keys of the media block.
     pub keys: Vec<key::CustomCamera>,
@@ -332,55 +468,64 @@ impl CustomCamera {
         r.chunk_id(0x030A2006)?;
         r.u32()?;
         let keys = r.list(|r| {
-            let _time = r.f32()?;
-            let _interpolation = r.u32()?;
-            let _anchor_rotation = r.bool32()?;
-            let _anchor = r.u32()?; // 0xFFFFFFFF = None, 0 = Local Player
-            let _show_anchor = r.bool32()?;
-            let _target = r.u32()?; // 0xFFFFFFFF = None, 0 = Local Player
-            let _x = r.f32()?;
-            let _y = r.f32()?;
-            let _z = r.f32()?;
-            let _pitch = r.f32()?;
-            let _yaw = r.f32()?;
-            let _roll = r.f32()?;
-            let _fov = r.f32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            let _z_near = r.f32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
-            r.u32()?;
+            let time = r.f32()?;
+            let interpolation = key::Interpolation::try_from(r.u32()?)
+                .map_err(|_| read::Error(String::from("invalid interpolation")))?;
+            let anchor_rotation = r.bool32()?;
+            let anchor = optional_index(r.u32()?);
+            let show_anchor = r.bool32()?;
+            let target = optional_index(r.u32()?);
+            let position = r.vec3f32()?;
+            let pitch = r.f32()?;
+            let yaw = r.f32()?;
+            let roll = r.f32()?;
+            let fov = r.f32()?;
+            let target_position = r.vec3f32()?;
+            let z_near = r.f32()?;
+            let left_tangent = read_camera_tangent(r)?;
+            let right_tangent = read_camera_tangent(r)?;
 
-            Ok(key::CustomCamera)
+            Ok(key::CustomCamera {
+                time,
+                interpolation,
+                anchor_rotation,
+                anchor,
+                show_anchor,
+                target,
+                position,
+                pitch,
+                yaw,
+                roll,
+                fov,
+                target_position,
+                z_near,
+                left_tangent,
+                right_tangent,
+            })
         })?;
 
         Ok(Self { keys })
     }
 }
 
+fn read_camera_tangent<R, I, N>(r: &mut Reader<R, I, N>) -> read::Result<key::CameraTangent>
+where
+    R: Read,
+{
+    Ok(key::CameraTangent {
+        position: r.vec3f32()?,
+        pitch: r.f32()?,
+        yaw: r.f32()?,
+        roll: r.f32()?,
+        fov: r.f32()?,
+        target_position: r.vec3f32()?,
+        z_near: r.f32()?,
+    })
+}
+
 /// Camera shake effect media block.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CameraShakeEffect {
     /// Keys of the media block.
     pub keys: Vec<key::CameraShakeEffect>,
@@ -393,11 +538,15 @@ impl CameraShakeEffect {
     {
         r.chunk_id(0x030A4000)?;
         let keys = r.list(|r| {
-            r.skip(4)?;
+            let time = r.f32()?;
             let intensity = r.f32()?;
             let speed = r.f32()?;
 
-            Ok(key::CameraShakeEffect { intensity, speed })
+            Ok(key::CameraShakeEffect {
+                time,
+                intensity,
+                speed,
+            })
         })?;
 
         Ok(Self { keys })
@@ -405,7 +554,8 @@ impl CameraShakeEffect {
 }
 
 /// Image media block.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Image {
     /// Effect of the image.
     pub effect: Effect,
@@ -428,7 +578,8 @@ impl Image {
 }
 
 /// Music volume media block.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MusicVolume {
     /// Keys of the media block.
     pub keys: Vec<key::MusicVolume>,
@@ -441,11 +592,12 @@ impl MusicVolume {
     {
         r.chunk_id(0x030A6001)?;
         let keys = r.list(|r| {
-            r.u32()?;
+            let time = r.f32()?;
             let music_volume = r.f32()?;
             let sound_volume = r.f32()?;
 
             Ok(key::MusicVolume {
+                time,
                 music_volume,
                 sound_volume,
             })
@@ -456,7 +608,8 @@ impl MusicVolume {
 }
 
 /// Sound media block.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sound {
     /// Number of times to play the sound.
     pub play_count: u32,
@@ -488,12 +641,16 @@ impl Sound {
         let sound = r.optional_file_ref()?;
         r.u32()?;
         let keys = r.list(|r| {
-            r.u32()?;
+            let time = r.f32()?;
             let volume = r.f32()?;
             r.u32()?;
             let position = r.vec3f32()?;
 
-            Ok(key::Sound { volume, position })
+            Ok(key::Sound {
+                time,
+                volume,
+                position,
+            })
         })?;
 
         Ok(Self {
@@ -507,7 +664,8 @@ impl Sound {
 }
 
 /// Text media block.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Text {
     /// The text.
     pub text: String,
@@ -541,7 +699,8 @@ impl Text {
 }
 
 /// Trails media block.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Trails {
     /// Start time of the block in seconds. [0.0, ∞)
     pub start_time: f32,
@@ -566,7 +725,8 @@ impl Trails {
 }
 
 /// Transition fade media block.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TransitionFade {
     /// Keys of the media block.
     pub keys: Vec<key::TransitionFade>,
@@ -599,7 +759,8 @@ impl TransitionFade {
 }
 
 /// Depth of field media block.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DepthOfField {
     /// Keys of the media block.
     pub keys: Vec<key::DepthOfField>,
@@ -615,13 +776,14 @@ impl DepthOfField {
             let time = r.f32()?;
             let focus_distance = r.f32()?;
             let lens_size = r.f32()?;
-            let _target = r.u32()?;
+            let target = optional_index(r.u32()?);
             let target_position = r.vec3f32()?;
 
             Ok(key::DepthOfField {
                 time,
                 focus_distance,
                 lens_size,
+                target,
                 target_position,
             })
         })?;
@@ -631,7 +793,8 @@ impl DepthOfField {
 }
 
 /// Tone mapping media block
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ToneMapping {
     /// Keys of the media block.
     pub keys: Vec<key::ToneMapping>,
@@ -663,7 +826,8 @@ impl ToneMapping {
 }
 
 /// Bloom media block.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Bloom {
     /// Keys of the media block.
     pub keys: Vec<key::Bloom>,
@@ -676,12 +840,13 @@ impl Bloom {
     {
         r.chunk_id(0x03128002)?;
         let keys = r.list(|r| {
-            r.u32()?;
+            let time = r.f32()?;
             let intensity = r.f32()?;
             let streaks_intensity = r.f32()?;
             let streaks_attenuation = r.f32()?;
 
             Ok(key::Bloom {
+                time,
                 intensity,
                 streaks_intensity,
                 streaks_attenuation,
@@ -693,7 +858,8 @@ impl Bloom {
 }
 
 /// Time speed media block.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TimeSpeed {
     /// Keys of the media block.
     pub keys: Vec<key::TimeSpeed>,
@@ -717,7 +883,8 @@ impl TimeSpeed {
 }
 
 /// Manialink media block.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Manialink {
     /// Start time of the block in seconds. [0.0, ∞)
     pub start_time: f32,
@@ -747,7 +914,8 @@ impl Manialink {
 }
 
 /// Vehicle light media block.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VehicleLight {
     /// Start time of the block in seconds. [0.0, ∞)
     pub start_time: f32,
@@ -775,8 +943,14 @@ impl VehicleLight {
 }
 
 /// Editing cut media block.
-#[derive(Clone, Debug)]
-pub struct EditingCut;
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EditingCut {
+    /// Start time of the block in seconds. [0.0, ∞)
+    pub start_time: f32,
+    /// End time of the block in seconds. [0.0, ∞)
+    pub end_time: f32,
+}
 
 impl EditingCut {
     pub(crate) fn read<R, I, N>(r: &mut Reader<R, I, N>) -> read::Result<Self>
@@ -784,15 +958,19 @@ impl EditingCut {
         R: Read,
     {
         r.chunk_id(0x03145000)?;
-        r.u32()?;
-        r.u32()?;
+        let start_time = r.f32()?;
+        let end_time = r.f32()?;
 
-        Ok(Self)
+        Ok(Self {
+            start_time,
+            end_time,
+        })
     }
 }
 
 /// Dirty lens media block.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DirtyLens {
     /// Keys of the media block.
     pub keys: Vec<key::DirtyLens>,
@@ -817,7 +995,8 @@ impl DirtyLens {
 }
 
 /// Color grading media block.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ColorGrading {
     /// Optional reference to the grade image file.
     pub grade: Option<InternalFileRef>,
@@ -846,7 +1025,8 @@ impl ColorGrading {
 }
 
 /// Manialink inferface media block.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ManialinkInterface {
     /// Start time of the block in seconds. [0.0, ∞)
     pub start_time: f32,
@@ -877,7 +1057,8 @@ impl ManialinkInterface {
 }
 
 /// Fog media block.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Fog {
     /// Keys of the media block.
     pub keys: Vec<key::Fog>,
@@ -918,8 +1099,22 @@ impl Fog {
 }
 
 /// Entity media block.
-#[derive(Clone)]
-pub struct Entity;
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Entity {
+    /// `true` if the entity can not take damage.
+    pub no_damage: bool,
+    /// `true` if the lights of the entity are forced on.
+    pub force_light: bool,
+    /// `true` if the hue of the entity is forced.
+    pub force_hue: bool,
+    /// Optional ID of the model of the entity.
+    pub model_id: Option<Id>,
+    /// Skins of the entity by slot, `None` for empty slots.
+    pub skins: Vec<Option<FileRef>>,
+    /// Skin options of the entity.
+    pub skin_options: String,
+}
 
 impl Entity {
     pub(crate) fn read<R, I, N>(r: &mut Reader<R, I, N>) -> read::Result<Self>
@@ -937,21 +1132,17 @@ impl Entity {
 
             Ok(())
         })?;
+        let no_damage = r.bool32()?;
         r.u32()?; // 0
-        r.u32()?; // 0
-        r.u32()?; // 0
-        r.u32()?; // 0
-        r.optional_id()?; // "CarSport"
+        let force_light = r.bool32()?;
+        let force_hue = r.bool32()?;
+        let model_id = r.optional_id()?; // "CarSport"
         r.u32()?;
         r.optional_id()?; // "Nadeo"
         r.u32()?; // f32
         r.u32()?; // f32
         r.u32()?; // f32
-        r.list(|r| {
-            r.optional_file_ref()?;
-
-            Ok(())
-        })?;
+        let skins = r.list(|r| r.optional_file_ref())?;
         r.u32()?;
         r.list(|r| {
             r.u32()?;
@@ -966,9 +1157,11 @@ impl Entity {
 
             Ok(())
         })?;
-        if version >= 7 {
-            r.string()?;
-        }
+        let skin_options = if version >= 7 {
+            r.string()?
+        } else {
+            String::new()
+        };
         if version >= 8 {
             r.u32()?;
         }
@@ -979,12 +1172,20 @@ impl Entity {
             Ok(())
         })?;
 
-        Ok(Self)
+        Ok(Self {
+            no_damage,
+            force_light,
+            force_hue,
+            model_id,
+            skins,
+            skin_options,
+        })
     }
 }
 
 /// Visibility of a opponent visibility media block.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 #[repr(u32)]
 pub enum Visibility {
@@ -995,7 +1196,8 @@ pub enum Visibility {
 }
 
 /// Opponent visibility media block.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct OpponentVisibility {
     /// Start time of the block in seconds. [0.0, ∞)
     pub start_time: f32,
@@ -1024,3 +1226,118 @@ impl OpponentVisibility {
         })
     }
 }
+
+fn key_range<K: Key>(keys: &[K]) -> Option<Range<f32>> {
+    Some(keys.first()?.time()..keys.last()?.time())
+}
+
+macro_rules! impl_keys {
+    ($($type:ident),+) => {
+        $(
+            impl $type {
+                /// Time range of the keys of the media block in seconds.
+                ///
+                /// Returns `None` if the media block has no keys.
+                pub fn time_range(&self) -> Option<Range<f32>> {
+                    key_range(&self.keys)
+                }
+
+                /// Sample the keys of the media block at the given `time` in seconds.
+                ///
+                /// Before the first key and after the last key the value of that key is held.
+                /// Returns `None` if the media block has no keys.
+                pub fn sample(&self, time: f32) -> Option<key::$type> {
+                    key::sample(&self.keys, time)
+                }
+
+                pub(crate) fn map_times<F: Fn(f32) -> f32>(&mut self, f: F) {
+                    for key in &mut self.keys {
+                        key.time = f(key.time);
+                    }
+                }
+
+                pub(crate) fn trim(&mut self, range: &Range<f32>) -> bool {
+                    key::trim(&mut self.keys, range, key::sample)
+                }
+            }
+        )+
+    };
+}
+
+impl_keys!(
+    Triangles,
+    Color,
+    Time,
+    OrbitalCamera,
+    PathCamera,
+    CustomCamera,
+    CameraShakeEffect,
+    MusicVolume,
+    Sound,
+    TransitionFade,
+    DepthOfField,
+    ToneMapping,
+    Bloom,
+    TimeSpeed,
+    DirtyLens,
+    ColorGrading,
+    Fog
+);
+
+impl Effect {
+    /// Time range of the keys of the effect in seconds.
+    ///
+    /// Returns `None` if the effect has no keys.
+    pub fn time_range(&self) -> Option<Range<f32>> {
+        key_range(&self.keys)
+    }
+
+    /// Sample the keys of the effect at the given `time` in seconds.
+    ///
+    /// Before the first key and after the last key the value of that key is held.
+    /// Returns `None` if the effect has no keys.
+    pub fn sample(&self, time: f32) -> Option<key::Effect> {
+        key::sample(&self.keys, time)
+    }
+
+    pub(crate) fn map_times<F: Fn(f32) -> f32>(&mut self, f: F) {
+        for key in &mut self.keys {
+            key.time = f(key.time);
+        }
+    }
+
+    pub(crate) fn trim(&mut self, range: &Range<f32>) -> bool {
+        key::trim(&mut self.keys, range, key::sample)
+    }
+}
+
+macro_rules! impl_time_span {
+    ($($type:ident),+) => {
+        $(
+            impl $type {
+                pub(crate) fn map_times<F: Fn(f32) -> f32>(&mut self, f: F) {
+                    self.start_time = f(self.start_time);
+                    self.end_time = f(self.end_time);
+                }
+
+                pub(crate) fn trim(&mut self, range: &Range<f32>) -> bool {
+                    self.start_time = self.start_time.max(range.start);
+                    self.end_time = self.end_time.min(range.end);
+
+                    self.start_time < self.end_time
+                }
+            }
+        )+
+    };
+}
+
+impl_time_span!(
+    MotionBlur,
+    PlayerCamera,
+    Trails,
+    Manialink,
+    VehicleLight,
+    EditingCut,
+    ManialinkInterface,
+    OpponentVisibility
+);
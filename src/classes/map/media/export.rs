@@ -0,0 +1,347 @@
+use super::{Block, Clip};
+use crate::map::euler_to_quaternion;
+use crate::Vec3;
+use std::error;
+use std::fmt::{self, Display, Write as _};
+use std::io::{self, Write};
+
+/// Maximum number of frames sampled by [`CameraPath::from_clip`].
+const MAX_FRAMES: f64 = (1 << 20) as f64;
+
+/// Reason why a camera path could not be exported.
+///
+/// Returned by [`CameraPath::from_clip`].
+#[derive(Clone, PartialEq, Debug)]
+pub enum ExportError {
+    /// The frame rate is not a positive finite number.
+    InvalidFrameRate(f32),
+    /// The clip is too long to be sampled at the frame rate.
+    TooManyFrames,
+}
+
+impl Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::InvalidFrameRate(frame_rate) => write!(f, "invalid frame rate {frame_rate}"),
+            Self::TooManyFrames => write!(
+                f,
+                "clip has more than {MAX_FRAMES} frames at the given frame rate"
+            ),
+        }
+    }
+}
+
+impl error::Error for ExportError {}
+
+/// A single keyframe of an exported camera path.
+///
+/// All values are converted from the left-handed coordinate system of the game
+/// to a right-handed, Y-up coordinate system as used by glTF and Blender's glTF importer,
+/// with the camera looking along its local -Z axis.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CameraKeyframe {
+    /// Time of the keyframe in seconds.
+    pub time: f32,
+    /// Position of the camera.
+    pub position: Vec3<f32>,
+    /// Rotation of the camera as an unit quaternion `[x, y, z, w]`.
+    pub rotation: [f32; 4],
+    /// Yaw rotation of the camera around the Y axis in degrees.
+    ///
+    /// The Euler angles are applied in the order yaw, pitch, roll,
+    /// where all zero angles look along +Z.
+    pub yaw: f32,
+    /// Pitch rotation of the camera around the X axis in degrees.
+    pub pitch: f32,
+    /// Roll rotation of the camera around the Z axis in degrees.
+    pub roll: f32,
+    /// Vertical field of view of the camera in degrees.
+    pub fov: f32,
+}
+
+impl CameraKeyframe {
+    /// Create a keyframe from the camera values as stored in a GBX file.
+    ///
+    /// The game applies yaw, pitch and then roll,
+    /// where a positive pitch looks downwards and the camera looks along +Z.
+    pub fn from_gbx(
+        time: f32,
+        position: &Vec3<f32>,
+        pitch: f32,
+        yaw: f32,
+        roll: f32,
+        fov: f32,
+    ) -> Self {
+        let [x, y, z, w] = euler_to_quaternion(yaw, pitch, roll);
+
+        // Mirror along the X axis to switch handedness, which negates the Y and Z components,
+        // then turn the camera around its local Y axis to look along -Z.
+        let rotation = [z, w, x, y];
+
+        Self {
+            time,
+            position: Vec3::new(-position.x, position.y, position.z),
+            rotation,
+            yaw: -yaw.to_degrees(),
+            pitch: pitch.to_degrees(),
+            roll: -roll.to_degrees(),
+            fov,
+        }
+    }
+}
+
+/// A camera path exported from a media clip.
+///
+/// # Examples
+///
+/// Export the camera of the intro of a map as glTF, which can be imported into Blender.
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let map = gbx::Map::reader().read_from_file("MyMap.Map.Gbx")?;
+///
+/// if let Some(intro) = map.intro_media {
+///     let path = gbx::map::media::export::CameraPath::from_clip(&intro, 30.0)?;
+///     path.write_gltf(std::fs::File::create("Intro.gltf")?)?;
+/// }
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CameraPath {
+    /// Keyframes of the camera path ordered by time.
+    pub keyframes: Vec<CameraKeyframe>,
+}
+
+impl CameraPath {
+    /// Sample the custom and path camera blocks of the `clip` at `frame_rate` frames per second.
+    ///
+    /// Frames at which no camera block is active are skipped.
+    /// Cameras anchored to a player are exported relative to that player.
+    ///
+    /// Returns an error if `frame_rate` is not a positive finite number
+    /// or if the clip would be sampled at more than 2^20 frames.
+    pub fn from_clip(clip: &Clip, frame_rate: f32) -> Result<Self, ExportError> {
+        if !frame_rate.is_finite() || frame_rate <= 0.0 {
+            return Err(ExportError::InvalidFrameRate(frame_rate));
+        }
+
+        let end_time = clip
+            .tracks
+            .iter()
+            .flat_map(|track| &track.blocks)
+            .filter(|block| matches!(block, Block::CustomCamera(_) | Block::PathCamera(_)))
+            .filter_map(|block| block.time_range())
+            .map(|range| range.end)
+            .fold(0.0, f32::max);
+
+        let num_frames = (end_time as f64 * frame_rate as f64).floor() + 1.0;
+
+        if num_frames > MAX_FRAMES {
+            return Err(ExportError::TooManyFrames);
+        }
+
+        let num_frames = num_frames as usize;
+
+        let keyframes = (0..num_frames)
+            .map(|frame| frame as f32 / frame_rate)
+            .filter_map(|time| {
+                clip.state_at(time).camera.map(|camera| {
+                    CameraKeyframe::from_gbx(
+                        time,
+                        &camera.position,
+                        camera.pitch,
+                        camera.yaw,
+                        camera.roll,
+                        camera.fov,
+                    )
+                })
+            })
+            .collect();
+
+        Ok(Self { keyframes })
+    }
+
+    /// Write the camera path as a JSON array of keyframe objects.
+    pub fn write_json<W>(&self, mut writer: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let mut json = String::from("[");
+
+        for (i, keyframe) in self.keyframes.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+
+            write!(
+                json,
+                "\n  {{\"time\":{},\"position\":[{},{},{}],\"rotation\":[{},{},{},{}],\"yaw\":{},\"pitch\":{},\"roll\":{},\"fov\":{}}}",
+                json_f32(keyframe.time),
+                json_f32(keyframe.position.x),
+                json_f32(keyframe.position.y),
+                json_f32(keyframe.position.z),
+                json_f32(keyframe.rotation[0]),
+                json_f32(keyframe.rotation[1]),
+                json_f32(keyframe.rotation[2]),
+                json_f32(keyframe.rotation[3]),
+                json_f32(keyframe.yaw),
+                json_f32(keyframe.pitch),
+                json_f32(keyframe.roll),
+                json_f32(keyframe.fov),
+            )
+            .unwrap();
+        }
+
+        json.push_str("\n]\n");
+
+        writer.write_all(json.as_bytes())
+    }
+
+    /// Write the camera path as CSV with a header row.
+    pub fn write_csv<W>(&self, mut writer: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        writeln!(writer, "time,x,y,z,qx,qy,qz,qw,yaw,pitch,roll,fov")?;
+
+        for keyframe in &self.keyframes {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{},{},{},{},{}",
+                keyframe.time,
+                keyframe.position.x,
+                keyframe.position.y,
+                keyframe.position.z,
+                keyframe.rotation[0],
+                keyframe.rotation[1],
+                keyframe.rotation[2],
+                keyframe.rotation[3],
+                keyframe.yaw,
+                keyframe.pitch,
+                keyframe.roll,
+                keyframe.fov,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the camera path as a glTF 2.0 file containing a single animated camera.
+    ///
+    /// The animation data is embedded as a base64 data URI.
+    /// Since glTF cannot animate the field of view,
+    /// the field of view of the first keyframe is used for the camera.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if the path has no keyframes.
+    pub fn write_gltf<W>(&self, mut writer: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let first = self.keyframes.first().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "camera path has no keyframes")
+        })?;
+        let last = self.keyframes.last().unwrap();
+        let count = self.keyframes.len();
+
+        let mut buffer = Vec::with_capacity(count * 32);
+
+        for keyframe in &self.keyframes {
+            buffer.extend_from_slice(&keyframe.time.to_le_bytes());
+        }
+
+        for keyframe in &self.keyframes {
+            buffer.extend_from_slice(&keyframe.position.x.to_le_bytes());
+            buffer.extend_from_slice(&keyframe.position.y.to_le_bytes());
+            buffer.extend_from_slice(&keyframe.position.z.to_le_bytes());
+        }
+
+        for keyframe in &self.keyframes {
+            for component in keyframe.rotation {
+                buffer.extend_from_slice(&component.to_le_bytes());
+            }
+        }
+
+        let mut json = String::new();
+
+        write!(
+            json,
+            concat!(
+                "{{\"asset\":{{\"version\":\"2.0\",\"generator\":\"gbx\"}},",
+                "\"scene\":0,\"scenes\":[{{\"nodes\":[0]}}],",
+                "\"nodes\":[{{\"name\":\"Camera\",\"camera\":0}}],",
+                "\"cameras\":[{{\"type\":\"perspective\",\"perspective\":{{\"yfov\":{},\"znear\":0.1}}}}],",
+                "\"buffers\":[{{\"byteLength\":{},\"uri\":\"data:application/octet-stream;base64,{}\"}}],",
+                "\"bufferViews\":[",
+                "{{\"buffer\":0,\"byteOffset\":0,\"byteLength\":{}}},",
+                "{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{}}},",
+                "{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{}}}],",
+                "\"accessors\":[",
+                "{{\"bufferView\":0,\"componentType\":5126,\"count\":{},\"type\":\"SCALAR\",\"min\":[{}],\"max\":[{}]}},",
+                "{{\"bufferView\":1,\"componentType\":5126,\"count\":{},\"type\":\"VEC3\"}},",
+                "{{\"bufferView\":2,\"componentType\":5126,\"count\":{},\"type\":\"VEC4\"}}],",
+                "\"animations\":[{{\"name\":\"Camera\",",
+                "\"samplers\":[{{\"input\":0,\"output\":1,\"interpolation\":\"LINEAR\"}},",
+                "{{\"input\":0,\"output\":2,\"interpolation\":\"LINEAR\"}}],",
+                "\"channels\":[{{\"sampler\":0,\"target\":{{\"node\":0,\"path\":\"translation\"}}}},",
+                "{{\"sampler\":1,\"target\":{{\"node\":0,\"path\":\"rotation\"}}}}]}}]}}\n"
+            ),
+            json_f32(first.fov.to_radians()),
+            buffer.len(),
+            base64_encode(&buffer),
+            count * 4,
+            count * 4,
+            count * 12,
+            count * 16,
+            count * 16,
+            count,
+            json_f32(first.time),
+            json_f32(last.time),
+            count,
+            count,
+        )
+        .unwrap();
+
+        writer.write_all(json.as_bytes())
+    }
+}
+
+fn json_f32(value: f32) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        String::from("null")
+    }
+}
+
+fn base64_encode(input: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut output = String::with_capacity((input.len() + 2) / 3 * 4);
+
+    for chunk in input.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        output.push(ALPHABET[(n >> 18) as usize & 63] as char);
+        output.push(ALPHABET[(n >> 12) as usize & 63] as char);
+
+        if chunk.len() > 1 {
+            output.push(ALPHABET[(n >> 6) as usize & 63] as char);
+        } else {
+            output.push('=');
+        }
+
+        if chunk.len() > 2 {
+            output.push(ALPHABET[n as usize & 63] as char);
+        } else {
+            output.push('=');
+        }
+    }
+
+    output
+}
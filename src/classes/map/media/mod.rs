@@ -1,13 +1,18 @@
 /// Media block types.
 pub mod block;
+/// Camera path export.
+pub mod export;
 
 use crate::read::{self, Reader};
 use crate::Vec3;
 use std::borrow::BorrowMut;
+use std::cmp::Ordering;
 use std::io::{Read, Seek};
+use std::ops::Range;
 
 /// A media block.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum Block {
     /// 2D triangles media block.
@@ -70,8 +75,143 @@ pub enum Block {
     OpponentVisibility(block::OpponentVisibility),
 }
 
+impl Block {
+    /// Time range in seconds during which the block is active.
+    ///
+    /// The range of a block with a single key is empty and starts at the time of the key,
+    /// [`Track::block_at`] treats such a block as active at exactly that time.
+    /// Returns `None` if the block has no keys or if its timing is unknown.
+    pub fn time_range(&self) -> Option<Range<f32>> {
+        match *self {
+            Self::Triangles2D(ref block) | Self::Triangles3D(ref block) => block.time_range(),
+            Self::Color(ref block) => block.time_range(),
+            Self::MotionBlur(ref block) => Some(block.start_time..block.end_time),
+            Self::PlayerCamera(ref block) => Some(block.start_time..block.end_time),
+            Self::Time(ref block) => block.time_range(),
+            Self::OrbitalCamera(ref block) => block.time_range(),
+            Self::PathCamera(ref block) => block.time_range(),
+            Self::CustomCamera(ref block) => block.time_range(),
+            Self::CameraShakeEffect(ref block) => block.time_range(),
+            Self::Image(ref block) => block.effect.time_range(),
+            Self::MusicVolume(ref block) => block.time_range(),
+            Self::Sound(ref block) => block.time_range(),
+            Self::Text(ref block) => block.effect.time_range(),
+            Self::Trails(ref block) => Some(block.start_time..block.end_time),
+            Self::TransitionFade(ref block) => block.time_range(),
+            Self::DepthOfField(ref block) => block.time_range(),
+            Self::ToneMapping(ref block) => block.time_range(),
+            Self::Bloom(ref block) => block.time_range(),
+            Self::TimeSpeed(ref block) => block.time_range(),
+            Self::Manialink(ref block) => Some(block.start_time..block.end_time),
+            Self::VehicleLight(ref block) => Some(block.start_time..block.end_time),
+            Self::EditingCut(ref block) => Some(block.start_time..block.end_time),
+            Self::DirtyLens(ref block) => block.time_range(),
+            Self::ColorGrading(ref block) => block.time_range(),
+            Self::ManialinkInterface(ref block) => Some(block.start_time..block.end_time),
+            Self::Fog(ref block) => block.time_range(),
+            Self::Entity(_) => None,
+            Self::OpponentVisibility(ref block) => Some(block.start_time..block.end_time),
+        }
+    }
+
+    /// Sampled camera of the block at the given `time` in seconds.
+    ///
+    /// Returns `None` if the block is not a custom or path camera block, or if it has no keys.
+    pub fn camera_at(&self, time: f32) -> Option<Camera> {
+        match *self {
+            Self::CustomCamera(ref block) => block.sample(time).map(|key| Camera {
+                position: key.position,
+                pitch: key.pitch,
+                yaw: key.yaw,
+                roll: key.roll,
+                fov: key.fov,
+                anchor: key.anchor,
+            }),
+            Self::PathCamera(ref block) => block.sample(time).map(|key| Camera {
+                position: key.position,
+                pitch: key.pitch,
+                yaw: key.yaw,
+                roll: key.roll,
+                fov: key.fov,
+                anchor: key.anchor,
+            }),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! dispatch_block {
+    ($block:expr, $b:ident => $expr:expr, Entity => $entity:expr) => {
+        match $block {
+            Block::Triangles2D(ref mut $b) | Block::Triangles3D(ref mut $b) => $expr,
+            Block::Color(ref mut $b) => $expr,
+            Block::MotionBlur(ref mut $b) => $expr,
+            Block::PlayerCamera(ref mut $b) => $expr,
+            Block::Time(ref mut $b) => $expr,
+            Block::OrbitalCamera(ref mut $b) => $expr,
+            Block::PathCamera(ref mut $b) => $expr,
+            Block::CustomCamera(ref mut $b) => $expr,
+            Block::CameraShakeEffect(ref mut $b) => $expr,
+            Block::Image(block::Image {
+                effect: ref mut $b, ..
+            }) => $expr,
+            Block::MusicVolume(ref mut $b) => $expr,
+            Block::Sound(ref mut $b) => $expr,
+            Block::Text(block::Text {
+                effect: ref mut $b, ..
+            }) => $expr,
+            Block::Trails(ref mut $b) => $expr,
+            Block::TransitionFade(ref mut $b) => $expr,
+            Block::DepthOfField(ref mut $b) => $expr,
+            Block::ToneMapping(ref mut $b) => $expr,
+            Block::Bloom(ref mut $b) => $expr,
+            Block::TimeSpeed(ref mut $b) => $expr,
+            Block::Manialink(ref mut $b) => $expr,
+            Block::VehicleLight(ref mut $b) => $expr,
+            Block::EditingCut(ref mut $b) => $expr,
+            Block::DirtyLens(ref mut $b) => $expr,
+            Block::ColorGrading(ref mut $b) => $expr,
+            Block::ManialinkInterface(ref mut $b) => $expr,
+            Block::Fog(ref mut $b) => $expr,
+            Block::Entity(_) => $entity,
+            Block::OpponentVisibility(ref mut $b) => $expr,
+        }
+    };
+}
+
+impl Block {
+    /// Map all key, start and end times of the block with `f`.
+    fn map_times<F: Fn(f32) -> f32>(&mut self, f: F) {
+        dispatch_block!(*self, block => block.map_times(f), Entity => {})
+    }
+
+    /// Trim the block to `range`, returns `false` if nothing of the block remains.
+    fn trim(&mut self, range: &Range<f32>) -> bool {
+        dispatch_block!(*self, block => block.trim(range), Entity => true)
+    }
+}
+
+/// Camera of a media clip at a specific time.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Camera {
+    /// Position of the camera, relative to the anchor if any.
+    pub position: Vec3<f32>,
+    /// Pitch rotation of the camera in radians.
+    pub pitch: f32,
+    /// Yaw rotation of the camera in radians.
+    pub yaw: f32,
+    /// Roll rotation of the camera in radians.
+    pub roll: f32,
+    /// Vertical field of view of the camera in degrees.
+    pub fov: f32,
+    /// Optional index of the player the camera is anchored to, `Some(0)` is the local player.
+    pub anchor: Option<u32>,
+}
+
 /// Segment of a media track.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TrackSegment {
     /// Start time of the segment. [0, ∞)
     pub start_time: f32,
@@ -80,7 +220,8 @@ pub struct TrackSegment {
 }
 
 /// A media track.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Track {
     /// All blocks of the track.
     pub blocks: Vec<Block>,
@@ -90,6 +231,89 @@ pub struct Track {
     pub repeat_track_segment: Option<TrackSegment>,
 }
 
+impl Track {
+    /// Get the active block of the track at the given `time` in seconds.
+    ///
+    /// Also returns the time at which the block should be sampled,
+    /// which differs from `time` if the track repeats a segment.
+    /// Returns `None` if no block is active.
+    pub fn block_at(&self, time: f32) -> Option<(&Block, f32)> {
+        let time = match self.repeat_track_segment {
+            Some(ref segment)
+                if time >= segment.end_time && segment.end_time > segment.start_time =>
+            {
+                segment.start_time
+                    + (time - segment.start_time) % (segment.end_time - segment.start_time)
+            }
+            _ => time,
+        };
+
+        let active_block = self.blocks.iter().find(|block| {
+            block.time_range().map_or(false, |range| {
+                range.start <= time && (time < range.end || time == range.start)
+            })
+        });
+
+        if let Some(block) = active_block {
+            return Some((block, time));
+        }
+
+        if self.keep_last_block_active {
+            let last_block = self.blocks.last()?;
+
+            if last_block
+                .time_range()
+                .map_or(false, |range| time >= range.end)
+            {
+                return Some((last_block, time));
+            }
+        }
+
+        None
+    }
+}
+
+impl Track {
+    /// Shift all blocks of the track by `offset` seconds.
+    pub fn shift(&mut self, offset: f32) {
+        self.map_times(|time| time + offset);
+    }
+
+    /// Scale the timing of all blocks of the track by `factor`. (0.0, ∞)
+    pub fn scale(&mut self, factor: f32) {
+        self.map_times(|time| time * factor);
+    }
+
+    /// Trim the track to the given time `range` in seconds.
+    ///
+    /// Blocks which are partially inside the range are cut at its boundaries
+    /// and blocks outside of the range are removed.
+    /// The times of the remaining blocks are not changed.
+    pub fn trim(&mut self, range: Range<f32>) {
+        self.blocks.retain_mut(|block| block.trim(&range));
+
+        if let Some(ref mut segment) = self.repeat_track_segment {
+            segment.start_time = segment.start_time.max(range.start);
+            segment.end_time = segment.end_time.min(range.end);
+
+            if segment.start_time >= segment.end_time {
+                self.repeat_track_segment = None;
+            }
+        }
+    }
+
+    fn map_times<F: Fn(f32) -> f32>(&mut self, f: F) {
+        for block in &mut self.blocks {
+            block.map_times(&f);
+        }
+
+        if let Some(ref mut segment) = self.repeat_track_segment {
+            segment.start_time = f(segment.start_time);
+            segment.end_time = f(segment.end_time);
+        }
+    }
+}
+
 impl Default for Track {
     fn default() -> Self {
         Self {
@@ -101,7 +325,8 @@ impl Default for Track {
 }
 
 /// A media clip.
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Clip {
     /// All tracks of the clip.
     pub tracks: Vec<Track>,
@@ -136,7 +361,7 @@ impl Clip {
                 let blocks = r.list(|r| {
                     r.any_node_owned(|r, class_id| {
                         let block = match class_id {
-                            0x0304B000 => Block::Triangles3D(block::Triangles::read(r)?),
+                            0x0304B000 => Block::Triangles2D(block::Triangles::read(r)?),
                             0x0304C000 => Block::Triangles3D(block::Triangles::read(r)?),
                             0x03080000 => Block::Color(block::Color::read(r)?),
                             0x03082000 => Block::MotionBlur(block::MotionBlur::read(r)?),
@@ -222,6 +447,187 @@ impl Clip {
     }
 }
 
+/// State of a media clip at a specific time.
+///
+/// Returned by [`Clip::state_at`].
+#[derive(Clone)]
+pub struct ClipState<'a> {
+    /// Active block of every track of the clip, if any.
+    pub blocks: Vec<Option<&'a Block>>,
+    /// Camera of the first track with an active custom or path camera block.
+    pub camera: Option<Camera>,
+    /// Sampled key of the first active color grading block.
+    pub color_grading: Option<block::key::ColorGrading>,
+    /// Sampled key of the first active fog block.
+    pub fog: Option<block::key::Fog>,
+    /// Sampled key of the first active tone mapping block.
+    pub tone_mapping: Option<block::key::ToneMapping>,
+    /// Sampled key of the first active music volume block.
+    pub music_volume: Option<block::key::MusicVolume>,
+    /// Sampled key of the first active depth of field block.
+    pub depth_of_field: Option<block::key::DepthOfField>,
+    /// Sampled key of the first active bloom block.
+    pub bloom: Option<block::key::Bloom>,
+    /// Sampled key of the first active time speed block.
+    pub time_speed: Option<block::key::TimeSpeed>,
+    /// Sampled key of the first active dirty lens block.
+    pub dirty_lens: Option<block::key::DirtyLens>,
+    /// Sampled key of the first active transition fade block.
+    pub transition_fade: Option<block::key::TransitionFade>,
+}
+
+impl Clip {
+    /// Evaluate the clip at the given `time` in seconds.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let map = gbx::Map::reader().read_from_file("MyMap.Map.Gbx")?;
+    ///
+    /// if let Some(intro) = map.intro_media {
+    ///     let state = intro.state_at(2.5);
+    ///
+    ///     if let Some(camera) = state.camera {
+    ///         println!("{:?}", camera.position);
+    ///     }
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub fn state_at(&self, time: f32) -> ClipState<'_> {
+        let mut state = ClipState {
+            blocks: Vec::with_capacity(self.tracks.len()),
+            camera: None,
+            color_grading: None,
+            fog: None,
+            tone_mapping: None,
+            music_volume: None,
+            depth_of_field: None,
+            bloom: None,
+            time_speed: None,
+            dirty_lens: None,
+            transition_fade: None,
+        };
+
+        for track in &self.tracks {
+            let (block, time) = match track.block_at(time) {
+                Some(active) => active,
+                None => {
+                    state.blocks.push(None);
+                    continue;
+                }
+            };
+
+            state.blocks.push(Some(block));
+
+            if state.camera.is_none() {
+                state.camera = block.camera_at(time);
+            }
+
+            match *block {
+                Block::ColorGrading(ref block) if state.color_grading.is_none() => {
+                    state.color_grading = block.sample(time)
+                }
+                Block::Fog(ref block) if state.fog.is_none() => state.fog = block.sample(time),
+                Block::ToneMapping(ref block) if state.tone_mapping.is_none() => {
+                    state.tone_mapping = block.sample(time)
+                }
+                Block::MusicVolume(ref block) if state.music_volume.is_none() => {
+                    state.music_volume = block.sample(time)
+                }
+                Block::DepthOfField(ref block) if state.depth_of_field.is_none() => {
+                    state.depth_of_field = block.sample(time)
+                }
+                Block::Bloom(ref block) if state.bloom.is_none() => {
+                    state.bloom = block.sample(time)
+                }
+                Block::TimeSpeed(ref block) if state.time_speed.is_none() => {
+                    state.time_speed = block.sample(time)
+                }
+                Block::DirtyLens(ref block) if state.dirty_lens.is_none() => {
+                    state.dirty_lens = block.sample(time)
+                }
+                Block::TransitionFade(ref block) if state.transition_fade.is_none() => {
+                    state.transition_fade = block.sample(time)
+                }
+                _ => {}
+            }
+        }
+
+        state
+    }
+}
+
+impl Clip {
+    /// End time of the last block of the clip in seconds.
+    ///
+    /// Returns `0.0` if the clip has no timed blocks.
+    pub fn end_time(&self) -> f32 {
+        self.tracks
+            .iter()
+            .flat_map(|track| &track.blocks)
+            .filter_map(|block| block.time_range())
+            .map(|range| range.end)
+            .fold(0.0, f32::max)
+    }
+
+    /// Shift all blocks of the clip by `offset` seconds.
+    pub fn shift(&mut self, offset: f32) {
+        for track in &mut self.tracks {
+            track.shift(offset);
+        }
+    }
+
+    /// Scale the timing of all blocks of the clip by `factor`. (0.0, ∞)
+    ///
+    /// A factor of `2.0` plays the clip at half speed.
+    pub fn scale(&mut self, factor: f32) {
+        for track in &mut self.tracks {
+            track.scale(factor);
+        }
+    }
+
+    /// Trim all tracks of the clip to the given time `range` in seconds.
+    ///
+    /// See [`Track::trim`] for details.
+    /// Tracks which have no blocks left are removed.
+    pub fn trim(&mut self, range: Range<f32>) {
+        self.tracks.retain_mut(|track| {
+            track.trim(range.clone());
+
+            !track.blocks.is_empty()
+        });
+    }
+
+    /// Append `other` to the end of the clip.
+    ///
+    /// The blocks of `other` are shifted to start at the [end time](Self::end_time) of the clip
+    /// and the tracks are merged by their index.
+    /// Tracks of the clip which are continued by `other` take over the properties
+    /// of the appended track, all other tracks of the clip stop at the end of their last block.
+    pub fn concat(&mut self, mut other: Clip) {
+        other.shift(self.end_time());
+
+        let mut other_tracks = other.tracks.into_iter();
+
+        for track in &mut self.tracks {
+            match other_tracks.next() {
+                Some(other_track) => {
+                    track.blocks.extend(other_track.blocks);
+                    track.keep_last_block_active = other_track.keep_last_block_active;
+                    track.repeat_track_segment = other_track.repeat_track_segment;
+                }
+                None => {
+                    track.keep_last_block_active = false;
+                    track.repeat_track_segment = None;
+                }
+            }
+        }
+
+        self.tracks.extend(other_tracks);
+    }
+}
+
 impl Default for Clip {
     fn default() -> Self {
         Self {
@@ -235,7 +641,8 @@ impl Default for Clip {
 }
 
 /// Condition to trigger a media clip.
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, PartialEq, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum Condition {
     #[default]
@@ -273,7 +680,8 @@ pub enum Condition {
 }
 
 /// A media clip and its trigger conditions.
-#[derive(Clone, Default)]
+#[derive(Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ClipTrigger {
     /// The clip which gets activated by the trigger conditions.
     pub clip: Clip,
@@ -284,7 +692,8 @@ pub struct ClipTrigger {
 }
 
 /// A media clip group.
-#[derive(Clone, Default)]
+#[derive(Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ClipGroup {
     /// All the clips and associated triggers in this clip group.
     pub clips: Vec<ClipTrigger>,
@@ -375,3 +784,53 @@ impl ClipGroup {
         Ok(clip_group)
     }
 }
+
+impl ClipGroup {
+    /// Insert `clip` at `index` and update the clip indices of all trigger conditions.
+    ///
+    /// The condition of the inserted `clip` already refers to the clip indices after the insertion.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, clip: ClipTrigger) {
+        self.update_clip_indices(|clip_index| {
+            Some(if clip_index as usize >= index {
+                clip_index + 1
+            } else {
+                clip_index
+            })
+        });
+        self.clips.insert(index, clip);
+    }
+
+    /// Remove the clip at `index` and update the clip indices of all trigger conditions.
+    ///
+    /// Conditions referring to the removed clip are set to `None`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> ClipTrigger {
+        let clip = self.clips.remove(index);
+        self.update_clip_indices(|clip_index| match (clip_index as usize).cmp(&index) {
+            Ordering::Less => Some(clip_index),
+            Ordering::Equal => None,
+            Ordering::Greater => Some(clip_index - 1),
+        });
+
+        clip
+    }
+
+    fn update_clip_indices<F: Fn(u32) -> Option<u32>>(&mut self, f: F) {
+        for clip in &mut self.clips {
+            match clip.condition {
+                Condition::AlreadyTriggered { ref mut clip_index }
+                | Condition::NotAlreadyTriggered { ref mut clip_index } => {
+                    *clip_index = clip_index.and_then(&f);
+                }
+                _ => {}
+            }
+        }
+    }
+}
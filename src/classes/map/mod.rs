@@ -1,22 +1,53 @@
 /// Media tracker types.
 pub mod media;
 
+mod check;
+mod dependencies;
+mod diff;
+mod geometry;
+mod header;
+mod map_type;
+mod preview;
+mod script_metadata;
+mod spatial;
+mod transform;
+mod uid;
+mod waypoints;
+
+pub use check::Problem;
+pub use dependencies::{Dependency, MapDependencies, ModelDependency, ModelKind};
+pub use diff::{diff, Change, MapDiff, MedalTimes, ObjectKind, Placement};
+pub use geometry::{
+    euler_to_matrix, euler_to_quaternion, grid_to_world, matrix_to_euler, quaternion_to_euler,
+    quaternion_to_matrix, world_to_grid, Matrix3, Quaternion, BLOCK_SIZE, GRID_Y_OFFSET,
+};
+pub use header::XmlHeader;
+pub use map_type::{MapType, MapTypeError};
+pub use preview::{PreviewError, PreviewImage, PreviewOptions, Projection};
+pub use script_metadata::{ScriptMetadata, ScriptType, ScriptValue};
+pub use spatial::{Aabb, IndexedMap, ObjectRef, SpatialIndex};
+pub use transform::{Selection, Transform, TransformError};
+pub use uid::{decode_uid, UidError, UidMode, UidStatus};
+pub use waypoints::{Waypoint, WaypointSummary};
+
 use crate::fmt::{DebugOption, DebugVec};
 use crate::ghost::Ghost;
 use crate::read::{self, ReadBodyChunk, Reader, ReaderBuilder};
 use crate::types::{ExternalFileRef, FileRef, Id, Vec3};
 use crate::write::{self, Writer, WriterBuilder};
+use crate::zip;
 use num_enum::{IntoPrimitive, TryFromPrimitive};
-use quick_xml::events::attributes::Attributes;
-use quick_xml::events::Event;
 use std::borrow::BorrowMut;
 use std::cell::RefCell;
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
 use std::fmt::{self, Debug};
-use std::io::{Cursor, Read, Seek, Write};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Cursor, Read, Seek, Write};
 use std::ops::{Add, Sub};
-use uuid::Uuid;
+use std::path::Path;
 
 /// Day time of the default night mood.
 pub const NIGHT_MOOD_TIME: u16 = 6554;
@@ -29,6 +60,7 @@ pub const SUNSET_MOOD_TIME: u16 = 52920;
 
 /// Map validation.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Validation {
     /// Bronze medal time in milliseconds.
     pub bronze_time: u32,
@@ -103,6 +135,7 @@ pub enum Color {
 
 /// Lightmap quality of a block or item.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug, TryFromPrimitive, IntoPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum LightmapQuality {
     /// Normal lightmap quality.
@@ -169,7 +202,8 @@ pub enum PhaseOffset {
 }
 
 /// Skin of a block or item.
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, PartialEq, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Skin {
     /// The skin.
     pub skin: Option<FileRef>,
@@ -223,6 +257,7 @@ impl Skin {
 
 /// Order of a start, finish or multilap block or item in royal.
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u32)]
 pub enum RoyalOrder {
     /// First.
@@ -238,7 +273,8 @@ pub enum RoyalOrder {
 }
 
 /// Waypoint property of a block or item.
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, PartialEq, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum WaypointProperty {
     /// Checkpoint waypoint.
@@ -315,6 +351,7 @@ impl WaypointProperty {
 
 /// A block inside of a `Map`.
 #[derive(Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Block {
     /// ID of the block's model.
     pub model_id: Id,
@@ -340,6 +377,7 @@ pub struct Block {
 
 /// A free block inside of a `Map`.
 #[derive(Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FreeBlock {
     /// ID of the block's model.
     pub model_id: Id,
@@ -361,8 +399,71 @@ pub struct FreeBlock {
     pub lightmap_quality: LightmapQuality,
 }
 
+/// Zone genealogy of a single ground column of a `Map`.
+///
+/// The zone genealogy describes which terrain zone (e.g. grass, dirt or water)
+/// is painted on the ground at a column.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ZoneGenealogy {
+    /// IDs of all zone transitions of the column, e.g. `VoidToGrass`.
+    pub zone_ids: Vec<Id>,
+    /// Index of the current zone transition in `zone_ids`.
+    pub current_index: u32,
+    /// Direction of the zone.
+    pub dir: Direction,
+    /// ID of the current zone, e.g. `Grass`.
+    pub current_zone_id: Id,
+}
+
+impl ZoneGenealogy {
+    fn read<R>(r: &mut Reader<R, read::IdState>) -> read::Result<Self>
+    where
+        R: Read + Seek,
+    {
+        let mut zone_genealogy = Self::default();
+
+        read::read_body(
+            &mut zone_genealogy,
+            r,
+            vec![(0x0311D002, ReadBodyChunk::Read(Self::read_chunk_0311d002))],
+        )?;
+
+        Ok(zone_genealogy)
+    }
+
+    fn read_chunk_0311d002<R, I, N>(&mut self, r: &mut Reader<R, I, N>) -> read::Result<()>
+    where
+        R: Read,
+        I: BorrowMut<read::IdState>,
+    {
+        self.zone_ids = r.list(|r| r.id())?;
+        self.current_index = r.u32()?;
+        self.dir = u8::try_from(r.u32()?)
+            .ok()
+            .and_then(|dir| Direction::try_from(dir).ok())
+            .ok_or_else(|| read::Error(String::from("invalid zone direction")))?;
+        self.current_zone_id = r.id()?;
+
+        Ok(())
+    }
+}
+
+impl Default for ZoneGenealogy {
+    /// Flat grass ground.
+    fn default() -> Self {
+        Self {
+            zone_ids: vec![Id::new(String::from("VoidToGrass"))],
+            current_index: 0,
+            dir: Direction::North,
+            current_zone_id: Id::new(String::from("Grass")),
+        }
+    }
+}
+
 /// Either a 'normal' block or a free block.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BlockType {
     /// A 'normal' block.
     Normal(Block),
@@ -420,9 +521,14 @@ impl Default for BlockType {
 
 /// An item inside of a `Map`.
 #[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Item {
     /// ID of the item's model.
     pub model_id: Id,
+    /// Collection of the item's model, `26` for Stadium.
+    pub collection: u32,
+    /// Optional author of the item's model, e.g. `Nadeo`.
+    pub author: Option<Id>,
     /// Yaw rotation of the item.
     pub yaw: f32,
     /// Pitch rotation of the item.
@@ -476,8 +582,8 @@ impl Item {
     {
         r.u32()?; // 8
         self.model_id = r.id()?;
-        r.u32()?; // 26
-        let _author = r.optional_id()?; // "Nadeo"
+        self.collection = r.u32()?; // 26
+        self.author = r.optional_id()?; // "Nadeo"
         self.yaw = r.f32()?;
         self.pitch = r.f32()?;
         self.roll = r.f32()?;
@@ -512,16 +618,200 @@ impl Item {
     }
 }
 
+/// A single file embedded in a map.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EmbeddedFile {
+    /// Internal path of the file, e.g. `Items/MyItem.Item.Gbx`.
+    pub path: String,
+    /// Raw content of the file.
+    pub data: Vec<u8>,
+}
+
 /// Files embedded in a map.
+///
+/// # Examples
+///
+/// Embed a custom item into a map.
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut map = gbx::Map::reader().read_from_file("MyMap.Map.Gbx")?;
+///
+/// let item = std::fs::read("MyItem.Item.Gbx")?;
+/// map.embedded_files
+///     .get_or_insert_with(Default::default)
+///     .embed("Items/MyItem.Item.Gbx", item, None)?;
+///
+/// map.writer().write_to_file("MyMap.Map.Gbx")?;
+/// # Ok(()) }
+/// ```
+#[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EmbeddedFiles {
     /// Internal paths of the files embedded in the map.
     ///
     /// The order of the paths corresponds to the order of the files in the archive.
     pub paths: Vec<Id>,
+    /// Optional unique IDs of the authors of the embedded files.
+    ///
+    /// The order of the authors corresponds to the order of `paths`.
+    pub authors: Vec<Option<Id>>,
     /// All files embedded in the map as a raw ZIP archive.
     pub archive: Vec<u8>,
 }
 
+/// Compare two internal paths ignoring the kind of path separator.
+fn is_same_path(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.chars()
+            .zip(b.chars())
+            .all(|(a, b)| a == b || (matches!(a, '/' | '\\') && matches!(b, '/' | '\\')))
+}
+
+impl EmbeddedFiles {
+    /// Create an empty set of embedded files.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse the archive and get all embedded files.
+    ///
+    /// Returns an error if the archive is not a valid ZIP archive.
+    pub fn files(&self) -> read::Result<Vec<EmbeddedFile>> {
+        if self.archive.is_empty() {
+            return Ok(vec![]);
+        }
+
+        Ok(zip::read_archive(&self.archive)?
+            .into_iter()
+            .map(|entry| EmbeddedFile {
+                path: entry.name,
+                data: entry.data,
+            })
+            .collect())
+    }
+
+    /// Get the content of the embedded file at `path`.
+    ///
+    /// Only the requested file is decompressed.
+    /// Returns `None` if no such file is embedded.
+    pub fn file(&self, path: &str) -> read::Result<Option<Vec<u8>>> {
+        if self.archive.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(zip::read_file(&self.archive, |name| is_same_path(name, path))?.map(|entry| entry.data))
+    }
+
+    /// Extract all embedded files into the directory `dir`, keeping their internal paths.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidData`] if the archive is invalid
+    /// or if a path would leave `dir`.
+    pub fn extract<P>(&self, dir: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let files = self
+            .files()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+
+        for file in files {
+            let mut path = dir.as_ref().to_path_buf();
+
+            for component in file.path.split(['/', '\\']) {
+                match component {
+                    "" | "." => {}
+                    ".." => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("invalid embedded file path {}", file.path),
+                        ))
+                    }
+                    component => path.push(component),
+                }
+            }
+
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            fs::write(path, file.data)?;
+        }
+
+        Ok(())
+    }
+
+    /// Embed a file with the given internal `path` and content `data`.
+    ///
+    /// Custom items and blocks are usually embedded at `Items/<name>.Item.Gbx` or `Blocks/<name>.Block.Gbx`.
+    /// Backslashes in `path` are replaced by slashes.
+    /// An already embedded file with the same path is replaced and moved to the end.
+    /// Returns an error if the existing archive is invalid or if the files exceed the limits of a ZIP archive.
+    pub fn embed(&mut self, path: &str, data: Vec<u8>, author: Option<Id>) -> write::Result {
+        let path = path.replace('\\', "/");
+        let mut files = self.files_for_writing()?;
+        files.retain(|file| !is_same_path(&file.path, &path));
+        files.push(EmbeddedFile {
+            path: path.clone(),
+            data,
+        });
+        self.write_archive(files)?;
+        self.remove_path(&path);
+
+        self.paths.push(Id::new(path));
+        self.authors.push(author);
+
+        Ok(())
+    }
+
+    /// Remove the embedded file at `path`.
+    ///
+    /// Returns `false` if no such file is embedded.
+    /// Returns an error if the existing archive is invalid.
+    pub fn remove(&mut self, path: &str) -> Result<bool, write::Error> {
+        let mut files = self.files_for_writing()?;
+        let num_files = files.len();
+        files.retain(|file| !is_same_path(&file.path, path));
+        self.remove_path(path);
+
+        let removed = files.len() != num_files;
+        if removed {
+            self.write_archive(files)?;
+        }
+
+        Ok(removed)
+    }
+
+    fn remove_path(&mut self, path: &str) {
+        self.authors.resize(self.paths.len(), None);
+
+        if let Some(index) = self.paths.iter().position(|p| is_same_path(p, path)) {
+            self.paths.remove(index);
+            self.authors.remove(index);
+        }
+    }
+
+    /// Files of the existing archive which is about to be rewritten.
+    fn files_for_writing(&self) -> Result<Vec<EmbeddedFile>, write::Error> {
+        self.files()
+            .map_err(|err| write::Error(format!("invalid embedded files archive: {err}")))
+    }
+
+    fn write_archive(&mut self, files: Vec<EmbeddedFile>) -> write::Result {
+        let entries: Vec<zip::Entry> = files
+            .into_iter()
+            .map(|file| zip::Entry {
+                name: file.path,
+                data: file.data,
+            })
+            .collect();
+
+        self.archive = zip::write_archive(&entries)?;
+
+        Ok(())
+    }
+}
+
 /// Type corresponding to the file extension `Map.Gbx`.
 ///
 /// # Examples
@@ -542,6 +832,7 @@ pub struct EmbeddedFiles {
 /// map.writer().write_to_file("Validated.Map.Gbx")?;
 /// # Ok(()) }
 /// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Map {
     /// Name of the map.
     ///
@@ -580,6 +871,14 @@ pub struct Map {
     pub size: Vec3<u32>,
     /// All (free) blocks placed inside of the map.
     pub blocks: Vec<BlockType>,
+    /// Zone genealogies of all ground columns of the map.
+    ///
+    /// The zone genealogy of the column at `x`, `z` is at index `x * size.z + z`
+    /// for the size of the map when it was read or created, see [`Map::zone_genealogy`].
+    /// After changing [`Map::size`], the columns keep their zone genealogy and are laid out
+    /// for the new size by [`Map::zone_genealogy_mut`] or when writing.
+    /// Missing columns are written as flat grass.
+    pub zone_genealogies: Vec<ZoneGenealogy>,
     /// Optional map music.
     pub music: Option<FileRef>,
     /// All items placed inside of the map.
@@ -596,9 +895,51 @@ pub struct Map {
     pub ambiance_media: Option<media::Clip>,
     /// Files embedded in the map.
     pub embedded_files: Option<EmbeddedFiles>,
+    /// Kind of the map, `6` for a regular map.
+    pub map_kind: u8,
+    /// `true` if the map is locked.
+    pub is_locked: bool,
+    /// Hash of the password of the map, `0` if the map has no password.
+    pub password: u32,
+    /// Author of the map decoration, e.g. `Nadeo`.
+    pub decoration_author: Id,
+    /// Origin of the map.
+    pub map_origin: [f32; 2],
+    /// Target of the map.
+    pub map_target: [f32; 2],
+    /// Script type of the map.
+    pub map_type: MapType,
+    /// Script metadata of the map.
+    ///
+    /// New maps have the default metadata of a race map.
+    /// When writing, default traits of other map types which still have their default value
+    /// are replaced by the default traits of [`Map::map_type`].
+    pub script_metadata: ScriptMetadata,
+    /// Style of the map.
+    pub map_style: String,
+    /// UID of the lightmap cache.
+    pub lightmap_cache_uid: u64,
+    /// Version of the lightmap.
+    pub lightmap_version: u8,
+    /// ID of the title of the map, e.g. `TMStadium`.
+    pub title_id: Id,
+    /// Mode of the editor in which the map was last saved.
+    pub editor_mode: u32,
+    /// Version of the author information.
+    pub author_version: u32,
+    /// Author score of the map.
+    pub author_score: u32,
+    /// Attributes of the XML header of the map as read from the file.
+    ///
+    /// The XML header is not written from this field, but generated from the other fields of the map.
+    pub xml_header: XmlHeader,
 
     uid: RefCell<Option<Id>>,
     baked_blocks: Vec<BlockType>,
+    /// Fingerprint of the ground of the map which the baked blocks are made for.
+    baked_blocks_ground: u64,
+    /// Width and depth of the map which the zone genealogies are laid out for.
+    zone_genealogies_size: (u32, u32),
 }
 
 impl Map {
@@ -607,18 +948,76 @@ impl Map {
         Self::default()
     }
 
+    /// Get the zone genealogy of the ground column at `x`, `z`.
+    pub fn zone_genealogy(&self, x: u32, z: u32) -> Option<&ZoneGenealogy> {
+        if x >= self.size.x || z >= self.size.z {
+            return None;
+        }
+
+        let (size_x, size_z) = self.zone_genealogies_size;
+
+        if x >= size_x || z >= size_z {
+            return None;
+        }
+
+        self.zone_genealogies.get((x * size_z + z) as usize)
+    }
+
+    /// Get the mutable zone genealogy of the ground column at `x`, `z`.
+    ///
+    /// If [`Map::size`] has changed, the zone genealogies are laid out for the new size first,
+    /// see [`Map::zone_genealogies`].
+    pub fn zone_genealogy_mut(&mut self, x: u32, z: u32) -> Option<&mut ZoneGenealogy> {
+        self.relayout_zone_genealogies();
+
+        if x >= self.size.x || z >= self.size.z {
+            return None;
+        }
+
+        self.zone_genealogies
+            .get_mut((x * self.size.z + z) as usize)
+    }
+
+    /// Zone genealogies laid out for the current size of the map.
+    ///
+    /// Columns which did not exist before the map was resized get the default zone genealogy.
+    fn resized_zone_genealogies(&self) -> Vec<ZoneGenealogy> {
+        let mut zone_genealogies = Vec::with_capacity(self.size.x as usize * self.size.z as usize);
+
+        for x in 0..self.size.x {
+            for z in 0..self.size.z {
+                zone_genealogies.push(self.zone_genealogy(x, z).cloned().unwrap_or_default());
+            }
+        }
+
+        zone_genealogies
+    }
+
+    fn relayout_zone_genealogies(&mut self) {
+        if self.zone_genealogies_size != (self.size.x, self.size.z) {
+            self.zone_genealogies = self.resized_zone_genealogies();
+            self.zone_genealogies_size = (self.size.x, self.size.z);
+        }
+    }
+
     /// Get the unique ID of the map.
     ///
     /// The UID is a 20 byte value which is URL-safe Base63 encoded.
-    /// The first 16 bytes are a v4 UUID,
+    /// For UIDs generated by this crate, the first 16 bytes are a marker followed by
+    /// the bytes of a v4 UUID or of a hash of the map, see [`UidMode`],
     /// and the last 4 bytes a ZLIB CRC-32 checksum of the map serialized as
-    /// GBX without user data and with an uncompressed body.
+    /// GBX without UID, without user data and with an uncompressed body.
     pub fn uid(&self) -> Option<Id> {
         let uid = self.uid.take();
         self.uid.replace(uid.clone());
         uid
     }
 
+    /// `true` if the map is validated, i.e. it has a [`Map::validation`].
+    pub fn is_validated(&self) -> bool {
+        self.validation.is_some()
+    }
+
     /// All grass blocks and clips inside the map.
     ///
     /// The `skin` and `waypoint_property` fields of the baked blocks are always `None`.
@@ -626,6 +1025,109 @@ impl Map {
         &self.baked_blocks
     }
 
+    /// Mutable grass blocks and clips inside the map.
+    ///
+    /// The baked blocks are written as they are as long as the ground of the map does not change,
+    /// i.e. [`Map::size`], [`Map::no_stadium`], the ground blocks and the current zones.
+    /// Use [`Map::regenerate_baked_blocks`] to bring them in line with the blocks and zones of the map.
+    /// The `skin` and `waypoint_property` fields of the baked blocks are ignored when writing.
+    pub fn baked_blocks_mut(&mut self) -> &mut Vec<BlockType> {
+        self.baked_blocks_ground = self.ground_fingerprint();
+        &mut self.baked_blocks
+    }
+
+    /// Regenerate the baked blocks from the size, decoration, zone genealogies and blocks of the map.
+    ///
+    /// Every ground column gets a baked block of its current zone,
+    /// except columns which are covered by a ground block.
+    /// Maps without a stadium have no baked ground.
+    /// All other baked blocks such as clips are removed, since they are recomputed by the game.
+    /// Columns outside of the 256 by 256 grid are skipped, such maps cannot be written.
+    ///
+    /// Baked blocks which were read or set for a different ground are regenerated when writing,
+    /// e.g. after resizing the map, changing a zone or moving a ground block.
+    pub fn regenerate_baked_blocks(&mut self) {
+        self.baked_blocks = self.generated_baked_blocks();
+        self.baked_blocks_ground = self.ground_fingerprint();
+    }
+
+    /// Baked blocks generated from the current state of the map, see [`Map::regenerate_baked_blocks`].
+    fn generated_baked_blocks(&self) -> Vec<BlockType> {
+        let ground_height = match self.ground_height() {
+            Some(ground_height) => ground_height,
+            None => return vec![],
+        };
+
+        let default_zone_genealogy = ZoneGenealogy::default();
+
+        let covered_columns: HashSet<(u8, u8)> = self.ground_columns(ground_height).collect();
+
+        let mut baked_blocks = vec![];
+
+        for x in ground_columns(self.size.x) {
+            for z in ground_columns(self.size.z) {
+                if covered_columns.contains(&(x, z)) {
+                    continue;
+                }
+
+                let zone_genealogy = self
+                    .zone_genealogy(x as u32, z as u32)
+                    .unwrap_or(&default_zone_genealogy);
+
+                baked_blocks.push(BlockType::Normal(Block {
+                    model_id: Id::clone(&zone_genealogy.current_zone_id),
+                    coord: Vec3 {
+                        x,
+                        y: ground_height,
+                        z,
+                    },
+                    is_ground: true,
+                    ..Default::default()
+                }));
+            }
+        }
+
+        baked_blocks
+    }
+
+    /// Columns covered by ground blocks at `ground_height`.
+    fn ground_columns(&self, ground_height: u8) -> impl Iterator<Item = (u8, u8)> + '_ {
+        self.blocks.iter().filter_map(move |block| match block {
+            BlockType::Normal(block) if block.is_ground && block.coord.y == ground_height => {
+                Some((block.coord.x, block.coord.z))
+            }
+            _ => None,
+        })
+    }
+
+    /// Hash of everything the baked blocks are generated from, see [`Map::regenerate_baked_blocks`].
+    fn ground_fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        (self.size.x, self.size.z, self.no_stadium).hash(&mut hasher);
+
+        if let Some(ground_height) = self.ground_height() {
+            let mut covered_columns: Vec<(u8, u8)> = self.ground_columns(ground_height).collect();
+            covered_columns.sort_unstable();
+            covered_columns.dedup();
+            covered_columns.hash(&mut hasher);
+
+            for x in ground_columns(self.size.x) {
+                for z in ground_columns(self.size.z) {
+                    self.zone_genealogy(x as u32, z as u32)
+                        .map(|zone_genealogy| &zone_genealogy.current_zone_id)
+                        .hash(&mut hasher);
+                }
+            }
+        }
+
+        hasher.finish()
+    }
+
+    /// Height of the baked ground in blocks, `None` if the decoration has no stadium.
+    fn ground_height(&self) -> Option<u8> {
+        (!self.no_stadium).then_some(GROUND_HEIGHT)
+    }
+
     /// Get a map reader.
     pub fn reader() -> ReaderBuilder<Self> {
         ReaderBuilder::new(
@@ -670,8 +1172,14 @@ impl Map {
                     0x03043042,
                     ReadBodyChunk::ReadSkippable(Self::read_chunk_03043042),
                 ),
-                (0x03043043, ReadBodyChunk::Skip),
-                (0x03043044, ReadBodyChunk::Skip),
+                (
+                    0x03043043,
+                    ReadBodyChunk::ReadSkippable(Self::read_chunk_03043043),
+                ),
+                (
+                    0x03043044,
+                    ReadBodyChunk::ReadSkippable(Self::read_chunk_03043044),
+                ),
                 (
                     0x03043048,
                     ReadBodyChunk::ReadSkippable(Self::read_chunk_03043048),
@@ -730,82 +1238,26 @@ impl Map {
     }
 }
 
-fn base63_encode_url_safe(mut input: Vec<u8>) -> String {
-    const ALPHABET: &[u8] = b"_0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
-
-    let mut output = String::new();
-
-    if input.is_empty() {
-        return output;
-    }
-
-    let mut last = input.len() - 1;
-
-    loop {
-        while input[last] == 0 {
-            if last == 0 {
-                return output;
-            }
-
-            last -= 1;
-        }
-
-        let mut i = last;
-        let mut m = 0;
-
-        loop {
-            let value = m * u8::MAX as u16 + input[i] as u16;
-            input[i] = (value / 63) as u8; // quotient
-            m = value % 63; // remainder
-
-            if i == 0 {
-                break;
-            }
-
-            i -= 1;
-        }
-
-        output.push(ALPHABET[m as usize] as char);
-    }
-}
-
 impl Map {
     /// Get a writer for this map.
     ///
-    /// Every time this function is called, the UID of the map is recomputed.
-    pub fn writer(&self) -> WriterBuilder<Self> {
-        let mut buf = vec![];
-
-        self.writer_without_computing_uid()
-            .user_data(false)
-            .compress_body(false)
-            .write_to(&mut buf)
-            .unwrap();
-
-        let uuid = Uuid::new_v4();
-        let checksum = unsafe { libz_sys::crc32(0, buf.as_ptr(), buf.len() as u32) };
-
-        let mut uid = [0; 20];
-        uid[..16].copy_from_slice(uuid.as_bytes());
-        uid[16..].copy_from_slice(&checksum.to_le_bytes()[..4]);
-
-        self.uid
-            .replace(Some(Id::new(base63_encode_url_safe(uid.to_vec()))));
-
-        self.writer_without_computing_uid()
+    /// Every time this function is called, a new random UID is generated for the map,
+    /// see [`Map::writer_with_uid`] to control the UID.
+    pub fn writer(&self) -> WriterBuilder<'_, Self> {
+        self.writer_with_uid(UidMode::Random)
     }
 
-    fn writer_without_computing_uid(&self) -> WriterBuilder<Self> {
+    fn writer_without_computing_uid(&self) -> WriterBuilder<'_, Self> {
         WriterBuilder::new(
             self,
             0x03043000,
             vec![
-                (0x03043002, |n, w| Self::write_chunk_03043002(n, w)),
-                (0x03043003, |n, w| Self::write_chunk_03043003(n, w)),
-                (0x03043004, |n, w| Self::write_chunk_03043004(n, w)),
-                (0x03043005, |n, w| Self::write_chunk_03043005(n, w)),
-                (0x03043007, |n, w| Self::write_chunk_03043007(n, w)),
-                (0x03043008, |n, w| Self::write_chunk_03043008(n, w)),
+                (0x03043002, |n, o, w| Self::write_chunk_03043002(n, o, w)),
+                (0x03043003, |n, _, w| Self::write_chunk_03043003(n, w)),
+                (0x03043004, |n, _, w| Self::write_chunk_03043004(n, w)),
+                (0x03043005, |n, _, w| Self::write_chunk_03043005(n, w)),
+                (0x03043007, |n, _, w| Self::write_chunk_03043007(n, w)),
+                (0x03043008, |n, _, w| Self::write_chunk_03043008(n, w)),
             ],
             |n, w| Self::write_body(n, w),
         )
@@ -865,7 +1317,7 @@ impl Map {
         r.u32()?;
         r.u32()?;
         r.u32()?;
-        let _editor_mode = r.u32()?;
+        self.editor_mode = r.u32()?;
         r.u32()?;
         self.num_cps = r.u32()?;
         self.num_laps = is_multilap.then_some(r.u32()?);
@@ -905,25 +1357,25 @@ impl Map {
         r.u32()?;
         self.author_uid = r.id()?;
         self.name = r.string()?;
-        let _map_kind = r.u8()?;
-        let _locked = r.u32()?;
-        let _password = r.u32()?;
+        self.map_kind = r.u8()?;
+        self.is_locked = r.u32()? != 0;
+        self.password = r.u32()?;
         let deco_id = r.id()?;
         self.no_stadium = does_deco_have_no_stadium(&deco_id);
         self.day_time = day_time_from_deco_id(&deco_id)?;
         r.u32()?;
-        let _deco_author = r.id()?;
-        let _map_origin = r.vec2f32()?;
-        let _map_target = r.vec2f32()?;
+        self.decoration_author = r.id()?;
+        self.map_origin = r.vec2f32()?;
+        self.map_target = r.vec2f32()?;
         r.u32()?;
         r.u32()?;
         r.u32()?;
         r.u32()?;
-        let _map_type = r.string()?;
-        let _map_style = r.string()?;
-        let _lightmap_cache_uid = r.u64()?;
-        let _lightmap_version = r.u8()?;
-        let _title_id = r.id()?;
+        self.map_type = MapType::from(r.string()?);
+        self.map_style = r.string()?;
+        self.lightmap_cache_uid = r.u64()?;
+        self.lightmap_version = r.u8()?;
+        self.title_id = r.id()?;
 
         Ok(())
     }
@@ -938,117 +1390,57 @@ impl Map {
     }
 }
 
-fn xml_attributes_to_map(attributes: Attributes) -> HashMap<String, String> {
-    attributes
-        .map(|attribute| {
-            let attribute = attribute.unwrap();
-            (
-                String::from_utf8(attribute.key.local_name().as_ref().to_vec()).unwrap(),
-                attribute.unescape_value().unwrap().into_owned(),
-            )
-        })
-        .collect()
-}
-
 impl Map {
     fn read_chunk_03043005<R, I, N>(&mut self, r: &mut Reader<R, I, N>) -> read::Result<()>
     where
         R: Read,
     {
         let xml = r.string()?;
-        let mut xml_reader = quick_xml::Reader::from_str(&xml);
+        let header = XmlHeader::parse(&xml)?;
 
-        match xml_reader.read_event().unwrap() {
-            Event::Start(e) if e.local_name().as_ref() == b"header" => {
-                let _attributes = xml_attributes_to_map(e.attributes());
-            }
-            _ => panic!(),
+        if let Some(ref uid) = header.uid {
+            self.uid = RefCell::new(Some(Id::new(uid.clone())));
         }
-
-        match xml_reader.read_event().unwrap() {
-            Event::Empty(e) if e.local_name().as_ref() == b"ident" => {
-                let attributes = xml_attributes_to_map(e.attributes());
-                self.uid = RefCell::new(Some(Id::new(attributes.get("uid").unwrap().clone())));
-                self.name = attributes.get("name").unwrap().clone();
-                self.author_uid = Id::new(attributes.get("author").unwrap().clone());
-                self.author_zone = attributes.get("authorzone").unwrap().clone();
-            }
-            _ => panic!(),
+        if let Some(ref name) = header.name {
+            self.name = name.clone();
         }
-
-        match xml_reader.read_event().unwrap() {
-            Event::Empty(e) if e.local_name().as_ref() == b"desc" => {
-                let attributes = xml_attributes_to_map(e.attributes());
-                let mood = attributes.get("mood").unwrap().as_str();
-                let mood = mood
-                    .strip_suffix("16x12")
-                    .or(mood.strip_suffix(" (no stadium)"))
-                    .unwrap_or(mood);
-                self.day_time = match mood {
-                    "Sunrise" => SUNRISE_MOOD_TIME,
-                    "Day" => DAY_MOOD_TIME,
-                    "Sunset" => SUNSET_MOOD_TIME,
-                    "Night" => NIGHT_MOOD_TIME,
-                    _ => panic!(),
-                };
-                self.cost = attributes.get("displaycost").unwrap().parse().unwrap();
-            }
-            _ => panic!(),
+        if let Some(ref author) = header.author {
+            self.author_uid = Id::new(author.clone());
         }
-
-        match xml_reader.read_event().unwrap() {
-            Event::Empty(e) if e.local_name().as_ref() == b"playermodel" => {}
-            _ => panic!(),
+        if let Some(ref author_zone) = header.author_zone {
+            self.author_zone = author_zone.clone();
         }
-
-        match xml_reader.read_event().unwrap() {
-            Event::Empty(e) if e.local_name().as_ref() == b"times" => {
-                let attributes = xml_attributes_to_map(e.attributes());
-
-                let medal_times = if attributes.get("bronze").unwrap() != "-1"
-                    && attributes.get("silver").unwrap() != "-1"
-                    && attributes.get("gold").unwrap() != "-1"
-                    && attributes.get("authortime").unwrap() != "-1"
-                {
-                    Some((
-                        attributes.get("bronze").unwrap().parse().unwrap(),
-                        attributes.get("silver").unwrap().parse().unwrap(),
-                        attributes.get("gold").unwrap().parse().unwrap(),
-                        attributes.get("authortime").unwrap().parse().unwrap(),
-                    ))
-                } else {
-                    None
-                };
-
-                self.set_validation_times(medal_times);
+        if let Some(ref mood) = header.mood {
+            let mood = mood
+                .strip_suffix("16x12")
+                .or(mood.strip_suffix(" (no stadium)"))
+                .unwrap_or(mood);
+            match mood {
+                "Sunrise" => self.day_time = SUNRISE_MOOD_TIME,
+                "Day" => self.day_time = DAY_MOOD_TIME,
+                "Sunset" => self.day_time = SUNSET_MOOD_TIME,
+                "Night" => self.day_time = NIGHT_MOOD_TIME,
+                _ => {}
             }
-            _ => panic!(),
         }
-
-        match xml_reader.read_event().unwrap() {
-            Event::Start(e) if e.local_name().as_ref() == b"deps" => {}
-            _ => panic!(),
+        if let Some(cost) = header.display_cost {
+            self.cost = cost;
         }
 
-        loop {
-            match xml_reader.read_event().unwrap() {
-                Event::Empty(e) if e.local_name().as_ref() == b"dep" => {
-                    let _attributes = xml_attributes_to_map(e.attributes());
-                }
-                Event::End(e) if e.local_name().as_ref() == b"deps" => break,
-                _ => panic!(),
+        let medal_times = match (
+            header.bronze_time,
+            header.silver_time,
+            header.gold_time,
+            header.author_time,
+        ) {
+            (Some(bronze), Some(silver), Some(gold), Some(author)) => {
+                Some((bronze, silver, gold, author))
             }
-        }
-
-        match xml_reader.read_event().unwrap() {
-            Event::End(e) if e.local_name().as_ref() == b"header" => {}
-            _ => panic!(),
-        }
+            _ => None,
+        };
+        self.set_validation_times(medal_times);
 
-        match xml_reader.read_event().unwrap() {
-            Event::Eof => {}
-            _ => panic!(),
-        }
+        self.xml_header = header;
 
         Ok(())
     }
@@ -1075,7 +1467,7 @@ impl Map {
         R: Read,
     {
         r.u32()?;
-        let _author_version = r.u32()?;
+        self.author_version = r.u32()?;
         self.author_uid = Id::new(r.string()?);
         self.author_name = r.string()?;
         self.author_zone = r.string()?;
@@ -1124,7 +1516,7 @@ impl Map {
 
             r.chunk_id(0x0305B004)?;
             self.set_validation_times(read_medal_times(r)?);
-            let _author_score = r.u32()?;
+            self.author_score = r.u32()?;
 
             r.chunk_id(0x0305B008)?;
             r.u32()?;
@@ -1139,15 +1531,16 @@ impl Map {
             }
 
             r.skippable_chunk_id(0x0305B00E)?;
-            let _map_type = r.string()?;
-            let _map_style = r.string()?;
-            let _is_validated = r.bool32()?;
+            self.map_type = MapType::from(r.string()?);
+            self.map_style = r.string()?;
+            r.bool32()?;
 
             r.node_end()?;
 
             Ok(())
         })?;
-        let _map_kind = r.u32()?;
+        self.map_kind =
+            u8::try_from(r.u32()?).map_err(|_| read::Error(String::from("invalid map kind")))?;
 
         Ok(())
     }
@@ -1333,7 +1726,7 @@ impl Map {
         R: Read,
     {
         r.u32()?;
-        r.u32()?;
+        self.author_version = r.u32()?;
         self.author_uid = Id::new(r.string()?);
         self.author_name = r.string()?;
         self.author_zone = r.string()?;
@@ -1342,6 +1735,41 @@ impl Map {
         Ok(())
     }
 
+    fn read_chunk_03043043<R, I, N>(&mut self, r: &mut Reader<R, I, N>) -> read::Result<()>
+    where
+        R: Read,
+    {
+        r.u32()?;
+        let size = r.u32()?;
+        let bytes = r.bytes(size as usize)?;
+        {
+            let mut r = Reader::with_id_state(Cursor::new(bytes), read::IdState::new());
+            self.zone_genealogies = r.list(|r| r.flat_node(0x0311D000, ZoneGenealogy::read))?;
+        }
+
+        if self.zone_genealogies.len() as u64 != self.size.x as u64 * self.size.z as u64 {
+            return Err(read::Error(String::from(
+                "zone genealogy count does not match the map size",
+            )));
+        }
+
+        self.zone_genealogies_size = (self.size.x, self.size.z);
+
+        Ok(())
+    }
+
+    fn read_chunk_03043044<R, I, N>(&mut self, r: &mut Reader<R, I, N>) -> read::Result<()>
+    where
+        R: Read,
+    {
+        r.u32()?;
+        let size = r.u32()?;
+        let bytes = r.bytes(size as usize)?;
+        self.script_metadata = ScriptMetadata::from_bytes(bytes);
+
+        Ok(())
+    }
+
     fn read_chunk_03043048<R, I, N>(&mut self, r: &mut Reader<R, I, N>) -> read::Result<()>
     where
         R: Read + Seek,
@@ -1388,6 +1816,7 @@ impl Map {
 
             self.baked_blocks.push(block_type);
         }
+        self.baked_blocks_ground = self.ground_fingerprint();
         r.u32()?;
         r.u32()?;
 
@@ -1420,17 +1849,24 @@ impl Map {
         let size = r.u32()?;
         {
             let mut r = Reader::with_id_state(r.take(size as u64), read::IdState::new());
-            let paths = r.list(|r| {
-                let path = r.id()?;
-                r.u32()?; // 26
-                let _author_id = r.optional_id()?; // "pTuyJG9STcCN_11BiU3t0Q"
-
-                Ok(path)
-            })?;
+            let (paths, authors) = r
+                .list(|r| {
+                    let path = r.id()?;
+                    r.u32()?; // 26
+                    let author = r.optional_id()?; // "pTuyJG9STcCN_11BiU3t0Q"
+
+                    Ok((path, author))
+                })?
+                .into_iter()
+                .unzip();
             let size = r.u32()?;
             if size > 0 {
                 let archive = r.bytes(size as usize)?;
-                self.embedded_files = Some(EmbeddedFiles { archive, paths });
+                self.embedded_files = Some(EmbeddedFiles {
+                    paths,
+                    authors,
+                    archive,
+                });
             }
             r.u32()?; // 0
         }
@@ -1562,10 +1998,18 @@ impl Map {
         Ok(())
     }
 
-    fn write_chunk_03043002<W, I, N>(&self, mut w: Writer<W, I, N>) -> write::Result
+    fn write_chunk_03043002<W, I, N>(
+        &self,
+        options: &write::WriteOptions,
+        mut w: Writer<W, I, N>,
+    ) -> write::Result
     where
         W: Write,
     {
+        if options.recompute_num_cps {
+            self.check_laps()?;
+        }
+
         w.u8(13)?;
         w.u32(0)?;
         w.u32(0xFFFFFFFF)?;
@@ -1573,14 +2017,18 @@ impl Map {
         w.u32(0xFFFFFFFF)?;
         w.u32(0xFFFFFFFF)?;
         w.u32(318)?;
+        w.u32(self.num_laps.is_some() as u32)?;
         w.u32(0)?;
         w.u32(0)?;
         w.u32(0)?;
+        w.u32(self.editor_mode)?;
         w.u32(0)?;
-        w.u32(0)?;
-        w.u32(0)?;
-        w.u32(1)?;
-        w.u32(1)?;
+        w.u32(if options.recompute_num_cps {
+            self.waypoint_summary().num_checkpoints
+        } else {
+            self.num_cps
+        })?;
+        w.u32(self.num_laps.unwrap_or(1))?;
 
         Ok(())
     }
@@ -1617,25 +2065,25 @@ impl Map {
         w.u32(26)?;
         w.id(Some(&self.author_uid))?;
         w.string(&self.name)?;
-        w.u8(6)?;
-        w.u32(0)?;
-        w.u32(0)?;
+        w.u8(self.map_kind)?;
+        w.u32(self.is_locked as u32)?;
+        w.u32(self.password)?;
         w.id(Some(&self.deco_id()))?;
         w.u32(26)?;
-        w.id(Some("Nadeo"))?;
-        w.u32(0)?;
-        w.u32(0)?;
+        w.id(Some(&self.decoration_author))?;
+        w.f32(self.map_origin[0])?;
+        w.f32(self.map_origin[1])?;
+        w.f32(self.map_target[0])?;
+        w.f32(self.map_target[1])?;
         w.u32(0)?;
         w.u32(0)?;
         w.u32(0)?;
         w.u32(0)?;
-        w.u32(0)?;
-        w.u32(0)?;
-        w.string("TrackMania\\TM_Race")?;
-        w.u32(0)?;
-        w.u64(0xFF58B6734983CC85)?;
-        w.u8(0)?;
-        w.id(Some("TMStadium"))?;
+        w.string(self.map_type.as_str())?;
+        w.string(&self.map_style)?;
+        w.u64(self.lightmap_cache_uid)?;
+        w.u8(self.lightmap_version)?;
+        w.id(Some(&self.title_id))?;
 
         Ok(())
     }
@@ -1661,8 +2109,8 @@ impl Map {
             .with_attribute(("type", "map"))
             .with_attribute(("exever", "3.3.0"))
             .with_attribute(("exebuild", "2023-01-26_15_32"))
-            .with_attribute(("title", "TMStadium"))
-            .with_attribute(("lightmap", "0"))
+            .with_attribute(("title", self.title_id.as_str()))
+            .with_attribute(("lightmap", self.lightmap_version.to_string().as_str()))
             .write_inner_content(|xml_writer| {
                 xml_writer
                     .create_element("ident")
@@ -1703,11 +2151,11 @@ impl Map {
                     .with_attribute(("envir", "Stadium"))
                     .with_attribute(("mood", self.mood_id()))
                     .with_attribute(("type", "Race"))
-                    .with_attribute(("maptype", "TrackMania\\TM_Race"))
-                    .with_attribute(("mapstyle", ""))
+                    .with_attribute(("maptype", self.map_type.as_str()))
+                    .with_attribute(("mapstyle", self.map_style.as_str()))
                     .with_attribute((
                         "validated",
-                        (self.validation.is_some() as u8).to_string().as_str(),
+                        (self.is_validated() as u8).to_string().as_str(),
                     ))
                     .with_attribute((
                         "nblaps",
@@ -1755,12 +2203,26 @@ impl Map {
                         "authortime",
                         author_time.unwrap_or(String::from("-1")).as_str(),
                     ))
-                    .with_attribute(("authorscore", "0"))
+                    .with_attribute(("authorscore", self.author_score.to_string().as_str()))
                     .write_empty()?;
 
                 xml_writer
                     .create_element("deps")
-                    .write_inner_content(|_| Ok(()))?;
+                    .write_inner_content(|xml_writer| {
+                        for dep in self.header_deps() {
+                            let mut element = xml_writer
+                                .create_element("dep")
+                                .with_attribute(("file", dep.file.as_str()));
+
+                            if let Some(ref url) = dep.url {
+                                element = element.with_attribute(("url", url.as_str()));
+                            }
+
+                            element.write_empty()?;
+                        }
+
+                        Ok(())
+                    })?;
 
                 Ok(())
             })
@@ -1797,7 +2259,7 @@ impl Map {
         W: Write,
     {
         w.u32(1)?;
-        w.u32(0)?;
+        w.u32(self.author_version)?;
         w.string(&self.author_uid)?;
         w.string(&self.author_name)?;
         w.string(&self.author_zone)?;
@@ -1812,6 +2274,21 @@ impl Map {
         I: BorrowMut<write::IdState>,
         N: BorrowMut<write::NodeState>,
     {
+        if self.size.x > MAX_GROUND_SIZE || self.size.z > MAX_GROUND_SIZE {
+            return Err(write::Error(format!(
+                "map size {}x{} exceeds the maximum ground size of {MAX_GROUND_SIZE}x{MAX_GROUND_SIZE}",
+                self.size.x, self.size.z
+            )));
+        }
+
+        let generated_baked_blocks;
+        let baked_blocks = if self.baked_blocks_ground == self.ground_fingerprint() {
+            &self.baked_blocks
+        } else {
+            generated_baked_blocks = self.generated_baked_blocks();
+            &generated_baked_blocks
+        };
+
         w.u32(0x0304300D)?;
         w.id(None)?;
         w.u32(0xFFFFFFFF)?;
@@ -1843,7 +2320,7 @@ impl Map {
                 w.u32(0xFFFFFFFF)?;
                 w.u32(0xFFFFFFFF)?;
             }
-            w.u32(0)?;
+            w.u32(self.author_score)?;
 
             w.u32(0x0305B008)?;
             w.u32(60000)?;
@@ -1863,7 +2340,7 @@ impl Map {
                     w.u32(0xFFFFFFFF)?;
                 }
                 w.u32(60000)?;
-                w.u32(0)?;
+                w.u32(self.author_score)?;
 
                 Ok(())
             })?;
@@ -1872,16 +2349,16 @@ impl Map {
             w.u32(0xFFFFFFFF)?;
 
             w.skippable_chunk(0x0305B00E, |mut w| {
-                w.string("TrackMania\\TM_Race")?;
-                w.u32(0)?;
-                w.bool(self.validation.is_some())?;
+                w.string(self.map_type.as_str())?;
+                w.string(&self.map_style)?;
+                w.bool(self.is_validated())?;
 
                 Ok(())
             })?;
 
             Ok(())
         })?;
-        w.u32(6)?;
+        w.u32(self.map_kind as u32)?;
 
         w.skippable_chunk(0x03043018, |mut w| {
             w.bool(self.num_laps.is_some())?;
@@ -1903,10 +2380,10 @@ impl Map {
         w.string(&self.name)?;
         w.id(Some(&self.deco_id()))?;
         w.u32(26)?;
-        w.id(Some("Nadeo"))?;
-        w.u32(48)?;
-        w.u32(40)?;
-        w.u32(48)?;
+        w.id(Some(&self.decoration_author))?;
+        w.u32(self.size.x)?;
+        w.u32(self.size.y)?;
+        w.u32(self.size.z)?;
         w.u32(0)?;
         w.u32(6)?;
         w.u32(self.blocks.len() as u32)?;
@@ -2054,7 +2531,7 @@ impl Map {
 
         w.skippable_chunk(0x03043042, |mut w| {
             w.u32(1)?;
-            w.u32(0)?;
+            w.u32(self.author_version)?;
             w.string(&self.author_uid)?;
             w.string(&self.author_name)?;
             w.string(&self.author_zone)?;
@@ -2067,16 +2544,23 @@ impl Map {
             let mut bytes = vec![];
             {
                 let mut w = Writer::with_id_state(&mut bytes, write::IdState::new());
-                w.u32(2304)?;
-                for _ in 0..2304 {
+                let default_zone_genealogy = ZoneGenealogy::default();
+                let columns = (0..self.size.x).flat_map(|x| (0..self.size.z).map(move |z| (x, z)));
+                w.u32(self.size.x * self.size.z)?;
+                for (x, z) in columns {
+                    let zone_genealogy =
+                        self.zone_genealogy(x, z).unwrap_or(&default_zone_genealogy);
+
                     w.u32(0x0311D000)?;
 
                     w.u32(0x0311D002)?;
-                    w.u32(1)?;
-                    w.id(Some("VoidToGrass"))?;
-                    w.u32(0)?;
-                    w.u32(0)?;
-                    w.id(Some("Grass"))?;
+                    w.u32(zone_genealogy.zone_ids.len() as u32)?;
+                    for zone_id in &zone_genealogy.zone_ids {
+                        w.id(Some(zone_id))?;
+                    }
+                    w.u32(zone_genealogy.current_index)?;
+                    w.u32(u8::from(zone_genealogy.dir) as u32)?;
+                    w.id(Some(&zone_genealogy.current_zone_id))?;
 
                     w.u32(0xFACADE01)?;
                 }
@@ -2091,31 +2575,9 @@ impl Map {
 
         w.skippable_chunk(0x03043044, |mut w| {
             let mut bytes = vec![];
-            {
-                let mut w = Writer::new(&mut bytes);
-
-                w.u32(0x11002000)?;
-                w.u32(6)?;
-                w.u8(2)?;
-                w.u8(2)?;
-                w.u8(7)?;
-                w.u8(0)?;
-                w.u8(2)?;
-                w.u8(2)?;
-                w.u8(25)?;
-                w.bytes(b"LibMapType_MapTypeVersion")?;
-                w.u8(0)?;
-                w.u8(1)?;
-                w.u8(0)?;
-                w.u8(0)?;
-                w.u8(0)?;
-                w.u8(28)?;
-                w.bytes(b"Race_AuthorRaceWaypointTimes")?;
-                w.u8(1)?;
-                w.u8(0)?;
-
-                w.u32(0xFACADE01)?;
-            }
+            self.script_metadata
+                .with_defaults(&self.map_type)
+                .write(&mut Writer::new(&mut bytes))?;
 
             w.u32(0)?;
             w.u32(bytes.len() as u32)?;
@@ -2127,8 +2589,8 @@ impl Map {
         w.skippable_chunk(0x03043048, |mut w| {
             w.u32(0)?;
             w.u32(6)?;
-            w.u32(self.baked_blocks.len() as u32)?;
-            for baked_block in &self.baked_blocks {
+            w.u32(baked_blocks.len() as u32)?;
+            for baked_block in baked_blocks {
                 let mut flags = 0;
 
                 if baked_block.skin().is_some() {
@@ -2211,7 +2673,7 @@ impl Map {
 
         w.skippable_chunk(0x03043051, |mut w| {
             w.u32(0)?;
-            w.id(Some("TMStadium"))?;
+            w.id(Some(&self.title_id))?;
             w.string("date=2023-01-26_15_32 git=116308-bbf6df4c7ba GameVersion=3.3.0")?;
 
             Ok(())
@@ -2234,9 +2696,25 @@ impl Map {
         w.skippable_chunk(0x03043054, |mut w| {
             let mut bytes = vec![];
             {
-                let mut w = Writer::new(&mut bytes);
-                w.u32(0)?;
-                w.u32(0)?;
+                let mut w = Writer::with_id_state(&mut bytes, write::IdState::new());
+                match self.embedded_files {
+                    Some(ref embedded_files) => {
+                        w.u32(embedded_files.paths.len() as u32)?;
+                        for (index, path) in embedded_files.paths.iter().enumerate() {
+                            let author = embedded_files.authors.get(index).and_then(Option::as_ref);
+
+                            w.id(Some(path))?;
+                            w.u32(26)?;
+                            w.id(author.map(|author| author.as_str()))?;
+                        }
+                        w.u32(embedded_files.archive.len() as u32)?;
+                        w.bytes(&embedded_files.archive)?;
+                    }
+                    None => {
+                        w.u32(0)?;
+                        w.u32(0)?;
+                    }
+                }
                 w.u32(0)?;
             }
 
@@ -2335,7 +2813,7 @@ impl Map {
                     w.f32(free_block.roll)?;
                 }
             }
-            for baked_block in &self.baked_blocks {
+            for baked_block in baked_blocks {
                 if let BlockType::Free(free_block) = baked_block {
                     w.f32(free_block.pos.x)?;
                     w.f32(free_block.pos.y)?;
@@ -2371,7 +2849,7 @@ impl Map {
             for block in &self.blocks {
                 w.u8(block.color().into())?;
             }
-            for baked_block in &self.baked_blocks {
+            for baked_block in baked_blocks {
                 w.u8(baked_block.color().into())?;
             }
             for item in &self.items {
@@ -2422,7 +2900,7 @@ impl Map {
             for block in &self.blocks {
                 w.u8(block.lightmap_quality().into())?;
             }
-            for baked_block in &self.baked_blocks {
+            for baked_block in baked_blocks {
                 w.u8(baked_block.lightmap_quality().into())?;
             }
             for item in &self.items {
@@ -2449,23 +2927,28 @@ impl Map {
     }
 }
 
+/// Ground columns `0..size` which fit into the grid.
+fn ground_columns(size: u32) -> impl Iterator<Item = u8> {
+    (0..=u8::MAX).take_while(move |&i| (i as u32) < size)
+}
+
+/// Height of the ground of the stadium in blocks.
+const GROUND_HEIGHT: u8 = 9;
+
+/// Maximum width and depth of a map, limited by the coords of the baked ground.
+const MAX_GROUND_SIZE: u32 = u8::MAX as u32 + 1;
+
 impl Default for Map {
     fn default() -> Self {
-        let mut baked_blocks = Vec::with_capacity(2304);
-        let grass_model_id = Id::new(String::from("Grass"));
-
-        for x in 0..48 {
-            for z in 0..48 {
-                baked_blocks.push(BlockType::Normal(Block {
-                    model_id: Id::clone(&grass_model_id),
-                    coord: Vec3 { x, y: 9, z },
-                    is_ground: true,
-                    ..Default::default()
-                }))
-            }
-        }
+        let size = Vec3 {
+            x: 48,
+            y: 40,
+            z: 48,
+        };
+        let zone_genealogies = vec![ZoneGenealogy::default(); (size.x * size.z) as usize];
+        let zone_genealogies_size = (size.x, size.z);
 
-        Self {
+        let mut map = Self {
             name: String::default(),
             uid: RefCell::new(None),
             author_name: String::default(),
@@ -2479,22 +2962,41 @@ impl Default for Map {
             thumbnail: None,
             texture_mod: None,
             day_time: DAY_MOOD_TIME,
-            size: Vec3 {
-                x: 48,
-                y: 40,
-                z: 48,
-            },
+            size,
             blocks: vec![],
+            zone_genealogies,
+            zone_genealogies_size,
             music: None,
             items: vec![],
-            baked_blocks,
+            baked_blocks: vec![],
+            baked_blocks_ground: 0,
             intro_media: None,
             podium_media: None,
             in_game_media: None,
             end_race_media: None,
             ambiance_media: None,
             embedded_files: None,
-        }
+            map_kind: 6,
+            is_locked: false,
+            password: 0,
+            decoration_author: Id::new(String::from("Nadeo")),
+            map_origin: [0.0, 0.0],
+            map_target: [0.0, 0.0],
+            map_type: MapType::Race,
+            script_metadata: ScriptMetadata::defaults(&MapType::Race),
+            map_style: String::default(),
+            lightmap_cache_uid: 0xFF58B6734983CC85,
+            lightmap_version: 0,
+            title_id: Id::new(String::from("TMStadium")),
+            editor_mode: 0,
+            author_version: 0,
+            author_score: 0,
+            xml_header: XmlHeader::default(),
+        };
+
+        map.regenerate_baked_blocks();
+
+        map
     }
 }
 
@@ -2516,6 +3018,7 @@ impl Debug for Map {
             .field("day_time", &self.day_time)
             .field("size", &self.size)
             .field("blocks", &DebugVec(&self.blocks))
+            .field("zone_genealogies", &DebugVec(&self.zone_genealogies))
             .field("music", &self.music)
             .field("items", &DebugVec(&self.items))
             .field("baked_blocks", &DebugVec(&self.baked_blocks))
@@ -2524,6 +3027,22 @@ impl Debug for Map {
             .field("intro_media", &DebugOption(&self.in_game_media))
             .field("intro_media", &DebugOption(&self.end_race_media))
             .field("intro_media", &DebugOption(&self.ambiance_media))
+            .field("map_kind", &self.map_kind)
+            .field("is_locked", &self.is_locked)
+            .field("password", &self.password)
+            .field("decoration_author", &self.decoration_author)
+            .field("map_origin", &self.map_origin)
+            .field("map_target", &self.map_target)
+            .field("map_type", &self.map_type)
+            .field("script_metadata", &self.script_metadata)
+            .field("map_style", &self.map_style)
+            .field("lightmap_cache_uid", &self.lightmap_cache_uid)
+            .field("lightmap_version", &self.lightmap_version)
+            .field("title_id", &self.title_id)
+            .field("editor_mode", &self.editor_mode)
+            .field("author_version", &self.author_version)
+            .field("author_score", &self.author_score)
+            .field("xml_header", &self.xml_header)
             .finish()
     }
 }
@@ -0,0 +1,486 @@
+use super::geometry::{euler_to_matrix, BLOCK_SIZE, GRID_Y_OFFSET};
+use super::{BlockType, Color, Map, WaypointProperty, GROUND_HEIGHT};
+use crate::types::{Id, Vec3};
+use crate::zip;
+use std::error;
+use std::fmt::{self, Display};
+use std::io::{self, Write};
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// Maximum number of pixels of a preview image, i.e. 256 MiB of RGBA pixels.
+const MAX_PIXELS: u64 = 1 << 26;
+
+const GROUND_COLOR: [u8; 3] = [52, 92, 52];
+
+/// Colors of blocks and items with the default color, picked by their model ID.
+const MODEL_COLORS: [[u8; 3]; 8] = [
+    [158, 158, 158],
+    [176, 152, 120],
+    [120, 144, 168],
+    [168, 128, 128],
+    [136, 160, 128],
+    [160, 136, 168],
+    [184, 176, 128],
+    [128, 168, 160],
+];
+
+/// Projection of a map preview.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Projection {
+    /// View from above with north at the top.
+    #[default]
+    TopDown,
+    /// Isometric view from the south-east.
+    Isometric,
+}
+
+/// Options of [`Map::render_preview`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PreviewOptions {
+    /// Projection of the preview.
+    pub projection: Projection,
+    /// Width of a single grid cell in pixels.
+    ///
+    /// Set to `8` by default.
+    pub cell_size: u32,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        Self {
+            projection: Projection::default(),
+            cell_size: 8,
+        }
+    }
+}
+
+/// Reason why a map preview could not be rendered.
+///
+/// Returned by [`Map::render_preview`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PreviewError {
+    /// The image of the given width and height would exceed the maximum number of pixels.
+    TooLarge {
+        /// Width of the image in pixels.
+        width: u32,
+        /// Height of the image in pixels.
+        height: u32,
+    },
+}
+
+impl Display for PreviewError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::TooLarge { width, height } => write!(
+                f,
+                "preview of {width}x{height} pixels exceeds the maximum of {MAX_PIXELS} pixels"
+            ),
+        }
+    }
+}
+
+impl error::Error for PreviewError {}
+
+/// An 8-bit RGBA image rendered by [`Map::render_preview`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PreviewImage {
+    /// Width of the image in pixels.
+    pub width: u32,
+    /// Height of the image in pixels.
+    pub height: u32,
+    /// RGBA pixels in rows from top to bottom.
+    pub pixels: Vec<u8>,
+}
+
+impl PreviewImage {
+    fn new(width: u32, height: u32) -> Result<Self, PreviewError> {
+        if width as u64 * height as u64 > MAX_PIXELS {
+            return Err(PreviewError::TooLarge { width, height });
+        }
+
+        Ok(Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        })
+    }
+
+    /// RGBA color of the pixel at column `x` and row `y`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pixel is outside of the image.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        assert!(x < self.width && y < self.height);
+
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+
+        [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ]
+    }
+
+    /// Encode the image as PNG into `w`.
+    pub fn write_png<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&self.width.to_be_bytes());
+        header.extend_from_slice(&self.height.to_be_bytes());
+        header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+        let stride = self.width as usize * 4;
+        let mut scanlines = Vec::with_capacity((stride + 1) * self.height as usize);
+        for row in self.pixels.chunks_exact(stride) {
+            scanlines.push(0);
+            scanlines.extend_from_slice(row);
+        }
+
+        w.write_all(&PNG_SIGNATURE)?;
+        write_png_chunk(&mut w, b"IHDR", &header)?;
+        let data = zip::compress(&scanlines)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+        write_png_chunk(&mut w, b"IDAT", &data)?;
+        write_png_chunk(&mut w, b"IEND", &[])
+    }
+
+    /// Encode the image as PNG.
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut png = vec![];
+        self.write_png(&mut png).unwrap();
+        png
+    }
+
+    /// Fill the convex polygon `points` with `color`, sampling at pixel centers.
+    fn fill_polygon(&mut self, points: &[(f32, f32)], color: [u8; 4]) {
+        let (min_x, max_x, min_y, max_y) = points.iter().fold(
+            (f32::MAX, f32::MIN, f32::MAX, f32::MIN),
+            |(min_x, max_x, min_y, max_y), &(x, y)| {
+                (min_x.min(x), max_x.max(x), min_y.min(y), max_y.max(y))
+            },
+        );
+
+        let x0 = min_x.floor().max(0.0) as u32;
+        let x1 = (max_x.ceil().max(0.0) as u32).min(self.width);
+        let y0 = min_y.floor().max(0.0) as u32;
+        let y1 = (max_y.ceil().max(0.0) as u32).min(self.height);
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let mut sign = 0.0;
+
+                let is_inside = points.iter().zip(points.iter().cycle().skip(1)).all(
+                    |(&(ax, ay), &(bx, by))| {
+                        let cross = (bx - ax) * (py - ay) - (by - ay) * (px - ax);
+
+                        if cross == 0.0 {
+                            true
+                        } else if sign == 0.0 {
+                            sign = cross.signum();
+                            true
+                        } else {
+                            cross.signum() == sign
+                        }
+                    },
+                );
+
+                if is_inside {
+                    let i = (y as usize * self.width as usize + x as usize) * 4;
+                    self.pixels[i..i + 4].copy_from_slice(&color);
+                }
+            }
+        }
+    }
+}
+
+fn write_png_chunk<W: Write>(w: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    let mut chunk = Vec::with_capacity(data.len() + 4);
+    chunk.extend_from_slice(kind);
+    chunk.extend_from_slice(data);
+
+    w.write_all(&(data.len() as u32).to_be_bytes())?;
+    w.write_all(&chunk)?;
+    w.write_all(&zip::crc32(&chunk).to_be_bytes())
+}
+
+/// A box to draw, in grid units.
+struct Shape {
+    min: Vec3<f32>,
+    max: Vec3<f32>,
+    color: [u8; 3],
+}
+
+impl Shape {
+    /// Box of the size `size` centered horizontally at the world position `pos`.
+    fn marker(pos: &Vec3<f32>, size: f32, color: [u8; 3]) -> Self {
+        let (x, y, z) = (
+            pos.x / BLOCK_SIZE.x,
+            pos.y / BLOCK_SIZE.y + GRID_Y_OFFSET as f32,
+            pos.z / BLOCK_SIZE.z,
+        );
+
+        Self {
+            min: Vec3::new(x - size / 2.0, y, z - size / 2.0),
+            max: Vec3::new(x + size / 2.0, y + 1.0, z + size / 2.0),
+            color,
+        }
+    }
+}
+
+fn model_color(model_id: &str) -> [u8; 3] {
+    // FNV-1a, to keep the colors stable across runs and platforms.
+    let hash = model_id.bytes().fold(0x811C9DC5u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x01000193)
+    });
+
+    MODEL_COLORS[hash as usize % MODEL_COLORS.len()]
+}
+
+fn object_color(
+    color: Color,
+    model_id: &str,
+    waypoint_property: Option<&WaypointProperty>,
+) -> [u8; 3] {
+    match waypoint_property {
+        Some(&WaypointProperty::Start { .. }) => return [0, 200, 80],
+        Some(&WaypointProperty::Finish { .. }) => return [224, 32, 32],
+        Some(&WaypointProperty::StartFinish { .. }) => return [240, 160, 0],
+        Some(&WaypointProperty::Checkpoint) | Some(&WaypointProperty::LinkedCheckpoint { .. }) => {
+            return [32, 144, 255]
+        }
+        None => {}
+    }
+
+    match color {
+        Color::Default => model_color(model_id),
+        Color::White => [232, 232, 232],
+        Color::Green => [72, 168, 72],
+        Color::Blue => [64, 112, 208],
+        Color::Red => [208, 64, 64],
+        Color::Black => [48, 48, 48],
+    }
+}
+
+fn shade(color: [u8; 3], factor: f32) -> [u8; 4] {
+    let channel = |value: u8| (value as f32 * factor).round().clamp(0.0, 255.0) as u8;
+
+    [channel(color[0]), channel(color[1]), channel(color[2]), 255]
+}
+
+fn dim(color: [u8; 3]) -> [u8; 3] {
+    let channel = |i: usize| ((color[i] as u16 + GROUND_COLOR[i] as u16) / 2) as u8;
+
+    [channel(0), channel(1), channel(2)]
+}
+
+/// Projection of grid units onto the image.
+struct Projector {
+    projection: Projection,
+    cell_size: f32,
+    size: Vec3<f32>,
+}
+
+impl Projector {
+    fn image_size(&self) -> (u32, u32) {
+        let (width, height) = match self.projection {
+            Projection::TopDown => (self.size.x, self.size.z),
+            Projection::Isometric => (
+                self.size.x + self.size.z,
+                (self.size.x + self.size.z) / 2.0 + self.size.y / 4.0,
+            ),
+        };
+
+        (
+            ((width * self.cell_size).ceil() as u32).max(1),
+            ((height * self.cell_size).ceil() as u32).max(1),
+        )
+    }
+
+    fn project(&self, x: f32, y: f32, z: f32) -> (f32, f32) {
+        // East is towards negative X and north towards positive Z.
+        let (u, v) = (self.size.x - x, self.size.z - z);
+
+        match self.projection {
+            Projection::TopDown => (u * self.cell_size, v * self.cell_size),
+            Projection::Isometric => (
+                (u - v + self.size.z) * self.cell_size,
+                ((u + v) / 2.0 + (self.size.y - y) / 4.0) * self.cell_size,
+            ),
+        }
+    }
+
+    /// Order in which shapes are drawn, from back to front.
+    fn depth(&self, shape: &Shape) -> (f32, f32) {
+        match self.projection {
+            Projection::TopDown => (shape.max.y, 0.0),
+            Projection::Isometric => (
+                -(shape.min.x + shape.max.x + shape.min.z + shape.max.z),
+                shape.min.y,
+            ),
+        }
+    }
+
+    fn draw(&self, image: &mut PreviewImage, shape: &Shape) {
+        let (min, max) = (&shape.min, &shape.max);
+
+        match self.projection {
+            Projection::TopDown => {
+                let range = self.size.y - GROUND_HEIGHT as f32;
+                let height = if range > 0.0 {
+                    (max.y - GROUND_HEIGHT as f32) / range
+                } else {
+                    1.0
+                };
+                let color = shade(shape.color, 0.6 + 0.4 * height.clamp(0.0, 1.0));
+
+                image.fill_polygon(&self.rect(min.x, max.x, max.y, min.z, max.z), color);
+            }
+            Projection::Isometric => {
+                // The faces at minimum X and Z point towards the viewer.
+                let face_x = [
+                    self.project(min.x, min.y, min.z),
+                    self.project(min.x, min.y, max.z),
+                    self.project(min.x, max.y, max.z),
+                    self.project(min.x, max.y, min.z),
+                ];
+                let face_z = [
+                    self.project(min.x, min.y, min.z),
+                    self.project(max.x, min.y, min.z),
+                    self.project(max.x, max.y, min.z),
+                    self.project(min.x, max.y, min.z),
+                ];
+
+                image.fill_polygon(&face_x, shade(shape.color, 0.8));
+                image.fill_polygon(&face_z, shade(shape.color, 0.65));
+                image.fill_polygon(
+                    &self.rect(min.x, max.x, max.y, min.z, max.z),
+                    shade(shape.color, 1.0),
+                );
+            }
+        }
+    }
+
+    /// Horizontal rectangle at the height `y`.
+    fn rect(&self, x0: f32, x1: f32, y: f32, z0: f32, z1: f32) -> [(f32, f32); 4] {
+        [
+            self.project(x0, y, z0),
+            self.project(x1, y, z0),
+            self.project(x1, y, z1),
+            self.project(x0, y, z1),
+        ]
+    }
+}
+
+impl Map {
+    /// Render a preview image of the map, without requiring a GPU.
+    ///
+    /// Blocks are drawn as boxes filling their footprint, colored by their
+    /// [`Color`] or otherwise by their model ID.
+    /// `block_size` returns the size of a block model in grid cells when facing north,
+    /// see [`Block::footprint`](super::Block::footprint).
+    /// Free blocks and items are drawn as small markers.
+    /// Waypoints are highlighted in green (start), red (finish), orange (multilap) and
+    /// blue (checkpoint), and ghost blocks are dimmed. Baked blocks are not drawn and
+    /// the ground is drawn as a plane at the height of the stadium ground,
+    /// unless the decoration has no stadium.
+    ///
+    /// Returns an error if the image would exceed 2^26 pixels,
+    /// e.g. because of a huge map size or [`PreviewOptions::cell_size`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use gbx::map::PreviewOptions;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let map = gbx::Map::reader().read_from_file("MyMap.Map.Gbx")?;
+    ///
+    /// let preview = map.render_preview(&PreviewOptions::default(), |_| gbx::Vec3::new(1, 1, 1))?;
+    /// preview.write_png(std::fs::File::create("MyMap.png")?)?;
+    /// # Ok(()) }
+    /// ```
+    pub fn render_preview(
+        &self,
+        options: &PreviewOptions,
+        block_size: impl Fn(&Id) -> Vec3<u8>,
+    ) -> Result<PreviewImage, PreviewError> {
+        let projector = Projector {
+            projection: options.projection,
+            cell_size: options.cell_size.max(1) as f32,
+            size: Vec3::new(self.size.x as f32, self.size.y as f32, self.size.z as f32),
+        };
+
+        let mut shapes = vec![];
+
+        for block in &self.blocks {
+            let color = object_color(block.color(), block.model_id(), block.waypoint_property());
+
+            shapes.push(match *block {
+                BlockType::Normal(ref block) => {
+                    let min = Vec3::new(
+                        block.coord.x as f32,
+                        block.coord.y as f32,
+                        block.coord.z as f32,
+                    );
+                    let footprint = block.footprint(block_size(&block.model_id));
+                    let max = min.clone()
+                        + Vec3::new(footprint.x as f32, footprint.y as f32, footprint.z as f32);
+
+                    Shape {
+                        min,
+                        max,
+                        color: if block.is_ghost { dim(color) } else { color },
+                    }
+                }
+                BlockType::Free(ref free_block) => {
+                    let matrix = euler_to_matrix(free_block.yaw, free_block.pitch, free_block.roll);
+                    let half =
+                        Vec3::new(BLOCK_SIZE.x / 2.0, BLOCK_SIZE.y / 2.0, BLOCK_SIZE.z / 2.0);
+                    let row = |row: &[f32; 3]| row[0] * half.x + row[1] * half.y + row[2] * half.z;
+                    let center = free_block.pos.clone()
+                        + Vec3::new(row(&matrix[0]), row(&matrix[1]), row(&matrix[2]));
+
+                    Shape::marker(&center, 0.75, color)
+                }
+            });
+        }
+
+        for item in &self.items {
+            let color = object_color(item.color, &item.model_id, item.waypoint_property.as_ref());
+
+            shapes.push(Shape::marker(&item.pos, 0.5, color));
+        }
+
+        shapes.sort_by(|a, b| {
+            projector
+                .depth(a)
+                .partial_cmp(&projector.depth(b))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let (width, height) = projector.image_size();
+        let mut image = PreviewImage::new(width, height)?;
+
+        if let Some(ground_height) = self.ground_height() {
+            let ground = projector.rect(
+                0.0,
+                projector.size.x,
+                ground_height as f32,
+                0.0,
+                projector.size.z,
+            );
+            image.fill_polygon(&ground, shade(GROUND_COLOR, 1.0));
+        }
+
+        for shape in &shapes {
+            projector.draw(&mut image, shape);
+        }
+
+        Ok(image)
+    }
+}
@@ -0,0 +1,552 @@
+use super::MapType;
+use crate::read::{self, Reader};
+use crate::types::Vec3;
+use crate::write::{self, Writer};
+use std::io::{Cursor, Read, Write};
+
+const VERSION: u32 = 6;
+
+/// Type of a script metadata value.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScriptType {
+    /// `Boolean`.
+    Boolean,
+    /// `Integer`.
+    Integer,
+    /// `Real`.
+    Real,
+    /// `Text`.
+    Text,
+    /// `Vec2`.
+    Vec2,
+    /// `Vec3`.
+    Vec3,
+    /// `Int3`.
+    Int3,
+    /// `Int2`.
+    Int2,
+    /// Array of values of the given type, e.g. `Integer[]`.
+    Array(Box<ScriptType>),
+    /// Associative array from keys of the first type to values of the second type,
+    /// e.g. `Integer[Text]`.
+    AssociativeArray(Box<ScriptType>, Box<ScriptType>),
+}
+
+impl ScriptType {
+    fn read<R, I, N>(r: &mut Reader<R, I, N>) -> read::Result<Self>
+    where
+        R: Read,
+    {
+        let tag = r.u8()?;
+
+        Self::read_with_tag(r, tag)
+    }
+
+    fn read_with_tag<R, I, N>(r: &mut Reader<R, I, N>, tag: u8) -> read::Result<Self>
+    where
+        R: Read,
+    {
+        match tag {
+            1 => Ok(Self::Boolean),
+            2 => Ok(Self::Integer),
+            3 => Ok(Self::Real),
+            5 => Ok(Self::Text),
+            7 => match r.u8()? {
+                0 => Ok(Self::Array(Box::new(Self::read(r)?))),
+                key_tag => {
+                    let key = Self::read_with_tag(r, key_tag)?;
+                    let value = Self::read(r)?;
+
+                    Ok(Self::AssociativeArray(Box::new(key), Box::new(value)))
+                }
+            },
+            9 => Ok(Self::Vec2),
+            10 => Ok(Self::Vec3),
+            11 => Ok(Self::Int3),
+            14 => Ok(Self::Int2),
+            _ => Err(read::Error(format!("unsupported script type {tag}"))),
+        }
+    }
+
+    fn write<W, I, N>(&self, w: &mut Writer<W, I, N>) -> write::Result
+    where
+        W: Write,
+    {
+        match *self {
+            Self::Boolean => w.u8(1),
+            Self::Integer => w.u8(2),
+            Self::Real => w.u8(3),
+            Self::Text => w.u8(5),
+            Self::Vec2 => w.u8(9),
+            Self::Vec3 => w.u8(10),
+            Self::Int3 => w.u8(11),
+            Self::Int2 => w.u8(14),
+            Self::Array(ref value) => {
+                w.u8(7)?;
+                w.u8(0)?;
+                value.write(w)
+            }
+            Self::AssociativeArray(ref key, ref value) => {
+                w.u8(7)?;
+                key.write(w)?;
+                value.write(w)
+            }
+        }
+    }
+}
+
+/// A script metadata value.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScriptValue {
+    /// `Boolean` value.
+    Boolean(bool),
+    /// `Integer` value.
+    Integer(i32),
+    /// `Real` value.
+    Real(f32),
+    /// `Text` value.
+    Text(String),
+    /// `Vec2` value.
+    Vec2([f32; 2]),
+    /// `Vec3` value.
+    Vec3(Vec3<f32>),
+    /// `Int3` value.
+    Int3(Vec3<i32>),
+    /// `Int2` value.
+    Int2([i32; 2]),
+    /// Array of values of the given type.
+    Array(ScriptType, Vec<ScriptValue>),
+    /// Associative array from keys of the first type to values of the second type.
+    AssociativeArray(ScriptType, ScriptType, Vec<(ScriptValue, ScriptValue)>),
+}
+
+impl ScriptValue {
+    /// Type of the value.
+    pub fn script_type(&self) -> ScriptType {
+        match *self {
+            Self::Boolean(_) => ScriptType::Boolean,
+            Self::Integer(_) => ScriptType::Integer,
+            Self::Real(_) => ScriptType::Real,
+            Self::Text(_) => ScriptType::Text,
+            Self::Vec2(_) => ScriptType::Vec2,
+            Self::Vec3(_) => ScriptType::Vec3,
+            Self::Int3(_) => ScriptType::Int3,
+            Self::Int2(_) => ScriptType::Int2,
+            Self::Array(ref value_type, _) => ScriptType::Array(Box::new(value_type.clone())),
+            Self::AssociativeArray(ref key_type, ref value_type, _) => {
+                ScriptType::AssociativeArray(
+                    Box::new(key_type.clone()),
+                    Box::new(value_type.clone()),
+                )
+            }
+        }
+    }
+
+    fn read<R, I, N>(r: &mut Reader<R, I, N>, ty: &ScriptType) -> read::Result<Self>
+    where
+        R: Read,
+    {
+        match *ty {
+            ScriptType::Boolean => Ok(Self::Boolean(r.u8()? != 0)),
+            ScriptType::Integer => Ok(Self::Integer(r.u32()? as i32)),
+            ScriptType::Real => Ok(Self::Real(r.f32()?)),
+            ScriptType::Text => Ok(Self::Text(read_short_string(r)?)),
+            ScriptType::Vec2 => Ok(Self::Vec2(r.vec2f32()?)),
+            ScriptType::Vec3 => Ok(Self::Vec3(r.vec3f32()?)),
+            ScriptType::Int3 => {
+                let Vec3 { x, y, z } = r.vec3u32()?;
+
+                Ok(Self::Int3(Vec3::new(x as i32, y as i32, z as i32)))
+            }
+            ScriptType::Int2 => Ok(Self::Int2([r.u32()? as i32, r.u32()? as i32])),
+            ScriptType::Array(ref value_type) => {
+                let len = r.u8()?;
+                let values = r.repeat(len as usize, |r| Self::read(r, value_type))?;
+
+                Ok(Self::Array((**value_type).clone(), values))
+            }
+            ScriptType::AssociativeArray(ref key_type, ref value_type) => {
+                let len = r.u8()?;
+                let entries = r.repeat(len as usize, |r| {
+                    Ok((Self::read(r, key_type)?, Self::read(r, value_type)?))
+                })?;
+
+                Ok(Self::AssociativeArray(
+                    (**key_type).clone(),
+                    (**value_type).clone(),
+                    entries,
+                ))
+            }
+        }
+    }
+
+    fn write<W, I, N>(&self, w: &mut Writer<W, I, N>) -> write::Result
+    where
+        W: Write,
+    {
+        match *self {
+            Self::Boolean(value) => w.u8(value as u8),
+            Self::Integer(value) => w.u32(value as u32),
+            Self::Real(value) => w.f32(value),
+            Self::Text(ref value) => write_short_string(w, value),
+            Self::Vec2([x, y]) => {
+                w.f32(x)?;
+                w.f32(y)
+            }
+            Self::Vec3(Vec3 { x, y, z }) => {
+                w.f32(x)?;
+                w.f32(y)?;
+                w.f32(z)
+            }
+            Self::Int3(Vec3 { x, y, z }) => {
+                w.u32(x as u32)?;
+                w.u32(y as u32)?;
+                w.u32(z as u32)
+            }
+            Self::Int2([x, y]) => {
+                w.u32(x as u32)?;
+                w.u32(y as u32)
+            }
+            Self::Array(ref value_type, ref values) => {
+                w.u8(short_len(values.len())?)?;
+                for value in values {
+                    check_type(value, value_type)?;
+                    value.write(w)?;
+                }
+
+                Ok(())
+            }
+            Self::AssociativeArray(ref key_type, ref value_type, ref entries) => {
+                w.u8(short_len(entries.len())?)?;
+                for (key, value) in entries {
+                    check_type(key, key_type)?;
+                    check_type(value, value_type)?;
+                    key.write(w)?;
+                    value.write(w)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl From<bool> for ScriptValue {
+    fn from(value: bool) -> Self {
+        Self::Boolean(value)
+    }
+}
+
+impl From<i32> for ScriptValue {
+    fn from(value: i32) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl From<f32> for ScriptValue {
+    fn from(value: f32) -> Self {
+        Self::Real(value)
+    }
+}
+
+impl From<String> for ScriptValue {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+
+impl From<&str> for ScriptValue {
+    fn from(value: &str) -> Self {
+        Self::Text(String::from(value))
+    }
+}
+
+impl From<Vec3<f32>> for ScriptValue {
+    fn from(value: Vec3<f32>) -> Self {
+        Self::Vec3(value)
+    }
+}
+
+impl From<Vec3<i32>> for ScriptValue {
+    fn from(value: Vec3<i32>) -> Self {
+        Self::Int3(value)
+    }
+}
+
+fn read_short_string<R, I, N>(r: &mut Reader<R, I, N>) -> read::Result<String>
+where
+    R: Read,
+{
+    let len = r.u8()?;
+    let bytes = r.bytes(len as usize)?;
+
+    String::from_utf8(bytes).map_err(|err| read::Error(format!("{err}")))
+}
+
+fn write_short_string<W, I, N>(w: &mut Writer<W, I, N>, string: &str) -> write::Result
+where
+    W: Write,
+{
+    w.u8(short_len(string.len())?)?;
+    w.bytes(string.as_bytes())
+}
+
+fn short_len(len: usize) -> Result<u8, write::Error> {
+    u8::try_from(len).map_err(|_| {
+        write::Error(format!(
+            "script metadata length {len} exceeds the maximum of 255"
+        ))
+    })
+}
+
+fn check_type(value: &ScriptValue, ty: &ScriptType) -> write::Result {
+    if value.script_type() != *ty {
+        return Err(write::Error(format!(
+            "expected script value of type {ty:?}, got {:?}",
+            value.script_type()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Script metadata of a map.
+///
+/// Mode scripts store per-map settings as named, typed traits.
+///
+/// Metadata of an unsupported version or with unsupported types is not decoded,
+/// it has no traits and is written back unchanged until a trait is set.
+///
+/// # Examples
+///
+/// ```
+/// # use gbx::Map;
+/// let mut map = Map::default();
+/// map.script_metadata.set("MyMode_NumRounds", 5);
+/// map.script_metadata.set("MyMode_Title", "Finals");
+/// ```
+#[derive(Clone, Default, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScriptMetadata {
+    traits: Vec<(String, ScriptValue)>,
+    /// Raw bytes of metadata which could not be decoded.
+    raw: Option<Vec<u8>>,
+}
+
+impl ScriptMetadata {
+    /// Create empty script metadata.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Value of the trait with the given `name`.
+    pub fn get(&self, name: &str) -> Option<&ScriptValue> {
+        self.traits
+            .iter()
+            .find(|(trait_name, _)| trait_name == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Mutable value of the trait with the given `name`.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut ScriptValue> {
+        self.traits
+            .iter_mut()
+            .find(|(trait_name, _)| trait_name == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Set the trait with the given `name` to `value`, returning the previous value.
+    ///
+    /// A new trait is added after all existing traits.
+    /// Setting a trait on metadata which could not be decoded discards the undecoded traits.
+    pub fn set(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<ScriptValue>,
+    ) -> Option<ScriptValue> {
+        let name = name.into();
+        let value = value.into();
+        self.raw = None;
+
+        match self.get_mut(&name) {
+            Some(old_value) => Some(std::mem::replace(old_value, value)),
+            None => {
+                self.traits.push((name, value));
+                None
+            }
+        }
+    }
+
+    /// Remove the trait with the given `name`, returning its value.
+    pub fn remove(&mut self, name: &str) -> Option<ScriptValue> {
+        let index = self
+            .traits
+            .iter()
+            .position(|(trait_name, _)| trait_name == name)?;
+
+        Some(self.traits.remove(index).1)
+    }
+
+    /// Iterate over the names and values of all traits.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ScriptValue)> {
+        self.traits
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Number of traits.
+    pub fn len(&self) -> usize {
+        self.traits.len()
+    }
+
+    /// `true` if there are no traits.
+    pub fn is_empty(&self) -> bool {
+        self.traits.is_empty()
+    }
+
+    /// `true` if the metadata was decoded, see [`ScriptMetadata`].
+    pub fn is_decoded(&self) -> bool {
+        self.raw.is_none()
+    }
+
+    /// Default script metadata of a map of the given `map_type`.
+    pub(crate) fn defaults(map_type: &MapType) -> Self {
+        let mut metadata = Self::new();
+        metadata.set("LibMapType_MapTypeVersion", 1);
+
+        if *map_type == MapType::Race {
+            metadata.set(
+                "Race_AuthorRaceWaypointTimes",
+                ScriptValue::Array(ScriptType::Integer, vec![]),
+            );
+        }
+
+        metadata
+    }
+
+    /// The metadata with the default traits of `map_type`.
+    ///
+    /// Missing default traits of `map_type` are added in front,
+    /// and default traits of other map types which still have their default value are removed.
+    pub(crate) fn with_defaults(&self, map_type: &MapType) -> Self {
+        if self.raw.is_some() {
+            return self.clone();
+        }
+
+        let defaults = Self::defaults(map_type);
+        let other_defaults: Vec<Self> = [
+            MapType::Race,
+            MapType::Royal,
+            MapType::Platform,
+            MapType::Stunt,
+        ]
+        .iter()
+        .filter(|other| *other != map_type)
+        .map(Self::defaults)
+        .collect();
+
+        let is_stale_default = |name: &str, value: &ScriptValue| {
+            defaults.get(name).is_none()
+                && other_defaults
+                    .iter()
+                    .any(|other| other.get(name) == Some(value))
+        };
+
+        let missing_defaults = defaults
+            .traits
+            .iter()
+            .filter(|(name, _)| self.get(name).is_none());
+        let traits = self
+            .traits
+            .iter()
+            .filter(|(name, value)| !is_stale_default(name, value));
+
+        Self {
+            traits: missing_defaults.chain(traits).cloned().collect(),
+            raw: None,
+        }
+    }
+
+    /// Decode the metadata in `bytes`, keeping the raw bytes if they cannot be decoded.
+    pub(crate) fn from_bytes(bytes: Vec<u8>) -> Self {
+        match Self::read_traits(&mut Reader::new(Cursor::new(&bytes))) {
+            Ok(traits) => Self { traits, raw: None },
+            Err(_) => Self {
+                traits: vec![],
+                raw: Some(bytes),
+            },
+        }
+    }
+
+    fn read_traits<R, I, N>(r: &mut Reader<R, I, N>) -> read::Result<Vec<(String, ScriptValue)>>
+    where
+        R: Read,
+    {
+        r.chunk_id(0x11002000)?;
+        let version = r.u32()?;
+        if version != VERSION {
+            return Err(read::Error(format!(
+                "unsupported script metadata version {version}"
+            )));
+        }
+
+        let num_types = r.u8()?;
+        let types = r.repeat(num_types as usize, ScriptType::read)?;
+        let num_traits = r.u8()?;
+        let traits = r.repeat(num_traits as usize, |r| {
+            let name = read_short_string(r)?;
+            let ty = types
+                .get(r.u8()? as usize)
+                .ok_or_else(|| read::Error(String::from("invalid script type index")))?;
+            let value = ScriptValue::read(r, ty)?;
+
+            Ok((name, value))
+        })?;
+
+        r.u32()?; // 0xFACADE01
+
+        Ok(traits)
+    }
+
+    pub(crate) fn write<W, I, N>(&self, w: &mut Writer<W, I, N>) -> write::Result
+    where
+        W: Write,
+    {
+        if let Some(ref raw) = self.raw {
+            return w.bytes(raw);
+        }
+
+        let mut types: Vec<ScriptType> = vec![];
+        let mut type_indices = Vec::with_capacity(self.traits.len());
+
+        for (_, value) in &self.traits {
+            let ty = value.script_type();
+            let index = match types.iter().position(|other| *other == ty) {
+                Some(index) => index,
+                None => {
+                    types.push(ty);
+                    types.len() - 1
+                }
+            };
+            type_indices.push(index);
+        }
+
+        w.u32(0x11002000)?;
+        w.u32(VERSION)?;
+        w.u8(short_len(types.len())?)?;
+        for ty in &types {
+            ty.write(w)?;
+        }
+        w.u8(short_len(self.traits.len())?)?;
+        for ((name, value), index) in self.traits.iter().zip(type_indices) {
+            write_short_string(w, name)?;
+            w.u8(index as u8)?;
+            value.write(w)?;
+        }
+        w.u32(0xFACADE01)?;
+
+        Ok(())
+    }
+}
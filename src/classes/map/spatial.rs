@@ -0,0 +1,547 @@
+use super::geometry::{euler_to_matrix, grid_to_world, world_to_grid, BLOCK_SIZE, GRID_Y_OFFSET};
+use super::{BlockType, Direction, Item, Map};
+use crate::types::{Id, Vec3};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Debug};
+
+/// Reference to a block, baked block or item of a map by its index.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub enum ObjectRef {
+    /// Index into [`Map::blocks`].
+    Block(usize),
+    /// Index into [`Map::baked_blocks`].
+    BakedBlock(usize),
+    /// Index into [`Map::items`].
+    Item(usize),
+}
+
+/// Axis-aligned bounding box in world space.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Aabb {
+    /// Minimum corner.
+    pub min: Vec3<f32>,
+    /// Maximum corner.
+    pub max: Vec3<f32>,
+}
+
+impl Aabb {
+    /// Create a bounding box from two opposite corners.
+    pub fn new(a: Vec3<f32>, b: Vec3<f32>) -> Self {
+        Self {
+            min: Vec3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            max: Vec3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        }
+    }
+
+    /// Bounding box of the grid cell at `coord`.
+    pub fn cell(coord: Vec3<u8>) -> Self {
+        let min = grid_to_world(coord);
+        let max = min.clone() + BLOCK_SIZE;
+
+        Self { min, max }
+    }
+
+    /// `true` if the boxes intersect, including touching faces.
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+            && self.min.z <= other.max.z
+            && other.min.z <= self.max.z
+    }
+
+    /// `true` if the boxes share a volume, excluding touching faces.
+    pub fn overlaps(&self, other: &Self) -> bool {
+        const EPSILON: f32 = 1e-3;
+
+        self.min.x + EPSILON < other.max.x
+            && other.min.x + EPSILON < self.max.x
+            && self.min.y + EPSILON < other.max.y
+            && other.min.y + EPSILON < self.max.y
+            && self.min.z + EPSILON < other.max.z
+            && other.min.z + EPSILON < self.max.z
+    }
+
+    /// Grid cells covered by the box, clamped to the grid.
+    fn cells(&self) -> impl Iterator<Item = Vec3<u8>> {
+        let clamp = |value: f32, size: f32, offset: u8| {
+            ((value / size).floor() + offset as f32).clamp(0.0, u8::MAX as f32) as u8
+        };
+
+        let min = Vec3::new(
+            clamp(self.min.x, BLOCK_SIZE.x, 0),
+            clamp(self.min.y, BLOCK_SIZE.y, GRID_Y_OFFSET),
+            clamp(self.min.z, BLOCK_SIZE.z, 0),
+        );
+        let max = Vec3::new(
+            clamp(self.max.x, BLOCK_SIZE.x, 0),
+            clamp(self.max.y, BLOCK_SIZE.y, GRID_Y_OFFSET),
+            clamp(self.max.z, BLOCK_SIZE.z, 0),
+        );
+
+        (min.x..=max.x).flat_map(move |x| {
+            (min.y..=max.y).flat_map(move |y| (min.z..=max.z).map(move |z| Vec3::new(x, y, z)))
+        })
+    }
+}
+
+/// Bounding box of a block or free block.
+///
+/// `size` is the size of the block model in grid cells when facing north.
+fn block_bounds(block: &BlockType, size: Vec3<u8>) -> (Option<Vec3<u8>>, Aabb) {
+    let size = Vec3::new(size.x.max(1), size.y.max(1), size.z.max(1));
+
+    match *block {
+        BlockType::Normal(ref block) => {
+            let footprint = block.footprint(size);
+            let min = grid_to_world(block.coord);
+            let max = min.clone()
+                + Vec3::new(
+                    footprint.x as f32 * BLOCK_SIZE.x,
+                    footprint.y as f32 * BLOCK_SIZE.y,
+                    footprint.z as f32 * BLOCK_SIZE.z,
+                );
+
+            (Some(block.coord), Aabb { min, max })
+        }
+        BlockType::Free(ref free_block) => {
+            let matrix = euler_to_matrix(free_block.yaw, free_block.pitch, free_block.roll);
+            let rotate = |x: f32, y: f32, z: f32| {
+                let row = |row: &[f32; 3]| row[0] * x + row[1] * y + row[2] * z;
+
+                Vec3::new(row(&matrix[0]), row(&matrix[1]), row(&matrix[2]))
+            };
+
+            let corners = (0..8).map(|corner| {
+                let extent = |bit: u8, size: u8, block_size: f32| {
+                    if corner & bit != 0 {
+                        size as f32 * block_size
+                    } else {
+                        0.0
+                    }
+                };
+                let x = extent(1, size.x, BLOCK_SIZE.x);
+                let y = extent(2, size.y, BLOCK_SIZE.y);
+                let z = extent(4, size.z, BLOCK_SIZE.z);
+
+                free_block.pos.clone() + rotate(x, y, z)
+            });
+
+            let bounds = corners.fold(
+                Aabb::new(free_block.pos.clone(), free_block.pos.clone()),
+                |bounds, corner| Aabb {
+                    min: Vec3::new(
+                        bounds.min.x.min(corner.x),
+                        bounds.min.y.min(corner.y),
+                        bounds.min.z.min(corner.z),
+                    ),
+                    max: Vec3::new(
+                        bounds.max.x.max(corner.x),
+                        bounds.max.y.max(corner.y),
+                        bounds.max.z.max(corner.z),
+                    ),
+                },
+            );
+
+            (world_to_grid(&free_block.pos), bounds)
+        }
+    }
+}
+
+/// Grid cell offset of the neighbor in direction `dir`.
+fn neighbor_offset(dir: Direction) -> (i16, i16) {
+    match dir {
+        Direction::North => (0, 1),
+        Direction::East => (-1, 0),
+        Direction::South => (0, -1),
+        Direction::West => (1, 0),
+    }
+}
+
+/// Index of the blocks, baked blocks and items of a map by their location.
+///
+/// The index does not borrow the map. Use [`IndexedMap`] to edit a map and keep its index in sync,
+/// or call [`SpatialIndex::update`] for every block or item added or moved in the map
+/// and [`SpatialIndex::remove`] for every removed one.
+/// Removing elements from the middle of [`Map::blocks`] or [`Map::items`] shifts the indices
+/// of all following elements, which then have to be updated as well.
+///
+/// Blocks are indexed by their whole footprint, for which the index takes a lookup
+/// of the size of each block model in grid cells when facing north, see [`Block::footprint`].
+/// Items are indexed as points.
+///
+/// # Examples
+///
+/// ```
+/// # use gbx::map::{Aabb, Block, BlockType, ObjectRef, SpatialIndex};
+/// # use gbx::{Map, Vec3};
+/// let mut map = Map::default();
+/// map.blocks.push(BlockType::Normal(Block {
+///     coord: Vec3::new(1, 12, 1),
+///     ..Default::default()
+/// }));
+///
+/// let index = SpatialIndex::new(&map, |_| Vec3::new(2, 1, 1));
+/// assert_eq!(index.at(Vec3::new(1, 12, 1)), [ObjectRef::Block(0)]);
+/// assert_eq!(index.query(&Aabb::cell(Vec3::new(2, 12, 1))), [ObjectRef::Block(0)]);
+/// ```
+///
+/// [`Block::footprint`]: super::Block::footprint
+#[derive(Clone, Default, Debug)]
+pub struct SpatialIndex {
+    /// Objects by the grid coord they are placed at.
+    anchors: HashMap<Vec3<u8>, Vec<ObjectRef>>,
+    /// Objects by all grid cells covered by their bounding box.
+    cells: HashMap<Vec3<u8>, Vec<ObjectRef>>,
+    /// Location of each object.
+    objects: HashMap<ObjectRef, Entry>,
+}
+
+/// Location of an object in a [`SpatialIndex`].
+#[derive(Clone, Debug)]
+struct Entry {
+    /// Grid coord the object is placed at.
+    anchor: Option<Vec3<u8>>,
+    /// Bounding box of the object.
+    bounds: Aabb,
+    /// `true` if the object is a ghost block.
+    is_ghost: bool,
+}
+
+impl SpatialIndex {
+    /// Build an index of all blocks, baked blocks and items of `map`.
+    ///
+    /// `block_size` returns the size of a block model in grid cells when facing north.
+    pub fn new(map: &Map, block_size: impl Fn(&Id) -> Vec3<u8>) -> Self {
+        let mut index = Self::default();
+
+        for i in 0..map.blocks.len() {
+            index.update(map, ObjectRef::Block(i), &block_size);
+        }
+
+        for i in 0..map.baked_blocks.len() {
+            index.update(map, ObjectRef::BakedBlock(i), &block_size);
+        }
+
+        for i in 0..map.items.len() {
+            index.update(map, ObjectRef::Item(i), &block_size);
+        }
+
+        index
+    }
+
+    /// Index `object` at its current location in `map`.
+    ///
+    /// `block_size` returns the size of a block model in grid cells when facing north.
+    /// Removes `object` if it does not exist in `map`.
+    pub fn update(&mut self, map: &Map, object: ObjectRef, block_size: impl Fn(&Id) -> Vec3<u8>) {
+        self.remove(object);
+
+        let block = match object {
+            ObjectRef::Block(i) => map.blocks.get(i),
+            ObjectRef::BakedBlock(i) => map.baked_blocks.get(i),
+            ObjectRef::Item(_) => None,
+        };
+
+        let entry = match (object, block) {
+            (_, Some(block)) => {
+                let (anchor, bounds) = block_bounds(block, block_size(block.model_id()));
+                let is_ghost = matches!(*block, BlockType::Normal(ref block) if block.is_ghost);
+
+                Entry {
+                    anchor,
+                    bounds,
+                    is_ghost,
+                }
+            }
+            (ObjectRef::Item(i), _) => match map.items.get(i) {
+                Some(item) => Entry {
+                    anchor: Some(item.coord),
+                    bounds: Aabb::new(item.pos.clone(), item.pos.clone()),
+                    is_ghost: false,
+                },
+                None => return,
+            },
+            _ => return,
+        };
+
+        if let Some(anchor) = entry.anchor {
+            self.anchors.entry(anchor).or_default().push(object);
+        }
+
+        for cell in entry.bounds.cells() {
+            self.cells.entry(cell).or_default().push(object);
+        }
+
+        self.objects.insert(object, entry);
+    }
+
+    /// Remove `object` from the index.
+    pub fn remove(&mut self, object: ObjectRef) {
+        let entry = match self.objects.remove(&object) {
+            Some(entry) => entry,
+            None => return,
+        };
+
+        let remove_from = |map: &mut HashMap<Vec3<u8>, Vec<ObjectRef>>, cell: Vec3<u8>| {
+            if let Some(objects) = map.get_mut(&cell) {
+                objects.retain(|&other| other != object);
+
+                if objects.is_empty() {
+                    map.remove(&cell);
+                }
+            }
+        };
+
+        if let Some(anchor) = entry.anchor {
+            remove_from(&mut self.anchors, anchor);
+        }
+
+        for cell in entry.bounds.cells() {
+            remove_from(&mut self.cells, cell);
+        }
+    }
+
+    /// All objects placed at the grid coord `coord`.
+    ///
+    /// Free blocks are placed at the grid cell containing their position.
+    pub fn at(&self, coord: Vec3<u8>) -> &[ObjectRef] {
+        self.anchors.get(&coord).map_or(&[], |objects| objects)
+    }
+
+    /// All objects placed at the grid coord next to `coord` in direction `dir`.
+    pub fn neighbors(&self, coord: Vec3<u8>, dir: Direction) -> &[ObjectRef] {
+        let (dx, dz) = neighbor_offset(dir);
+        let x = u8::try_from(coord.x as i16 + dx);
+        let z = u8::try_from(coord.z as i16 + dz);
+
+        match (x, z) {
+            (Ok(x), Ok(z)) => self.at(Vec3::new(x, coord.y, z)),
+            _ => &[],
+        }
+    }
+
+    /// Bounding box of `object`, `None` if it is not indexed.
+    pub fn bounds(&self, object: ObjectRef) -> Option<&Aabb> {
+        self.objects.get(&object).map(|entry| &entry.bounds)
+    }
+
+    /// All objects whose bounding box intersects `aabb`, sorted.
+    pub fn query(&self, aabb: &Aabb) -> Vec<ObjectRef> {
+        let mut objects: Vec<ObjectRef> = aabb
+            .cells()
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(|object| self.objects[object].bounds.intersects(aabb))
+            .collect();
+
+        objects.sort_unstable();
+        objects.dedup();
+        objects
+    }
+
+    /// All pairs of blocks in [`Map::blocks`] whose bounding boxes overlap, sorted.
+    ///
+    /// Blocks which only touch each other do not overlap.
+    /// Ghost blocks, baked blocks and items are ignored.
+    pub fn overlapping_blocks(&self) -> Vec<(ObjectRef, ObjectRef)> {
+        let mut pairs = HashSet::new();
+
+        for objects in self.cells.values() {
+            for (i, &a) in objects.iter().enumerate() {
+                for &b in &objects[i + 1..] {
+                    if let (ObjectRef::Block(_), ObjectRef::Block(_)) = (a, b) {
+                        let (a_entry, b_entry) = (&self.objects[&a], &self.objects[&b]);
+
+                        if !a_entry.is_ghost
+                            && !b_entry.is_ghost
+                            && a_entry.bounds.overlaps(&b_entry.bounds)
+                        {
+                            pairs.insert((a.min(b), a.max(b)));
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut pairs: Vec<_> = pairs.into_iter().collect();
+        pairs.sort_unstable();
+        pairs
+    }
+}
+
+/// A map together with its [`SpatialIndex`].
+///
+/// All edits of the blocks and items go through the methods of this type,
+/// which keep the index in sync with the map.
+///
+/// # Examples
+///
+/// ```
+/// # use gbx::map::{Block, BlockType, ObjectRef};
+/// # use gbx::{Map, Vec3};
+/// let mut map = Map::default();
+/// let mut indexed = map.indexed(|_| Vec3::new(1, 1, 1));
+///
+/// let block = indexed.push_block(BlockType::Normal(Block {
+///     coord: Vec3::new(1, 12, 1),
+///     ..Default::default()
+/// }));
+/// assert_eq!(indexed.index().at(Vec3::new(1, 12, 1)), [block]);
+///
+/// indexed.remove_block(0);
+/// assert!(indexed.index().at(Vec3::new(1, 12, 1)).is_empty());
+/// ```
+pub struct IndexedMap<'a, F> {
+    map: &'a mut Map,
+    index: SpatialIndex,
+    block_size: F,
+}
+
+impl<'a, F> IndexedMap<'a, F>
+where
+    F: Fn(&Id) -> Vec3<u8>,
+{
+    /// Index all blocks, baked blocks and items of `map`.
+    ///
+    /// `block_size` returns the size of a block model in grid cells when facing north.
+    pub fn new(map: &'a mut Map, block_size: F) -> Self {
+        let index = SpatialIndex::new(map, &block_size);
+
+        Self {
+            map,
+            index,
+            block_size,
+        }
+    }
+
+    /// The indexed map.
+    pub fn map(&self) -> &Map {
+        self.map
+    }
+
+    /// The index of the map.
+    pub fn index(&self) -> &SpatialIndex {
+        &self.index
+    }
+
+    /// Take the index, releasing the map.
+    pub fn into_index(self) -> SpatialIndex {
+        self.index
+    }
+
+    /// Add `block` to the end of [`Map::blocks`].
+    pub fn push_block(&mut self, block: BlockType) -> ObjectRef {
+        let object = ObjectRef::Block(self.map.blocks.len());
+        self.map.blocks.push(block);
+        self.index.update(self.map, object, &self.block_size);
+
+        object
+    }
+
+    /// Add `item` to the end of [`Map::items`].
+    pub fn push_item(&mut self, item: Item) -> ObjectRef {
+        let object = ObjectRef::Item(self.map.items.len());
+        self.map.items.push(item);
+        self.index.update(self.map, object, &self.block_size);
+
+        object
+    }
+
+    /// Edit the block at index `i` of [`Map::blocks`] with `f`.
+    ///
+    /// Returns `None` if there is no such block.
+    pub fn edit_block<R>(&mut self, i: usize, f: impl FnOnce(&mut BlockType) -> R) -> Option<R> {
+        let result = f(self.map.blocks.get_mut(i)?);
+        self.index
+            .update(self.map, ObjectRef::Block(i), &self.block_size);
+
+        Some(result)
+    }
+
+    /// Edit the item at index `i` of [`Map::items`] with `f`.
+    ///
+    /// Returns `None` if there is no such item.
+    pub fn edit_item<R>(&mut self, i: usize, f: impl FnOnce(&mut Item) -> R) -> Option<R> {
+        let result = f(self.map.items.get_mut(i)?);
+        self.index
+            .update(self.map, ObjectRef::Item(i), &self.block_size);
+
+        Some(result)
+    }
+
+    /// Remove the block at index `i` of [`Map::blocks`].
+    ///
+    /// All following blocks are shifted down by one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds.
+    pub fn remove_block(&mut self, i: usize) -> BlockType {
+        let len = self.map.blocks.len();
+        let block = self.map.blocks.remove(i);
+        self.reindex(ObjectRef::Block, i, len);
+
+        block
+    }
+
+    /// Remove the item at index `i` of [`Map::items`].
+    ///
+    /// All following items are shifted down by one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds.
+    pub fn remove_item(&mut self, i: usize) -> Item {
+        let len = self.map.items.len();
+        let item = self.map.items.remove(i);
+        self.reindex(ObjectRef::Item, i, len);
+
+        item
+    }
+
+    /// Regenerate the baked blocks of the map, see [`Map::regenerate_baked_blocks`].
+    pub fn regenerate_baked_blocks(&mut self) {
+        let len = self.map.baked_blocks.len();
+        self.map.regenerate_baked_blocks();
+        let len = len.max(self.map.baked_blocks.len());
+        self.reindex(ObjectRef::BakedBlock, 0, len);
+    }
+
+    /// Update the objects from index `start` up to `end` created with `object`.
+    fn reindex(&mut self, object: fn(usize) -> ObjectRef, start: usize, end: usize) {
+        for i in start..end {
+            self.index.update(self.map, object(i), &self.block_size);
+        }
+    }
+}
+
+impl<F> Debug for IndexedMap<'_, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IndexedMap")
+            .field("map", &self.map)
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
+impl Map {
+    /// Build a [`SpatialIndex`] of the blocks, baked blocks and items of the map.
+    ///
+    /// `block_size` returns the size of a block model in grid cells when facing north.
+    pub fn spatial_index(&self, block_size: impl Fn(&Id) -> Vec3<u8>) -> SpatialIndex {
+        SpatialIndex::new(self, block_size)
+    }
+
+    /// Index the map to edit it while keeping its [`SpatialIndex`] in sync.
+    ///
+    /// `block_size` returns the size of a block model in grid cells when facing north.
+    pub fn indexed<F>(&mut self, block_size: F) -> IndexedMap<'_, F>
+    where
+        F: Fn(&Id) -> Vec3<u8>,
+    {
+        IndexedMap::new(self, block_size)
+    }
+}
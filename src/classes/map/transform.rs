@@ -0,0 +1,363 @@
+use super::geometry::{euler_to_matrix, matrix_to_euler, BLOCK_SIZE};
+use super::{BlockType, Direction, Map};
+use crate::types::{Id, Vec3};
+use std::error;
+use std::fmt::{self, Display};
+
+/// A transformation of the blocks and items of a map.
+///
+/// Rotations and mirrors are applied around the center of the map.
+/// Blocks are moved such that their whole footprint is transformed, see [`Block::footprint`](super::Block::footprint).
+///
+/// Mirroring only re-orients blocks and items, it does not produce mirrored models,
+/// so asymmetric models such as turns keep their handedness.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Transform {
+    /// Translate by the given number of grid cells.
+    Translate(Vec3<i32>),
+    /// Rotate in quarter turns, such that a block facing north afterwards faces the given direction.
+    Rotate(Direction),
+    /// Mirror along the X axis, swapping east and west.
+    MirrorX,
+    /// Mirror along the Z axis, swapping north and south.
+    MirrorZ,
+}
+
+/// Blocks and items of a map to transform.
+///
+/// Used by [`Map::transform_selection`].
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Selection {
+    /// Indices into [`Map::blocks`].
+    pub blocks: Vec<usize>,
+    /// Indices into [`Map::items`].
+    pub items: Vec<usize>,
+}
+
+/// Reason why a map could not be transformed.
+///
+/// Returned by [`Map::transform`] and [`Map::transform_selection`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TransformError {
+    /// A block, item or trigger would end up outside of [`Map::size`].
+    OutOfBounds,
+    /// A block, item or trigger would end up between two grid cells,
+    /// e.g. when rotating a map whose width and depth differ by an odd number.
+    OffGrid,
+    /// The selection contains an index which is out of range.
+    InvalidIndex(usize),
+}
+
+impl Display for TransformError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::OutOfBounds => write!(f, "transformed content is outside of the map"),
+            Self::OffGrid => write!(f, "transformed content is not aligned to the grid"),
+            Self::InvalidIndex(index) => write!(f, "selection index {index} is out of range"),
+        }
+    }
+}
+
+impl error::Error for TransformError {}
+
+impl Transform {
+    /// Transform the grid cell `coord` inside of a map of size `size`.
+    fn cell(&self, coord: &Vec3<i64>, size: &Vec3<u32>) -> Result<Vec3<i64>, TransformError> {
+        let (sx, sz) = (size.x as i64, size.z as i64);
+
+        // Coordinates relative to the center of the map in half cells.
+        let (cx, cz) = (2 * coord.x + 1 - sx, 2 * coord.z + 1 - sz);
+
+        let (cx, cz) = match *self {
+            Self::Translate(ref offset) => {
+                return Ok(Vec3::new(
+                    coord.x + offset.x as i64,
+                    coord.y + offset.y as i64,
+                    coord.z + offset.z as i64,
+                ))
+            }
+            Self::Rotate(dir) => (0..u8::from(dir)).fold((cx, cz), |(x, z), _| (-z, x)),
+            Self::MirrorX => (-cx, cz),
+            Self::MirrorZ => (cx, -cz),
+        };
+
+        if (cx + sx - 1) % 2 != 0 || (cz + sz - 1) % 2 != 0 {
+            return Err(TransformError::OffGrid);
+        }
+
+        Ok(Vec3::new((cx + sx - 1) / 2, coord.y, (cz + sz - 1) / 2))
+    }
+
+    /// Transform the direction `dir`.
+    fn dir(&self, dir: Direction) -> Direction {
+        match *self {
+            Self::Translate(_) => dir,
+            Self::Rotate(rotation) => dir + rotation,
+            Self::MirrorX if matches!(dir, Direction::East | Direction::West) => dir.opposite(),
+            Self::MirrorZ if matches!(dir, Direction::North | Direction::South) => dir.opposite(),
+            Self::MirrorX | Self::MirrorZ => dir,
+        }
+    }
+
+    /// Transform the world position `pos` inside of a map of size `size`.
+    fn pos(&self, pos: &Vec3<f32>, size: &Vec3<u32>) -> Vec3<f32> {
+        let (cx, cz) = (
+            size.x as f32 * BLOCK_SIZE.x / 2.0,
+            size.z as f32 * BLOCK_SIZE.z / 2.0,
+        );
+        let (x, z) = (pos.x - cx, pos.z - cz);
+
+        let (x, z) = match *self {
+            Self::Translate(ref offset) => {
+                return Vec3::new(
+                    pos.x + offset.x as f32 * BLOCK_SIZE.x,
+                    pos.y + offset.y as f32 * BLOCK_SIZE.y,
+                    pos.z + offset.z as f32 * BLOCK_SIZE.z,
+                )
+            }
+            Self::Rotate(dir) => (0..u8::from(dir)).fold((x, z), |(x, z), _| (-z, x)),
+            Self::MirrorX => (-x, z),
+            Self::MirrorZ => (x, -z),
+        };
+
+        Vec3::new(x + cx, pos.y, z + cz)
+    }
+
+    /// Transform the Euler angles `(yaw, pitch, roll)`.
+    fn rotation(&self, yaw: f32, pitch: f32, roll: f32) -> (f32, f32, f32) {
+        match *self {
+            Self::Translate(_) => (yaw, pitch, roll),
+            Self::Rotate(dir) => (yaw + dir.yaw(), pitch, roll),
+            Self::MirrorX | Self::MirrorZ => {
+                let axis = self.mirror_axis();
+                let mut matrix = euler_to_matrix(yaw, pitch, roll);
+
+                // Conjugate the rotation with the mirror to keep it a proper rotation.
+                for (i, row) in matrix.iter_mut().enumerate() {
+                    for (j, value) in row.iter_mut().enumerate() {
+                        if (i == axis) != (j == axis) {
+                            *value = -*value;
+                        }
+                    }
+                }
+
+                matrix_to_euler(&matrix)
+            }
+        }
+    }
+
+    /// Transform the local offset `offset`, e.g. an item pivot.
+    fn local(&self, offset: &Vec3<f32>) -> Vec3<f32> {
+        match *self {
+            Self::MirrorX => Vec3::new(-offset.x, offset.y, offset.z),
+            Self::MirrorZ => Vec3::new(offset.x, offset.y, -offset.z),
+            _ => offset.clone(),
+        }
+    }
+
+    fn mirror_axis(&self) -> usize {
+        match *self {
+            Self::MirrorZ => 2,
+            _ => 0,
+        }
+    }
+}
+
+fn to_u8_cell(coord: &Vec3<i64>, size: &Vec3<u32>) -> Result<Vec3<u8>, TransformError> {
+    let to_u8 = |value: i64, size: u32| {
+        if value >= 0 && value < size as i64 && value <= u8::MAX as i64 {
+            Ok(value as u8)
+        } else {
+            Err(TransformError::OutOfBounds)
+        }
+    };
+
+    Ok(Vec3::new(
+        to_u8(coord.x, size.x)?,
+        to_u8(coord.y, size.y)?,
+        to_u8(coord.z, size.z)?,
+    ))
+}
+
+fn to_u32_cell(coord: &Vec3<i64>, size: &Vec3<u32>) -> Result<Vec3<u32>, TransformError> {
+    let to_u32 = |value: i64, size: u32| {
+        if value >= 0 && value < size as i64 {
+            Ok(value as u32)
+        } else {
+            Err(TransformError::OutOfBounds)
+        }
+    };
+
+    Ok(Vec3::new(
+        to_u32(coord.x, size.x)?,
+        to_u32(coord.y, size.y)?,
+        to_u32(coord.z, size.z)?,
+    ))
+}
+
+fn check_pos(pos: &Vec3<f32>, size: &Vec3<u32>) -> Result<(), TransformError> {
+    let is_in_bounds = (0.0..=size.x as f32 * BLOCK_SIZE.x).contains(&pos.x)
+        && (0.0..=size.z as f32 * BLOCK_SIZE.z).contains(&pos.z);
+
+    if is_in_bounds {
+        Ok(())
+    } else {
+        Err(TransformError::OutOfBounds)
+    }
+}
+
+fn widen(coord: &Vec3<u8>) -> Vec3<i64> {
+    Vec3::new(coord.x as i64, coord.y as i64, coord.z as i64)
+}
+
+/// New placement of a single block or item.
+enum Placed {
+    Block(Vec3<u8>, Direction),
+    Free(Vec3<f32>, (f32, f32, f32)),
+    Item(Vec3<u8>, Vec3<f32>, (f32, f32, f32), Vec3<f32>),
+}
+
+impl Map {
+    /// Transform all blocks, items and MediaTracker trigger coords of the map.
+    ///
+    /// `block_size` returns the size of a block model in grid cells when facing north,
+    /// see [`Map::transform_selection`].
+    /// The map is left unchanged if anything would end up outside of [`Map::size`].
+    /// Baked blocks and zone genealogies are not transformed.
+    pub fn transform(
+        &mut self,
+        transform: &Transform,
+        block_size: impl Fn(&Id) -> Vec3<u8>,
+    ) -> Result<(), TransformError> {
+        let selection = Selection {
+            blocks: (0..self.blocks.len()).collect(),
+            items: (0..self.items.len()).collect(),
+        };
+
+        let mut triggers = vec![];
+        for clip_group in self.in_game_media.iter().chain(self.end_race_media.iter()) {
+            for clip in &clip_group.clips {
+                for coord in &clip.coords {
+                    let coord = Vec3::new(coord.x as i64, coord.y as i64, coord.z as i64);
+                    let coord = transform.cell(&coord, &self.size)?;
+                    triggers.push(to_u32_cell(&coord, &self.size)?);
+                }
+            }
+        }
+
+        self.transform_selection(&selection, transform, block_size)?;
+
+        let mut triggers = triggers.into_iter();
+        for clip_group in self
+            .in_game_media
+            .iter_mut()
+            .chain(self.end_race_media.iter_mut())
+        {
+            for clip in &mut clip_group.clips {
+                for coord in &mut clip.coords {
+                    *coord = triggers.next().unwrap();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Transform the selected blocks and items of the map.
+    ///
+    /// `block_size` returns the size of a block model in grid cells when facing north,
+    /// which is needed to move the origin of blocks spanning multiple cells.
+    /// The map is left unchanged if a selected block or item would end up outside of [`Map::size`].
+    pub fn transform_selection(
+        &mut self,
+        selection: &Selection,
+        transform: &Transform,
+        block_size: impl Fn(&Id) -> Vec3<u8>,
+    ) -> Result<(), TransformError> {
+        let size = &self.size;
+        let mut placed = Vec::with_capacity(selection.blocks.len() + selection.items.len());
+
+        for &index in &selection.blocks {
+            let block = self
+                .blocks
+                .get(index)
+                .ok_or(TransformError::InvalidIndex(index))?;
+
+            placed.push(match *block {
+                BlockType::Normal(ref block) => {
+                    let footprint = block.footprint(block_size(&block.model_id));
+                    let first = widen(&block.coord);
+                    let last = first.clone() + widen(&footprint) - Vec3::new(1, 1, 1);
+                    let first = to_u8_cell(&transform.cell(&first, size)?, size)?;
+                    let last = to_u8_cell(&transform.cell(&last, size)?, size)?;
+                    let coord = Vec3::new(
+                        first.x.min(last.x),
+                        first.y.min(last.y),
+                        first.z.min(last.z),
+                    );
+
+                    Placed::Block(coord, transform.dir(block.dir))
+                }
+                BlockType::Free(ref free_block) => {
+                    let pos = transform.pos(&free_block.pos, size);
+                    check_pos(&pos, size)?;
+
+                    Placed::Free(
+                        pos,
+                        transform.rotation(free_block.yaw, free_block.pitch, free_block.roll),
+                    )
+                }
+            });
+        }
+
+        for &index in &selection.items {
+            let item = self
+                .items
+                .get(index)
+                .ok_or(TransformError::InvalidIndex(index))?;
+
+            let coord = transform.cell(&widen(&item.coord), size)?;
+            let pos = transform.pos(&item.pos, size);
+            check_pos(&pos, size)?;
+
+            placed.push(Placed::Item(
+                to_u8_cell(&coord, size)?,
+                pos,
+                transform.rotation(item.yaw, item.pitch, item.roll),
+                transform.local(&item.pivot_pos),
+            ));
+        }
+
+        let indices = selection.blocks.iter().chain(&selection.items);
+
+        for (&index, placed) in indices.zip(placed) {
+            match placed {
+                Placed::Block(coord, dir) => {
+                    if let BlockType::Normal(ref mut block) = self.blocks[index] {
+                        block.coord = coord;
+                        block.dir = dir;
+                    }
+                }
+                Placed::Free(pos, (yaw, pitch, roll)) => {
+                    if let BlockType::Free(ref mut free_block) = self.blocks[index] {
+                        free_block.pos = pos;
+                        free_block.yaw = yaw;
+                        free_block.pitch = pitch;
+                        free_block.roll = roll;
+                    }
+                }
+                Placed::Item(coord, pos, (yaw, pitch, roll), pivot_pos) => {
+                    let item = &mut self.items[index];
+                    item.coord = coord;
+                    item.pos = pos;
+                    item.yaw = yaw;
+                    item.pitch = pitch;
+                    item.roll = roll;
+                    item.pivot_pos = pivot_pos;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
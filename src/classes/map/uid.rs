@@ -0,0 +1,265 @@
+use super::Map;
+use crate::write::{self, WriterBuilder};
+use crate::{zip, Id};
+use std::error;
+use std::fmt::{self, Display};
+use uuid::Uuid;
+
+const ALPHABET: &[u8] = b"_0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Length of an encoded map UID.
+const UID_LEN: usize = 27;
+
+/// First bytes of the UIDs generated by this crate, which distinguish them from UIDs of other schemes.
+const MARKER: [u8; 4] = *b"gbx1";
+
+/// Radix of the digits of the number which is encoded in a map UID.
+///
+/// The game interprets the bytes of a UID as digits of radix 255 instead of 256.
+const RADIX: u16 = u8::MAX as u16;
+
+/// Encode `input` as little endian, URL-safe Base63 number padded to `UID_LEN` characters.
+///
+/// The bytes of `input` are interpreted as digits of radix [`RADIX`].
+fn base63_encode_url_safe(mut input: [u8; 20]) -> String {
+    let mut output = String::with_capacity(UID_LEN);
+
+    while input.iter().any(|&byte| byte != 0) {
+        let mut m = 0;
+
+        for byte in input.iter_mut().rev() {
+            let value = m * RADIX + *byte as u16;
+            *byte = (value / 63) as u8; // quotient
+            m = value % 63; // remainder
+        }
+
+        output.push(ALPHABET[m as usize] as char);
+    }
+
+    while output.len() < UID_LEN {
+        output.push(ALPHABET[0] as char);
+    }
+
+    output
+}
+
+/// Decode the 20 bytes of the map UID `uid`.
+///
+/// The first 16 bytes are a UUID and the last 4 bytes a CRC-32 checksum, see [`Map::uid`].
+/// Since the bytes are encoded as digits of radix 255, a byte of `255` is decoded as a carry
+/// into the next byte, and UIDs whose last byte overflows cannot be decoded.
+pub fn decode_uid(uid: &str) -> Result<[u8; 20], UidError> {
+    decode_digits(uid)
+}
+
+/// Decode the little endian radix 255 digits of the map UID `uid`.
+///
+/// UIDs may be shorter than [`UID_LEN`], since trailing zero digits can be omitted.
+fn decode_digits<const N: usize>(uid: &str) -> Result<[u8; N], UidError> {
+    if uid.is_empty() || uid.len() > UID_LEN {
+        return Err(UidError::InvalidLength(uid.len()));
+    }
+
+    let mut output = [0; N];
+
+    for c in uid.chars().rev() {
+        let digit = ALPHABET
+            .iter()
+            .position(|&a| a as char == c)
+            .ok_or(UidError::InvalidCharacter(c))?;
+
+        let mut carry = digit as u16;
+
+        for byte in output.iter_mut() {
+            let value = *byte as u16 * 63 + carry;
+            *byte = (value % RADIX) as u8;
+            carry = value / RADIX;
+        }
+
+        if carry != 0 {
+            return Err(UidError::Overflow);
+        }
+    }
+
+    Ok(output)
+}
+
+/// Value of the little endian radix 255 `digits`.
+fn digits_value(digits: &[u8]) -> u64 {
+    digits
+        .iter()
+        .rev()
+        .fold(0, |value, &digit| value * RADIX as u64 + digit as u64)
+}
+
+/// 128-bit FNV-1a hash of `data`, starting from the hash of `seed`.
+fn fnv1a_128(seed: u64, data: &[u8]) -> [u8; 16] {
+    const OFFSET_BASIS: u128 = 0x6C62272E07BB014262B821756295C58D;
+    const PRIME: u128 = 0x0000000001000000000000000000013B;
+
+    let hash = seed
+        .to_le_bytes()
+        .iter()
+        .chain(data)
+        .fold(OFFSET_BASIS, |hash, &byte| {
+            (hash ^ byte as u128).wrapping_mul(PRIME)
+        });
+
+    hash.to_le_bytes()
+}
+
+/// How [`Map::writer_with_uid`] sets the UID of a map.
+#[derive(Clone, Debug)]
+pub enum UidMode {
+    /// Generate a new UID from a random UUID.
+    Random,
+    /// Generate a UID from a hash of the map and the given seed instead of a random UUID.
+    ///
+    /// Writing the same map with the same seed always results in the same file.
+    Deterministic(u64),
+    /// Keep the current UID of the map, e.g. the UID read from the file.
+    ///
+    /// A random UID is generated if the map has no UID.
+    Keep,
+    /// Use the given UID as is.
+    Explicit(Id),
+}
+
+/// Result of [`Map::validate_uid`] for a well-formed UID.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum UidStatus {
+    /// The UID was generated by this crate and its checksum matches the map.
+    Valid,
+    /// The UID was not generated by this crate, e.g. by the game, so its checksum cannot be checked.
+    UnknownScheme,
+}
+
+/// Reason why a map UID is invalid.
+///
+/// Returned by [`decode_uid`] and [`Map::validate_uid`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum UidError {
+    /// The map has no UID.
+    Missing,
+    /// The UID is empty or has more than 27 characters.
+    InvalidLength(usize),
+    /// The UID contains a character which is not in the URL-safe Base63 alphabet.
+    InvalidCharacter(char),
+    /// The UID encodes a number larger than 20 bytes.
+    Overflow,
+    /// The checksum of the UID does not match the map.
+    ChecksumMismatch,
+    /// The map cannot be serialized to compute its checksum.
+    Unserializable(String),
+}
+
+impl Display for UidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Missing => write!(f, "map has no uid"),
+            Self::InvalidLength(len) => {
+                write!(f, "uid has {len} characters instead of 1 to {UID_LEN}")
+            }
+            Self::InvalidCharacter(c) => write!(f, "uid contains invalid character {c:?}"),
+            Self::Overflow => write!(f, "uid is larger than 20 bytes"),
+            Self::ChecksumMismatch => write!(f, "uid checksum does not match the map"),
+            Self::Unserializable(ref err) => write!(f, "failed to serialize the map: {err}"),
+        }
+    }
+}
+
+impl error::Error for UidError {}
+
+impl Map {
+    /// Get a writer for this map, setting the UID of the map according to `mode`.
+    pub fn writer_with_uid(&self, mode: UidMode) -> WriterBuilder<'_, Self> {
+        let uid = match mode {
+            UidMode::Random => self.generate_uid(*Uuid::new_v4().as_bytes()).map(Some),
+            UidMode::Deterministic(seed) => self
+                .serialize_without_uid()
+                .and_then(|buf| self.generate_uid(fnv1a_128(seed, &buf)))
+                .map(Some),
+            UidMode::Keep if self.uid.borrow().is_some() => Ok(None),
+            UidMode::Keep => self.generate_uid(*Uuid::new_v4().as_bytes()).map(Some),
+            UidMode::Explicit(uid) => Ok(Some(uid)),
+        };
+
+        match uid {
+            Ok(Some(uid)) => {
+                self.uid.replace(Some(uid));
+            }
+            Ok(None) => {}
+            Err(err) => return self.writer_without_computing_uid().fail(err),
+        }
+
+        self.writer_without_computing_uid()
+    }
+
+    /// Validate the UID of the map.
+    ///
+    /// Checks the length and alphabet of the UID and, if the UID was generated by this crate,
+    /// whether its checksum matches the map.
+    ///
+    /// The checksum covers the map as serialized by this crate, so the UIDs of other schemes,
+    /// e.g. of maps saved by the game, cannot be checked and report [`UidStatus::UnknownScheme`].
+    pub fn validate_uid(&self) -> Result<UidStatus, UidError> {
+        let uid = self.uid().ok_or(UidError::Missing)?;
+        let digits = decode_digits::<21>(&uid)?;
+
+        if digits[..MARKER.len()] != MARKER {
+            return Ok(UidStatus::UnknownScheme);
+        }
+
+        let checksum = self
+            .checksum()
+            .map_err(|err| UidError::Unserializable(err.to_string()))?;
+        let checksum = digits_value(&checksum.to_le_bytes());
+        let quotient = digits_value(&digits[16..]);
+
+        // A UUID byte of 255 carries into the checksum digits,
+        // which is only possible if the remainder is at most 1111...10 in radix 255.
+        let is_valid = quotient == checksum
+            || (quotient == checksum + 1
+                && digits[1..16]
+                    .iter()
+                    .rev()
+                    .find(|&&digit| digit != 1)
+                    .map_or(digits[0] == 0, |&digit| digit == 0));
+
+        if !is_valid {
+            return Err(UidError::ChecksumMismatch);
+        }
+
+        Ok(UidStatus::Valid)
+    }
+
+    fn generate_uid(&self, uuid: [u8; 16]) -> Result<Id, write::Error> {
+        let mut uid = [0; 20];
+        uid[..16].copy_from_slice(&uuid);
+        uid[..MARKER.len()].copy_from_slice(&MARKER);
+        uid[16..].copy_from_slice(&self.checksum()?.to_le_bytes());
+
+        Ok(Id::new(base63_encode_url_safe(uid)))
+    }
+
+    /// ZLIB CRC-32 checksum of the map serialized without UID.
+    fn checksum(&self) -> Result<u32, write::Error> {
+        Ok(zip::crc32(&self.serialize_without_uid()?))
+    }
+
+    /// Serialize the map as GBX without user data, with an uncompressed body and without UID.
+    fn serialize_without_uid(&self) -> Result<Vec<u8>, write::Error> {
+        let uid = self.uid.take();
+        let mut buf = vec![];
+
+        let result = self
+            .writer_without_computing_uid()
+            .user_data(false)
+            .compress_body(false)
+            .write_to(&mut buf);
+
+        self.uid.replace(uid);
+
+        result.map(|()| buf)
+    }
+}
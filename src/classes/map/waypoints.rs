@@ -0,0 +1,156 @@
+use super::{BlockType, Map, ObjectRef, Placement, WaypointProperty};
+use crate::write::{self, WriterBuilder};
+use std::collections::BTreeMap;
+
+/// A waypoint of a block, free block or item placed inside of a map.
+///
+/// Returned by [`Map::waypoints`].
+#[derive(Clone, PartialEq, Debug)]
+pub struct Waypoint<'a> {
+    /// The block or item of the waypoint.
+    pub object: ObjectRef,
+    /// Placement of the block or item.
+    pub placement: Placement,
+    /// Waypoint property of the block or item.
+    pub property: &'a WaypointProperty,
+}
+
+/// Number of waypoints of each kind placed inside of a map.
+///
+/// Returned by [`Map::waypoint_summary`].
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct WaypointSummary {
+    /// Number of checkpoints, counting each linked checkpoint group once.
+    pub num_checkpoints: u32,
+    /// Number of linked checkpoints in each linked checkpoint group.
+    pub linked_groups: BTreeMap<u32, u32>,
+    /// Number of start waypoints.
+    pub num_starts: u32,
+    /// Number of finish waypoints.
+    pub num_finishes: u32,
+    /// Number of multilap waypoints.
+    pub num_start_finishes: u32,
+}
+
+impl WaypointSummary {
+    /// `true` if the map has a start, multilap waypoints count as start.
+    pub fn has_start(&self) -> bool {
+        self.num_starts > 0 || self.num_start_finishes > 0
+    }
+
+    /// `true` if the map has a finish, multilap waypoints count as finish.
+    pub fn has_finish(&self) -> bool {
+        self.num_finishes > 0 || self.num_start_finishes > 0
+    }
+}
+
+impl Map {
+    /// Iterate over the waypoints of all blocks, free blocks and items of the map.
+    pub fn waypoints(&self) -> impl Iterator<Item = Waypoint<'_>> {
+        let blocks = self.blocks.iter().enumerate().filter_map(|(i, block)| {
+            let (placement, property) = match *block {
+                BlockType::Normal(ref block) => (
+                    Placement::Grid {
+                        coord: block.coord,
+                        dir: block.dir,
+                    },
+                    block.waypoint_property.as_ref()?,
+                ),
+                BlockType::Free(ref free_block) => (
+                    Placement::Free {
+                        pos: free_block.pos.clone(),
+                        yaw: free_block.yaw,
+                        pitch: free_block.pitch,
+                        roll: free_block.roll,
+                    },
+                    free_block.waypoint_property.as_ref()?,
+                ),
+            };
+
+            Some(Waypoint {
+                object: ObjectRef::Block(i),
+                placement,
+                property,
+            })
+        });
+
+        let items = self.items.iter().enumerate().filter_map(|(i, item)| {
+            Some(Waypoint {
+                object: ObjectRef::Item(i),
+                placement: Placement::Free {
+                    pos: item.pos.clone(),
+                    yaw: item.yaw,
+                    pitch: item.pitch,
+                    roll: item.roll,
+                },
+                property: item.waypoint_property.as_ref()?,
+            })
+        });
+
+        blocks.chain(items)
+    }
+
+    /// Count the waypoints of the map.
+    pub fn waypoint_summary(&self) -> WaypointSummary {
+        let mut summary = WaypointSummary::default();
+        let mut num_checkpoints = 0;
+
+        for waypoint in self.waypoints() {
+            match *waypoint.property {
+                WaypointProperty::Checkpoint => num_checkpoints += 1,
+                WaypointProperty::LinkedCheckpoint { group } => {
+                    *summary.linked_groups.entry(group).or_default() += 1
+                }
+                WaypointProperty::Start { .. } => summary.num_starts += 1,
+                WaypointProperty::Finish { .. } => summary.num_finishes += 1,
+                WaypointProperty::StartFinish { .. } => summary.num_start_finishes += 1,
+            }
+        }
+
+        summary.num_checkpoints = num_checkpoints + summary.linked_groups.len() as u32;
+
+        summary
+    }
+
+    /// Check whether [`Map::num_laps`] matches the placed multilap waypoints.
+    pub(crate) fn check_laps(&self) -> Result<(), write::Error> {
+        let has_start_finish = self.waypoint_summary().num_start_finishes > 0;
+
+        match self.num_laps {
+            Some(0) => Err(write::Error(String::from("map has zero laps"))),
+            Some(_) if !has_start_finish => {
+                Err(write::Error(String::from("map has laps but no multilap")))
+            }
+            None if has_start_finish => {
+                Err(write::Error(String::from("map has a multilap but no laps")))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl WriterBuilder<'_, Map> {
+    /// Set whether or not to recompute the number of checkpoints from the placed waypoints.
+    ///
+    /// If set, [`Map::num_cps`] is ignored and the [`WaypointSummary::num_checkpoints`]
+    /// are written to the user data instead,
+    /// and writing fails if [`Map::num_laps`] does not match the placed multilap waypoints.
+    ///
+    /// Set to `false` by default.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # || -> gbx::write::Result {
+    /// let map = gbx::Map::new();
+    ///
+    /// map.writer()
+    ///     .recompute_num_cps(true)
+    ///     .write_to_file("MyMap.Map.Gbx")?;
+    /// # Ok(()) };
+    /// ```
+    pub fn recompute_num_cps(mut self, recompute_num_cps: bool) -> Self {
+        self.options_mut().recompute_num_cps = recompute_num_cps;
+        self
+    }
+}
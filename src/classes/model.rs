@@ -7,6 +7,7 @@ use std::ops::{Deref, DerefMut};
 
 /// Material of a model.
 #[derive(Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Material;
 
 impl Material {
@@ -92,6 +93,7 @@ impl Material {
 
 /// Model.
 #[derive(Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Model {
     /// Materials used in the model.
     pub materials: Vec<Material>,
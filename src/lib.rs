@@ -9,6 +9,9 @@ pub mod read;
 /// Types for writing GBX nodes.
 pub mod write;
 
+/// [Text styling](https://wiki.trackmania.io/en/content-creation/text-styling) of names.
+pub mod text;
+
 mod classes {
     /// Types for `Block`.
     pub mod block;
@@ -24,6 +27,7 @@ mod classes {
 
 mod fmt;
 mod types;
+mod zip;
 
 pub use block::Block;
 pub use classes::{block, ghost, item, map, model};
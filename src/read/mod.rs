@@ -37,6 +37,140 @@ type HeaderChunks<T> = Vec<(
 
 type BodyChunks<T> = Vec<(u32, ReadBodyChunk<T, Cursor<Vec<u8>>, IdState, NodeState>)>;
 
+/// Header of a GBX file of any class.
+///
+/// # Examples
+///
+/// ```no_run
+/// # || -> gbx::read::Result<()> {
+/// let header = gbx::read::Header::read_from_file("MyMap.Map.Gbx")?;
+///
+/// println!("{:08X}", header.class_id);
+/// # Ok(()) };
+/// ```
+#[derive(Clone, Debug)]
+pub struct Header {
+    /// Version of the file format.
+    pub version: u16,
+    /// `true` if the body is compressed.
+    pub is_body_compressed: bool,
+    /// Class ID of the node in the file.
+    pub class_id: u32,
+    /// All chunks of the user data.
+    pub chunks: Vec<HeaderChunk>,
+    /// Number of nodes in the file.
+    pub num_nodes: u32,
+    /// Number of references to external nodes.
+    pub num_node_refs: u32,
+}
+
+/// A chunk of the user data of a GBX file.
+#[derive(Clone, Debug)]
+pub struct HeaderChunk {
+    /// ID of the chunk.
+    pub id: u32,
+    /// Size of the chunk in bytes.
+    pub size: u32,
+    /// `true` if the chunk is heavy.
+    pub is_heavy: bool,
+}
+
+impl Header {
+    /// Read the header of a GBX file of any class from the given `reader`, without reading the body.
+    pub fn read_from<R>(reader: R) -> Result<Self>
+    where
+        R: Read,
+    {
+        Ok(read_header(&mut Reader::new(reader))?.0)
+    }
+
+    /// Read the header of a GBX file of any class from a file at the given path.
+    pub fn read_from_file<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::open(path).map_err(|err| Error(format!("{err}")))?;
+        let reader = BufReader::new(file);
+        Self::read_from(reader)
+    }
+}
+
+/// Read the header of a GBX file and the content of its user data chunks.
+fn read_header<R>(r: &mut Reader<R>) -> Result<(Header, Vec<u8>)>
+where
+    R: Read,
+{
+    if r.bytes(3)? != b"GBX" {
+        return Err(Error(String::from("bad magic")));
+    }
+
+    let version = r.u16()?;
+
+    if version != 6 {
+        return Err(Error(String::from("version not supported")));
+    }
+
+    match r.u8()? {
+        b'B' => {}
+        b'T' => return Err(Error(String::from("text format not supported"))),
+        _ => return Err(Error(String::from("bad format"))),
+    }
+
+    match r.u8()? {
+        b'U' => {}
+        b'C' => return Err(Error(String::from("compressed ref table not supported"))),
+        _ => return Err(Error(String::from("bad compression"))),
+    }
+
+    let is_body_compressed = match r.u8()? {
+        b'C' => true,
+        b'U' => false,
+        _ => return Err(Error(String::from("bad compression"))),
+    };
+
+    if r.u8()? != b'R' {
+        return Err(Error(String::from("bad unknown byte")));
+    }
+
+    let class_id = r.u32()?;
+    let user_data_size = r.u32()?;
+
+    let (chunks, chunk_data) = if user_data_size > 0 {
+        let mut user_data = r.bytes(user_data_size as usize)?;
+
+        let chunks = Reader::new(user_data.as_slice()).list(|r| {
+            let chunk_id = r.u32()?;
+            let size = r.u32()?;
+
+            Ok(HeaderChunk {
+                id: chunk_id,
+                size: size & 0x7FFFFFFF,
+                is_heavy: size & 0x80000000 != 0,
+            })
+        })?;
+
+        let chunk_data = user_data.split_off(4 + 8 * chunks.len());
+
+        (chunks, chunk_data)
+    } else {
+        (vec![], vec![])
+    };
+
+    let num_nodes = r.u32()?;
+    let num_node_refs = r.u32()?;
+
+    let header = Header {
+        version,
+        is_body_compressed,
+        class_id,
+        chunks,
+        num_nodes,
+        num_node_refs,
+    };
+
+    Ok((header, chunk_data))
+}
+
 /// Reader builder.
 pub struct ReaderBuilder<T> {
     read_user_data: bool,
@@ -111,73 +245,31 @@ impl<T> ReaderBuilder<T> {
 
         let mut r = Reader::new(reader);
 
-        if r.bytes(3)? != b"GBX" {
-            return Err(Error(String::from("bad magic")));
-        }
-
-        if r.u16()? != 6 {
-            return Err(Error(String::from("version not supported")));
-        }
-
-        match r.u8()? {
-            b'B' => {}
-            b'T' => return Err(Error(String::from("text format not supported"))),
-            _ => return Err(Error(String::from("bad format"))),
-        }
-
-        match r.u8()? {
-            b'U' => {}
-            b'C' => return Err(Error(String::from("compressed ref table not supported"))),
-            _ => return Err(Error(String::from("bad compression"))),
-        }
-
-        let body_compressed = match r.u8()? {
-            b'C' => true,
-            b'U' => false,
-            _ => return Err(Error(String::from("bad compression"))),
-        };
-
-        if r.u8()? != b'R' {
-            return Err(Error(String::from("bad unknown byte")));
-        }
+        let (header, chunk_data) = read_header(&mut r)?;
 
-        if r.u32()? != self.class_id {
+        if header.class_id != self.class_id {
             return Err(Error(String::from("unexpected node class")));
         }
 
-        let user_data_size = r.u32()?;
-
-        if user_data_size > 0 {
-            let user_data = r.bytes(user_data_size as usize)?;
-
-            if self.read_user_data {
-                let mut r = Reader::new(user_data.as_slice());
-
-                let user_data_chunks = r.list(|r| {
-                    let chunk_id = r.u32()?;
-                    let size = r.u32()? & 0x7FFFFFFF;
-
-                    Ok((chunk_id, size))
-                })?;
-
-                let mut header_chunks = self.header_chunks.into_iter();
-                let mut id_state = IdState::new();
+        if self.read_user_data {
+            let mut r = Reader::new(chunk_data.as_slice());
+            let mut header_chunks = self.header_chunks.into_iter();
+            let mut id_state = IdState::new();
 
-                for (chunk_id, size) in user_data_chunks {
-                    let (_, read_fn) = header_chunks.find(|(id, _)| *id == chunk_id).unwrap();
+            for chunk in header.chunks {
+                let (_, read_fn) = header_chunks.find(|(id, _)| *id == chunk.id).unwrap();
 
-                    let bytes = r.bytes(size as usize)?;
-                    let mut r = Reader::with_id_state(bytes.as_slice(), &mut id_state);
+                let bytes = r.bytes(chunk.size as usize)?;
+                let mut r = Reader::with_id_state(bytes.as_slice(), &mut id_state);
 
-                    read_fn(&mut node, &mut r)?;
-                }
+                read_fn(&mut node, &mut r)?;
             }
         }
 
-        let num_nodes = r.u32()?;
-        let num_node_refs = r.u32()?;
+        let num_nodes = header.num_nodes;
+        let body_compressed = header.is_body_compressed;
 
-        if num_node_refs > 0 {
+        if header.num_node_refs > 0 {
             todo!()
         }
 
@@ -188,7 +280,8 @@ impl<T> ReaderBuilder<T> {
                 let compressed_body = r.bytes(compressed_body_size as usize)?;
                 let mut body = vec![0; body_size as usize];
 
-                lzo1x_1::decompress_to_slice(&compressed_body, &mut body).unwrap();
+                lzo1x_1::decompress_to_slice(&compressed_body, &mut body)
+                    .map_err(|_| Error(String::from("invalid compressed body")))?;
 
                 let mut r = Reader::with_id_and_node_state(
                     Cursor::new(body),
@@ -198,7 +291,18 @@ impl<T> ReaderBuilder<T> {
 
                 read_body(&mut node, &mut r, self.body_chunks)?;
             } else {
-                todo!()
+                let mut body = vec![];
+                r.take(u64::MAX)
+                    .read_to_end(&mut body)
+                    .map_err(|err| Error(format!("{err}")))?;
+
+                let mut r = Reader::with_id_and_node_state(
+                    Cursor::new(body),
+                    IdState::new(),
+                    NodeState::new(num_nodes as usize),
+                );
+
+                read_body(&mut node, &mut r, self.body_chunks)?;
             }
         }
 
@@ -0,0 +1,547 @@
+use std::fmt::{self, Display, Write};
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Color of styled text with 4 bits per channel, e.g. `$f80`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextColor {
+    /// Red. [0, 15]
+    pub r: u8,
+    /// Green. [0, 15]
+    pub g: u8,
+    /// Blue. [0, 15]
+    pub b: u8,
+}
+
+impl TextColor {
+    /// Create a new color, the channels are clamped to [0, 15].
+    pub fn new(r: u8, g: u8, b: u8) -> Self {
+        Self {
+            r: r.min(15),
+            g: g.min(15),
+            b: b.min(15),
+        }
+    }
+
+    /// Color as 8 bits per channel.
+    pub fn to_rgb8(self) -> [u8; 3] {
+        [self.r * 17, self.g * 17, self.b * 17]
+    }
+}
+
+/// Width of styled text.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Width {
+    /// Normal width, `$m`.
+    #[default]
+    Normal,
+    /// Wide text, `$w`.
+    Wide,
+    /// Narrow text, `$n`.
+    Narrow,
+}
+
+/// Style of a span of styled text.
+///
+/// # Examples
+///
+/// ```
+/// use gbx::text::{Style, StyledText, TextColor};
+///
+/// let text = StyledText::new()
+///     .push("Fast", Style::new().bold(true).color(TextColor::new(15, 8, 0)))
+///     .push(" map", Style::new());
+/// assert_eq!(text.to_string(), "$o$f80Fast$z map");
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Style {
+    /// Text color, `None` for the default color.
+    pub color: Option<TextColor>,
+    /// Bold text, `$o`.
+    pub bold: bool,
+    /// Italic text, `$i`.
+    pub italic: bool,
+    /// Text width.
+    pub width: Width,
+    /// Text with shadow, `$s`.
+    pub shadow: bool,
+    /// Uppercase text, `$t`.
+    pub uppercase: bool,
+    /// URL of a link, `$l`, or manialink, `$h` or `$p`.
+    pub link: Option<String>,
+    /// Whether or not the link is a manialink.
+    pub manialink: bool,
+}
+
+impl Style {
+    /// Create a new default style.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the text color.
+    pub fn color(mut self, color: TextColor) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Set whether or not the text is bold.
+    pub fn bold(mut self, bold: bool) -> Self {
+        self.bold = bold;
+        self
+    }
+
+    /// Set whether or not the text is italic.
+    pub fn italic(mut self, italic: bool) -> Self {
+        self.italic = italic;
+        self
+    }
+
+    /// Set the text width.
+    pub fn width(mut self, width: Width) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Set whether or not the text has a shadow.
+    pub fn shadow(mut self, shadow: bool) -> Self {
+        self.shadow = shadow;
+        self
+    }
+
+    /// Set whether or not the text is uppercase.
+    pub fn uppercase(mut self, uppercase: bool) -> Self {
+        self.uppercase = uppercase;
+        self
+    }
+
+    /// Set the URL of a link.
+    pub fn link(mut self, url: impl Into<String>) -> Self {
+        self.link = Some(url.into());
+        self.manialink = false;
+        self
+    }
+
+    /// Set the name of a manialink.
+    pub fn manialink(mut self, name: impl Into<String>) -> Self {
+        self.link = Some(name.into());
+        self.manialink = true;
+        self
+    }
+}
+
+/// A span of text with a single style.
+#[derive(Clone, PartialEq, Eq, Hash, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    /// The unformatted text.
+    pub text: String,
+    /// Style of the text.
+    pub style: Style,
+}
+
+/// Text with styling, split into spans.
+///
+/// Formatting with [`Display`] writes the styled string with formatting codes.
+///
+/// # Examples
+///
+/// ```
+/// use gbx::text::{StyledText, TextColor};
+///
+/// let text = StyledText::parse("$o$f00Red$z and $iitalic$$");
+/// assert_eq!(text.plain(), "Red and italic$");
+/// assert_eq!(text.spans[0].style.color, Some(TextColor::new(15, 0, 0)));
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StyledText {
+    /// All spans of the text, adjacent spans have different styles.
+    pub spans: Vec<Span>,
+}
+
+/// Remove all formatting codes from the styled string `text`.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(gbx::text::strip("$l[https://trackmania.io]$fffTM$l.io"), "TM.io");
+/// ```
+pub fn strip(text: &str) -> String {
+    StyledText::parse(text).plain()
+}
+
+fn hex_digit(c: char) -> Option<u8> {
+    c.to_digit(16).map(|digit| digit as u8)
+}
+
+/// Parse the optional `[url]` of a link code.
+fn parse_link_url(chars: &mut Peekable<Chars<'_>>) -> Option<String> {
+    if chars.peek() != Some(&'[') {
+        return None;
+    }
+
+    chars.next();
+    let mut url = String::new();
+
+    for c in chars.by_ref() {
+        if c == ']' {
+            break;
+        }
+
+        url.push(c);
+    }
+
+    Some(url)
+}
+
+impl StyledText {
+    /// Create a new empty text.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse the styled string `text`.
+    ///
+    /// Unknown formatting codes are ignored.
+    /// Links without URL, e.g. `$lwww.example.com$l`, link to their text.
+    pub fn parse(text: &str) -> Self {
+        let mut styled_text = Self::new();
+        let mut style = Style::new();
+        let mut stack = vec![];
+        let mut link_start = None;
+        let mut chars = text.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c != '$' {
+                styled_text.push_char(c, &style);
+                continue;
+            }
+
+            let code = match chars.next() {
+                Some(code) => code,
+                None => break,
+            };
+
+            match code.to_ascii_lowercase() {
+                '$' => styled_text.push_char('$', &style),
+                'o' => style.bold = !style.bold,
+                'i' => style.italic = !style.italic,
+                'w' => style.width = Width::Wide,
+                'n' => style.width = Width::Narrow,
+                'm' => style.width = Width::Normal,
+                's' => style.shadow = !style.shadow,
+                't' => style.uppercase = !style.uppercase,
+                'g' => style.color = None,
+                'z' => {
+                    style = Style {
+                        link: style.link.take(),
+                        manialink: style.manialink,
+                        ..Style::new()
+                    }
+                }
+                '<' => stack.push(style.clone()),
+                '>' => {
+                    if let Some(pushed) = stack.pop() {
+                        style = Style {
+                            link: style.link.take(),
+                            manialink: style.manialink,
+                            ..pushed
+                        };
+                    }
+                }
+                'l' | 'h' | 'p' => {
+                    if style.link.is_some() {
+                        styled_text.close_link(link_start.take());
+                        style.link = None;
+                        style.manialink = false;
+                    } else {
+                        let url = parse_link_url(&mut chars);
+
+                        // Links without URL are resolved when they are closed.
+                        if url.is_none() {
+                            link_start = Some(styled_text.spans.len());
+                        }
+
+                        style.link = Some(url.unwrap_or_default());
+                        style.manialink = !code.eq_ignore_ascii_case(&'l');
+                    }
+                }
+                _ => {
+                    if let Some(r) = hex_digit(code) {
+                        let g = chars.peek().copied().and_then(hex_digit);
+                        if g.is_some() {
+                            chars.next();
+                        }
+
+                        let b = chars.peek().copied().and_then(hex_digit);
+                        if g.is_some() && b.is_some() {
+                            chars.next();
+                        }
+
+                        style.color = Some(TextColor::new(r, g.unwrap_or(0), b.unwrap_or(0)));
+                    }
+                }
+            }
+        }
+
+        styled_text.close_link(link_start);
+        styled_text
+    }
+
+    /// Append `text` with the given `style`.
+    pub fn push(mut self, text: impl Into<String>, style: Style) -> Self {
+        let text = text.into();
+
+        if text.is_empty() {
+            return self;
+        }
+
+        match self.spans.last_mut() {
+            Some(span) if span.style == style => span.text.push_str(&text),
+            _ => self.spans.push(Span { text, style }),
+        }
+
+        self
+    }
+
+    fn push_char(&mut self, c: char, style: &Style) {
+        match self.spans.last_mut() {
+            Some(span) if span.style == *style => span.text.push(c),
+            _ => self.spans.push(Span {
+                text: String::from(c),
+                style: style.clone(),
+            }),
+        }
+    }
+
+    /// Set the URL of all link spans without URL since `start` to their text.
+    fn close_link(&mut self, start: Option<usize>) {
+        let start = match start {
+            Some(start) => start,
+            None => return,
+        };
+
+        let url: String = self.spans[start..]
+            .iter()
+            .map(|span| span.text.as_str())
+            .collect();
+
+        for span in &mut self.spans[start..] {
+            if let Some(ref mut link) = span.style.link {
+                if link.is_empty() {
+                    *link = url.clone();
+                }
+            }
+        }
+    }
+
+    /// The text without any formatting.
+    pub fn plain(&self) -> String {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    /// Render the text as HTML.
+    ///
+    /// Every span is written as a `<span>` element with inline CSS, wrapped in an `<a>`
+    /// element if it is a link. Links which are not HTTP(S) URLs are prefixed with `http://`.
+    /// Manialinks only exist in the game and are rendered as plain text.
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+
+        for span in &self.spans {
+            let style = &span.style;
+            let mut css = String::new();
+
+            if let Some(color) = style.color {
+                let [r, g, b] = color.to_rgb8();
+                write!(css, "color:#{r:02x}{g:02x}{b:02x};").unwrap();
+            }
+
+            if style.bold {
+                css.push_str("font-weight:bold;");
+            }
+
+            if style.italic {
+                css.push_str("font-style:italic;");
+            }
+
+            match style.width {
+                Width::Normal => {}
+                Width::Wide => css.push_str("letter-spacing:0.1em;"),
+                Width::Narrow => css.push_str("letter-spacing:-0.1em;"),
+            }
+
+            if style.shadow {
+                css.push_str("text-shadow:1px 1px 1px rgba(0,0,0,0.5);");
+            }
+
+            if style.uppercase {
+                css.push_str("text-transform:uppercase;");
+            }
+
+            let link = style.link.as_ref().filter(|_| !style.manialink);
+
+            if let Some(link) = link {
+                let is_http = link.starts_with("http://") || link.starts_with("https://");
+                let prefix = if is_http { "" } else { "http://" };
+                write!(html, "<a href=\"{prefix}{}\">", escape_html(link)).unwrap();
+            }
+
+            if css.is_empty() {
+                write!(html, "<span>{}</span>", escape_html(&span.text)).unwrap();
+            } else {
+                write!(
+                    html,
+                    "<span style=\"{css}\">{}</span>",
+                    escape_html(&span.text)
+                )
+                .unwrap();
+            }
+
+            if link.is_some() {
+                html.push_str("</a>");
+            }
+        }
+
+        html
+    }
+
+    /// Render the text for terminals using ANSI escape codes.
+    ///
+    /// Colors are written as 24-bit colors, uppercase text is converted to uppercase
+    /// and links are written as OSC 8 hyperlinks. Width and shadow are ignored.
+    /// Manialinks only exist in the game and are written as plain text.
+    ///
+    /// Control characters other than newlines and tabs are removed from the text and links,
+    /// so the text cannot inject escape sequences of its own.
+    pub fn to_ansi(&self) -> String {
+        let mut ansi = String::new();
+
+        for span in &self.spans {
+            let style = &span.style;
+            let link = style.link.as_ref().filter(|_| !style.manialink);
+
+            if let Some(link) = link {
+                let link: String = link.chars().filter(|c| !c.is_control()).collect();
+                write!(ansi, "\x1b]8;;{link}\x1b\\").unwrap();
+            }
+
+            if let Some(color) = style.color {
+                let [r, g, b] = color.to_rgb8();
+                write!(ansi, "\x1b[38;2;{r};{g};{b}m").unwrap();
+            }
+
+            if style.bold {
+                ansi.push_str("\x1b[1m");
+            }
+
+            if style.italic {
+                ansi.push_str("\x1b[3m");
+            }
+
+            let text = span
+                .text
+                .chars()
+                .filter(|&c| !c.is_control() || c == '\n' || c == '\t');
+
+            if style.uppercase {
+                ansi.extend(text.flat_map(char::to_uppercase));
+            } else {
+                ansi.extend(text);
+            }
+
+            ansi.push_str("\x1b[0m");
+
+            if link.is_some() {
+                ansi.push_str("\x1b]8;;\x1b\\");
+            }
+        }
+
+        ansi
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+impl Display for StyledText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, span) in self.spans.iter().enumerate() {
+            let style = &span.style;
+
+            if i > 0 {
+                f.write_str("$z")?;
+            }
+
+            if let Some(ref link) = style.link {
+                let code = if style.manialink { 'h' } else { 'l' };
+
+                // Links to their own text are written without URL, otherwise a `]`
+                // would end the URL early and is percent-encoded instead.
+                if *link == span.text && !link.is_empty() && !link.starts_with('[') {
+                    write!(f, "${code}")?;
+                } else {
+                    write!(f, "${code}[{}]", link.replace(']', "%5D"))?;
+                }
+            }
+
+            if style.bold {
+                f.write_str("$o")?;
+            }
+
+            if style.italic {
+                f.write_str("$i")?;
+            }
+
+            match style.width {
+                Width::Normal => {}
+                Width::Wide => f.write_str("$w")?,
+                Width::Narrow => f.write_str("$n")?,
+            }
+
+            if style.shadow {
+                f.write_str("$s")?;
+            }
+
+            if style.uppercase {
+                f.write_str("$t")?;
+            }
+
+            if let Some(color) = style.color {
+                write!(f, "${:x}{:x}{:x}", color.r, color.g, color.b)?;
+            }
+
+            f.write_str(&span.text.replace('$', "$$"))?;
+
+            if style.link.is_some() {
+                f.write_str(if style.manialink { "$h" } else { "$l" })?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl From<&str> for StyledText {
+    fn from(text: &str) -> Self {
+        Self::parse(text)
+    }
+}
@@ -66,7 +66,8 @@ where
 }
 
 /// RGB color.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rgb {
     /// Red. [0.0, 1.0]
     pub red: f32,
@@ -77,14 +78,16 @@ pub struct Rgb {
 }
 
 /// Reference to an internal file.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InternalFileRef {
     /// Internal path to the file.
     pub path: PathBuf,
 }
 
 /// Reference to an external file.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExternalFileRef {
     /// Hash digest of the file created using SHA-256.
     pub hash: [u8; 32],
@@ -95,7 +98,8 @@ pub struct ExternalFileRef {
 }
 
 /// Reference to a file.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FileRef {
     /// Reference to an internal file.
     Internal(InternalFileRef),
@@ -203,3 +207,23 @@ impl Display for Id {
         }
     }
 }
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Id {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Id {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+
+        if s.is_empty() {
+            Ok(Self::empty())
+        } else {
+            Ok(Self::new(s))
+        }
+    }
+}
@@ -24,7 +24,17 @@ impl error::Error for Error {}
 /// Write result.
 pub type Result = result::Result<(), Error>;
 
-type HeaderChunks<T> = Vec<(u32, fn(&T, Writer<&mut Vec<u8>, &mut IdState>) -> Result)>;
+type HeaderChunks<T> = Vec<(
+    u32,
+    fn(&T, &WriteOptions, Writer<&mut Vec<u8>, &mut IdState>) -> Result,
+)>;
+
+/// Options of a [`WriterBuilder`] which are specific to the written node.
+#[derive(Clone, Default, Debug)]
+pub(crate) struct WriteOptions {
+    /// Whether or not to recompute the number of checkpoints of a map.
+    pub(crate) recompute_num_cps: bool,
+}
 
 /// Writer builder.
 pub struct WriterBuilder<'a, T> {
@@ -34,6 +44,8 @@ pub struct WriterBuilder<'a, T> {
     class_id: u32,
     header_chunks: HeaderChunks<T>,
     body: fn(&T, &mut Writer<&mut Vec<u8>, IdState, &mut NodeState>) -> Result,
+    options: WriteOptions,
+    error: Option<Error>,
 }
 
 impl<'a, T> WriterBuilder<'a, T> {
@@ -50,9 +62,22 @@ impl<'a, T> WriterBuilder<'a, T> {
             class_id,
             header_chunks,
             body,
+            options: WriteOptions::default(),
+            error: None,
         }
     }
 
+    /// Fail writing with the given `error`, e.g. if preparing the node for writing failed.
+    pub(crate) fn fail(mut self, error: Error) -> Self {
+        self.error = Some(error);
+        self
+    }
+
+    /// The options specific to the written node.
+    pub(crate) fn options_mut(&mut self) -> &mut WriteOptions {
+        &mut self.options
+    }
+
     /// Set whether or not to write the user data.
     ///
     /// Set to `true` by default.
@@ -100,6 +125,10 @@ impl<'a, T> WriterBuilder<'a, T> {
     where
         W: Write,
     {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
         let mut body = vec![];
         let mut node_state = NodeState::new();
         {
@@ -115,7 +144,7 @@ impl<'a, T> WriterBuilder<'a, T> {
         w.u16(6)?;
         w.u8(b'B')?;
         w.u8(b'U')?;
-        w.u8(b'C')?;
+        w.u8(if self.compress_body { b'C' } else { b'U' })?;
         w.u8(b'R')?;
         w.u32(self.class_id)?;
 
@@ -129,7 +158,7 @@ impl<'a, T> WriterBuilder<'a, T> {
                 for (chunk_id, write_fn) in self.header_chunks {
                     let mut chunk = vec![];
                     let w = Writer::with_id_state(&mut chunk, &mut id_state);
-                    write_fn(self.node, w)?;
+                    write_fn(self.node, &self.options, w)?;
                     chunks.push((chunk_id, chunk));
                 }
 
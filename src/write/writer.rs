@@ -112,7 +112,18 @@ where
                 self.u32(0)?;
                 self.u32(0)?;
             }
-            _ => todo!(),
+            Some(FileRef::Internal(internal_file_ref)) => {
+                let mut hash = [0; 32];
+                hash[0] = 2;
+                self.bytes(&hash)?;
+                self.string(&internal_file_ref.path.to_string_lossy())?;
+                self.u32(0)?;
+            }
+            Some(FileRef::External(external_file_ref)) => {
+                self.bytes(&external_file_ref.hash)?;
+                self.string(&external_file_ref.path.to_string_lossy())?;
+                self.string(&external_file_ref.locator_url)?;
+            }
         }
 
         Ok(())
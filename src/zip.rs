@@ -0,0 +1,398 @@
+use crate::{read, write};
+use libz_sys::{uInt, voidpf, z_stream};
+use std::alloc::{self, Layout};
+use std::mem;
+use std::os::raw::c_int;
+use std::ptr;
+use std::result;
+
+const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x04034B50;
+const CENTRAL_DIRECTORY_HEADER_SIGNATURE: u32 = 0x02014B50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054B50;
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+
+/// Maximum ratio between the decompressed and compressed size of deflate data.
+const MAX_DEFLATE_RATIO: usize = 1032;
+
+/// A file inside of a ZIP archive.
+pub struct Entry {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+fn u16_at(bytes: &[u8], pos: usize) -> read::Result<u16> {
+    bytes
+        .get(pos..pos + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| read::Error(String::from("unexpected end of zip archive")))
+}
+
+fn u32_at(bytes: &[u8], pos: usize) -> read::Result<u32> {
+    bytes
+        .get(pos..pos + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| read::Error(String::from("unexpected end of zip archive")))
+}
+
+fn slice_at(bytes: &[u8], pos: usize, len: usize) -> read::Result<&[u8]> {
+    bytes
+        .get(pos..pos + len)
+        .ok_or_else(|| read::Error(String::from("unexpected end of zip archive")))
+}
+
+fn find_end_of_central_directory(bytes: &[u8]) -> read::Result<usize> {
+    let last = bytes
+        .len()
+        .checked_sub(22)
+        .ok_or_else(|| read::Error(String::from("zip archive is too small")))?;
+    let first = last.saturating_sub(0xFFFF);
+
+    (first..=last)
+        .rev()
+        .find(|&pos| u32_at(bytes, pos).ok() == Some(END_OF_CENTRAL_DIRECTORY_SIGNATURE))
+        .ok_or_else(|| read::Error(String::from("missing zip end of central directory")))
+}
+
+/// Header of a file in the central directory of a ZIP archive.
+struct CentralDirectoryEntry {
+    name: String,
+    method: u16,
+    crc: u32,
+    compressed_size: usize,
+    size: usize,
+    offset: usize,
+}
+
+/// Read the central directory of the ZIP archive `bytes` without decompressing any file.
+fn read_central_directory(bytes: &[u8]) -> read::Result<Vec<CentralDirectoryEntry>> {
+    let eocd = find_end_of_central_directory(bytes)?;
+    let num_entries = u16_at(bytes, eocd + 10)? as usize;
+    let mut pos = u32_at(bytes, eocd + 16)? as usize;
+
+    let mut entries = Vec::with_capacity(num_entries);
+
+    for _ in 0..num_entries {
+        if u32_at(bytes, pos)? != CENTRAL_DIRECTORY_HEADER_SIGNATURE {
+            return Err(read::Error(String::from(
+                "expected zip central directory header",
+            )));
+        }
+
+        let method = u16_at(bytes, pos + 10)?;
+        let crc = u32_at(bytes, pos + 16)?;
+        let compressed_size = u32_at(bytes, pos + 20)? as usize;
+        let size = u32_at(bytes, pos + 24)? as usize;
+        let name_len = u16_at(bytes, pos + 28)? as usize;
+        let extra_len = u16_at(bytes, pos + 30)? as usize;
+        let comment_len = u16_at(bytes, pos + 32)? as usize;
+        let offset = u32_at(bytes, pos + 42)? as usize;
+        let name = String::from_utf8_lossy(slice_at(bytes, pos + 46, name_len)?).into_owned();
+
+        pos += 46 + name_len + extra_len + comment_len;
+
+        entries.push(CentralDirectoryEntry {
+            name,
+            method,
+            crc,
+            compressed_size,
+            size,
+            offset,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Read and decompress the file of the ZIP archive `bytes` described by `entry`.
+fn read_entry(bytes: &[u8], entry: CentralDirectoryEntry) -> read::Result<Entry> {
+    let offset = entry.offset;
+
+    if u32_at(bytes, offset)? != LOCAL_FILE_HEADER_SIGNATURE {
+        return Err(read::Error(String::from("expected zip local file header")));
+    }
+
+    let data_offset =
+        offset + 30 + u16_at(bytes, offset + 26)? as usize + u16_at(bytes, offset + 28)? as usize;
+    let compressed = slice_at(bytes, data_offset, entry.compressed_size)?;
+
+    let data = match entry.method {
+        METHOD_STORED => compressed.to_vec(),
+        METHOD_DEFLATED => inflate(compressed, entry.size)?,
+        method => {
+            return Err(read::Error(format!(
+                "unsupported zip compression method {method}"
+            )))
+        }
+    };
+
+    if crc32(&data) != entry.crc {
+        return Err(read::Error(format!(
+            "invalid checksum of zip file {}",
+            entry.name
+        )));
+    }
+
+    Ok(Entry {
+        name: entry.name,
+        data,
+    })
+}
+
+/// Read all files of the ZIP archive `bytes`.
+///
+/// Only stored and deflated files are supported.
+pub fn read_archive(bytes: &[u8]) -> read::Result<Vec<Entry>> {
+    read_central_directory(bytes)?
+        .into_iter()
+        .map(|entry| read_entry(bytes, entry))
+        .collect()
+}
+
+/// Read the first file of the ZIP archive `bytes` of which the name matches `predicate`.
+///
+/// Only the matching file is decompressed.
+pub fn read_file<P>(bytes: &[u8], predicate: P) -> read::Result<Option<Entry>>
+where
+    P: Fn(&str) -> bool,
+{
+    read_central_directory(bytes)?
+        .into_iter()
+        .find(|entry| predicate(&entry.name))
+        .map(|entry| read_entry(bytes, entry))
+        .transpose()
+}
+
+/// Write all `entries` into a ZIP archive, deflating them if that reduces their size.
+///
+/// Returns an error if the entries exceed the limits of a ZIP archive without ZIP64 extensions.
+pub fn write_archive(entries: &[Entry]) -> result::Result<Vec<u8>, write::Error> {
+    let num_entries = u16::try_from(entries.len()).map_err(|_| {
+        write::Error(format!(
+            "{} files exceed the maximum of {} files in a zip archive",
+            entries.len(),
+            u16::MAX
+        ))
+    })?;
+
+    let mut archive = vec![];
+    let mut central_directory = vec![];
+
+    for entry in entries {
+        let offset = zip_u32(archive.len())?;
+        let compressed = deflate(&entry.data)?;
+        let (method, data) = if compressed.len() < entry.data.len() {
+            (METHOD_DEFLATED, compressed.as_slice())
+        } else {
+            (METHOD_STORED, entry.data.as_slice())
+        };
+        let crc = crc32(&entry.data);
+        let name = entry.name.as_bytes();
+        let name_len = u16::try_from(name.len())
+            .map_err(|_| write::Error(format!("zip file name {} is too long", entry.name)))?;
+        let compressed_size = zip_u32(data.len())?;
+        let size = zip_u32(entry.data.len())?;
+
+        archive.extend_from_slice(&LOCAL_FILE_HEADER_SIGNATURE.to_le_bytes());
+        archive.extend_from_slice(&20u16.to_le_bytes());
+        archive.extend_from_slice(&0u16.to_le_bytes());
+        archive.extend_from_slice(&method.to_le_bytes());
+        archive.extend_from_slice(&0u16.to_le_bytes());
+        archive.extend_from_slice(&0x0021u16.to_le_bytes());
+        archive.extend_from_slice(&crc.to_le_bytes());
+        archive.extend_from_slice(&compressed_size.to_le_bytes());
+        archive.extend_from_slice(&size.to_le_bytes());
+        archive.extend_from_slice(&name_len.to_le_bytes());
+        archive.extend_from_slice(&0u16.to_le_bytes());
+        archive.extend_from_slice(name);
+        archive.extend_from_slice(data);
+
+        central_directory.extend_from_slice(&CENTRAL_DIRECTORY_HEADER_SIGNATURE.to_le_bytes());
+        central_directory.extend_from_slice(&20u16.to_le_bytes());
+        central_directory.extend_from_slice(&20u16.to_le_bytes());
+        central_directory.extend_from_slice(&0u16.to_le_bytes());
+        central_directory.extend_from_slice(&method.to_le_bytes());
+        central_directory.extend_from_slice(&0u16.to_le_bytes());
+        central_directory.extend_from_slice(&0x0021u16.to_le_bytes());
+        central_directory.extend_from_slice(&crc.to_le_bytes());
+        central_directory.extend_from_slice(&compressed_size.to_le_bytes());
+        central_directory.extend_from_slice(&size.to_le_bytes());
+        central_directory.extend_from_slice(&name_len.to_le_bytes());
+        central_directory.extend_from_slice(&0u16.to_le_bytes());
+        central_directory.extend_from_slice(&0u16.to_le_bytes());
+        central_directory.extend_from_slice(&0u16.to_le_bytes());
+        central_directory.extend_from_slice(&0u16.to_le_bytes());
+        central_directory.extend_from_slice(&0u32.to_le_bytes());
+        central_directory.extend_from_slice(&offset.to_le_bytes());
+        central_directory.extend_from_slice(name);
+    }
+
+    let central_directory_offset = zip_u32(archive.len())?;
+    let central_directory_size = zip_u32(central_directory.len())?;
+    archive.extend_from_slice(&central_directory);
+
+    archive.extend_from_slice(&END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes());
+    archive.extend_from_slice(&num_entries.to_le_bytes());
+    archive.extend_from_slice(&num_entries.to_le_bytes());
+    archive.extend_from_slice(&central_directory_size.to_le_bytes());
+    archive.extend_from_slice(&central_directory_offset.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes());
+
+    Ok(archive)
+}
+
+/// Convert a size or offset into a 32-bit ZIP field.
+fn zip_u32(value: usize) -> result::Result<u32, write::Error> {
+    u32::try_from(value).map_err(|_| {
+        write::Error(String::from(
+            "zip archive exceeds the maximum size of 4 GiB",
+        ))
+    })
+}
+
+/// CRC-32 checksum of `data`, as used by ZIP and PNG.
+pub fn crc32(data: &[u8]) -> u32 {
+    unsafe { libz_sys::crc32(0, data.as_ptr(), data.len() as u32) as u32 }
+}
+
+const ALLOC_ALIGN: usize = 16;
+
+unsafe extern "C" fn zalloc(_opaque: voidpf, items: uInt, size: uInt) -> voidpf {
+    let size = items as usize * size as usize + ALLOC_ALIGN;
+    let layout = match Layout::from_size_align(size, ALLOC_ALIGN) {
+        Ok(layout) => layout,
+        Err(_) => return ptr::null_mut(),
+    };
+    let ptr = alloc::alloc(layout);
+
+    if ptr.is_null() {
+        return ptr::null_mut();
+    }
+
+    *(ptr as *mut usize) = size;
+    ptr.add(ALLOC_ALIGN) as voidpf
+}
+
+unsafe extern "C" fn zfree(_opaque: voidpf, address: voidpf) {
+    if address.is_null() {
+        return;
+    }
+
+    let ptr = (address as *mut u8).sub(ALLOC_ALIGN);
+    let size = *(ptr as *mut usize);
+    alloc::dealloc(ptr, Layout::from_size_align_unchecked(size, ALLOC_ALIGN));
+}
+
+fn new_stream() -> z_stream {
+    z_stream {
+        next_in: ptr::null_mut(),
+        avail_in: 0,
+        total_in: 0,
+        next_out: ptr::null_mut(),
+        avail_out: 0,
+        total_out: 0,
+        msg: ptr::null_mut(),
+        state: ptr::null_mut(),
+        zalloc,
+        zfree,
+        opaque: ptr::null_mut(),
+        data_type: 0,
+        adler: 0,
+        reserved: 0,
+    }
+}
+
+/// Decompress raw deflate `data` of which the decompressed size is `size`.
+fn inflate(data: &[u8], size: usize) -> read::Result<Vec<u8>> {
+    if size > data.len().saturating_mul(MAX_DEFLATE_RATIO) {
+        return Err(read::Error(format!(
+            "decompressed size {size} of deflate data is too large"
+        )));
+    }
+
+    let mut output = vec![0; size];
+    let mut stream = new_stream();
+
+    unsafe {
+        if libz_sys::inflateInit2_(
+            &mut stream,
+            -15,
+            libz_sys::zlibVersion(),
+            mem::size_of::<z_stream>() as c_int,
+        ) != libz_sys::Z_OK
+        {
+            return Err(read::Error(String::from("failed to initialize inflate")));
+        }
+
+        stream.next_in = data.as_ptr() as *mut u8;
+        stream.avail_in = data.len() as uInt;
+        stream.next_out = output.as_mut_ptr();
+        stream.avail_out = output.len() as uInt;
+
+        let result = libz_sys::inflate(&mut stream, libz_sys::Z_FINISH);
+        let total_out = stream.total_out as usize;
+        libz_sys::inflateEnd(&mut stream);
+
+        if result != libz_sys::Z_STREAM_END || total_out != size {
+            return Err(read::Error(String::from("invalid deflate data")));
+        }
+    }
+
+    Ok(output)
+}
+
+/// Compress `data` as raw deflate.
+fn deflate(data: &[u8]) -> result::Result<Vec<u8>, write::Error> {
+    deflate_with_window_bits(data, -15)
+}
+
+/// Compress `data` as a zlib stream, e.g. for PNG.
+pub fn compress(data: &[u8]) -> result::Result<Vec<u8>, write::Error> {
+    deflate_with_window_bits(data, 15)
+}
+
+/// Compress `data`, writing a zlib header and trailer if `window_bits` is positive.
+fn deflate_with_window_bits(
+    data: &[u8],
+    window_bits: c_int,
+) -> result::Result<Vec<u8>, write::Error> {
+    let len = uInt::try_from(data.len())
+        .map_err(|_| write::Error(String::from("data is too large to deflate")))?;
+    let mut stream = new_stream();
+
+    unsafe {
+        let result = libz_sys::deflateInit2_(
+            &mut stream,
+            libz_sys::Z_DEFAULT_COMPRESSION,
+            libz_sys::Z_DEFLATED,
+            window_bits,
+            8,
+            libz_sys::Z_DEFAULT_STRATEGY,
+            libz_sys::zlibVersion(),
+            mem::size_of::<z_stream>() as c_int,
+        );
+        if result != libz_sys::Z_OK {
+            return Err(write::Error(String::from("failed to initialize deflate")));
+        }
+
+        let mut output = vec![0; libz_sys::deflateBound(&mut stream, len as _) as usize];
+
+        stream.next_in = data.as_ptr() as *mut u8;
+        stream.avail_in = len;
+        stream.next_out = output.as_mut_ptr();
+        stream.avail_out = output.len() as uInt;
+
+        let result = libz_sys::deflate(&mut stream, libz_sys::Z_FINISH);
+        output.truncate(stream.total_out as usize);
+        libz_sys::deflateEnd(&mut stream);
+
+        if result != libz_sys::Z_STREAM_END {
+            return Err(write::Error(String::from("failed to deflate data")));
+        }
+
+        Ok(output)
+    }
+}
@@ -86,3 +86,1993 @@ fn write_read_default_map() {
     map.writer().write_to(&mut buf).unwrap();
     Map::reader().read_from(buf.as_slice()).unwrap();
 }
+
+#[test]
+fn write_read_map_size() {
+    let mut map = Map::default();
+    map.size = gbx::Vec3 {
+        x: 32,
+        y: 40,
+        z: 16,
+    };
+    let mut buf = vec![];
+    map.writer().write_to(&mut buf).unwrap();
+    let map = Map::reader().read_from(buf.as_slice()).unwrap();
+    assert_eq!((map.size.x, map.size.y, map.size.z), (32, 40, 16));
+    assert_eq!(map.baked_blocks().len(), 32 * 16);
+    assert_eq!(map.zone_genealogies.len(), 32 * 16);
+}
+
+#[test]
+fn write_map_ground() {
+    let mut map = Map::default();
+    map.no_stadium = true;
+    map.regenerate_baked_blocks();
+    assert!(map.baked_blocks().is_empty());
+
+    let mut buf = vec![];
+    map.writer().write_to(&mut buf).unwrap();
+    let map = Map::reader().read_from(buf.as_slice()).unwrap();
+    assert!(map.no_stadium);
+    assert!(map.baked_blocks().is_empty());
+
+    let mut map = Map::default();
+    map.size.x = 300;
+    let uid = gbx::map::UidMode::Explicit(gbx::Id::new(String::from("Uid")));
+    assert!(map.writer_with_uid(uid).write_to(vec![]).is_err());
+}
+
+#[test]
+fn write_read_zone_genealogy() {
+    let mut map = Map::default();
+    let zone_genealogy = map.zone_genealogy_mut(3, 5).unwrap();
+    zone_genealogy.zone_ids = vec![gbx::Id::new(String::from("GrassToDirt"))];
+    zone_genealogy.current_zone_id = gbx::Id::new(String::from("Dirt"));
+    zone_genealogy.dir = gbx::map::Direction::East;
+    let mut buf = vec![];
+    map.writer().write_to(&mut buf).unwrap();
+    let map = Map::reader().read_from(buf.as_slice()).unwrap();
+    let zone_genealogy = map.zone_genealogy(3, 5).unwrap();
+    assert_eq!(zone_genealogy.current_zone_id.as_str(), "Dirt");
+    assert_eq!(zone_genealogy.dir, gbx::map::Direction::East);
+    assert_eq!(
+        map.zone_genealogy(0, 0),
+        Some(&gbx::map::ZoneGenealogy::default())
+    );
+
+    let mut map = map;
+    map.size.z = 64;
+    assert_eq!(
+        map.zone_genealogy(3, 5).unwrap().current_zone_id.as_str(),
+        "Dirt"
+    );
+    assert!(map.zone_genealogy(3, 60).is_none());
+    map.zone_genealogy_mut(3, 60).unwrap().current_zone_id = gbx::Id::new(String::from("Dirt"));
+    let mut buf = vec![];
+    map.writer().write_to(&mut buf).unwrap();
+    let map = Map::reader().read_from(buf.as_slice()).unwrap();
+    assert_eq!(map.zone_genealogies.len(), 48 * 64);
+    assert_eq!(
+        map.zone_genealogy(3, 5).unwrap().current_zone_id.as_str(),
+        "Dirt"
+    );
+    assert_eq!(
+        map.zone_genealogy(3, 60).unwrap().current_zone_id.as_str(),
+        "Dirt"
+    );
+    assert_eq!(
+        map.zone_genealogy(4, 5),
+        Some(&gbx::map::ZoneGenealogy::default())
+    );
+}
+
+#[test]
+fn regenerate_baked_blocks() {
+    let mut map = Map::default();
+    map.blocks
+        .push(gbx::map::BlockType::Normal(gbx::map::Block {
+            model_id: gbx::Id::new(String::from("RoadTechStraight")),
+            coord: gbx::Vec3 { x: 1, y: 9, z: 2 },
+            is_ground: true,
+            ..Default::default()
+        }));
+    map.zone_genealogy_mut(0, 0).unwrap().current_zone_id = gbx::Id::new(String::from("Dirt"));
+    map.regenerate_baked_blocks();
+    assert_eq!(map.baked_blocks().len(), 48 * 48 - 1);
+    assert_eq!(map.baked_blocks()[0].model_id().as_str(), "Dirt");
+
+    map.baked_blocks_mut().clear();
+    let mut buf = vec![];
+    map.writer().write_to(&mut buf).unwrap();
+    let mut map = Map::reader().read_from(buf.as_slice()).unwrap();
+    assert!(map.baked_blocks().is_empty());
+
+    map.zone_genealogy_mut(1, 1).unwrap().current_zone_id = gbx::Id::new(String::from("Dirt"));
+    let mut buf = vec![];
+    map.writer().write_to(&mut buf).unwrap();
+    let mut map = Map::reader().read_from(buf.as_slice()).unwrap();
+    assert_eq!(map.baked_blocks().len(), 48 * 48 - 1);
+    assert_eq!(map.baked_blocks()[49].model_id().as_str(), "Dirt");
+
+    map.baked_blocks_mut().clear();
+    if let gbx::map::BlockType::Normal(block) = &mut map.blocks[0] {
+        block.coord.x = 0;
+        block.coord.z = 0;
+    }
+    let mut buf = vec![];
+    map.writer().write_to(&mut buf).unwrap();
+    let mut map = Map::reader().read_from(buf.as_slice()).unwrap();
+    assert_eq!(map.baked_blocks().len(), 48 * 48 - 1);
+    assert_eq!(map.baked_blocks()[0].model_id().as_str(), "Grass");
+    assert_eq!(map.baked_blocks()[48].model_id().as_str(), "Dirt");
+
+    map.baked_blocks_mut().clear();
+    map.no_stadium = false;
+    map.size.x = 16;
+    map.blocks
+        .push(gbx::map::BlockType::Normal(gbx::map::Block {
+            model_id: gbx::Id::new(String::from("RoadTechStraight")),
+            coord: gbx::Vec3 { x: 3, y: 20, z: 4 },
+            is_ground: true,
+            ..Default::default()
+        }));
+    let mut buf = vec![];
+    map.writer().write_to(&mut buf).unwrap();
+    let map = Map::reader().read_from(buf.as_slice()).unwrap();
+    assert_eq!(map.baked_blocks().len(), 16 * 48 - 1);
+}
+
+#[test]
+fn media_track_block_at() {
+    use gbx::map::media::{block, Block, Track, TrackSegment};
+
+    let color_grading = |start_time: f32, end_time: f32| {
+        Block::ColorGrading(block::ColorGrading {
+            grade: None,
+            keys: vec![
+                block::key::ColorGrading {
+                    time: start_time,
+                    intensity: 0.0,
+                },
+                block::key::ColorGrading {
+                    time: end_time,
+                    intensity: 1.0,
+                },
+            ],
+        })
+    };
+
+    let mut track = Track {
+        blocks: vec![color_grading(0.0, 2.0), color_grading(2.0, 4.0)],
+        keep_last_block_active: false,
+        repeat_track_segment: None,
+    };
+
+    match track.block_at(3.0) {
+        Some((Block::ColorGrading(block), time)) => {
+            assert_eq!(time, 3.0);
+            assert_eq!(block.sample(time).unwrap().intensity, 0.5);
+        }
+        _ => panic!("expected color grading block"),
+    }
+
+    assert!(track.block_at(5.0).is_none());
+
+    // A block with a single key is active at the time of its key.
+    let single_key = Track {
+        blocks: vec![Block::ColorGrading(block::ColorGrading {
+            grade: None,
+            keys: vec![block::key::ColorGrading {
+                time: 1.0,
+                intensity: 1.0,
+            }],
+        })],
+        keep_last_block_active: false,
+        repeat_track_segment: None,
+    };
+    assert_eq!(single_key.block_at(1.0).unwrap().1, 1.0);
+    assert!(single_key.block_at(1.5).is_none());
+
+    track.keep_last_block_active = true;
+    assert!(track.block_at(5.0).is_some());
+
+    track.repeat_track_segment = Some(TrackSegment {
+        start_time: 0.0,
+        end_time: 4.0,
+    });
+    assert_eq!(track.block_at(5.0).unwrap().1, 1.0);
+}
+
+/// Write a map whose intro clip holds a single track with a media block of class `class_id`.
+///
+/// `block` builds the block from the index of the first free node,
+/// which is used by `num_block_nodes` nodes of the block.
+fn write_intro_media_block(
+    class_id: u32,
+    block: impl FnOnce(u32) -> Vec<u8>,
+    num_block_nodes: u32,
+) -> Vec<u8> {
+    let mut buf = vec![];
+    Map::default()
+        .writer()
+        .compress_body(false)
+        .write_to(&mut buf)
+        .unwrap();
+
+    let user_data_len = u32::from_le_bytes(buf[13..17].try_into().unwrap()) as usize;
+    let num_nodes_pos = 17 + user_data_len;
+    let num_nodes = u32::from_le_bytes(buf[num_nodes_pos..num_nodes_pos + 4].try_into().unwrap());
+    buf[num_nodes_pos..num_nodes_pos + 4]
+        .copy_from_slice(&(num_nodes + 3 + num_block_nodes).to_le_bytes());
+
+    let u32s = |values: &[u32]| {
+        values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<u8>>()
+    };
+    let mut clip = u32s(&[
+        num_nodes + 1,
+        0x03079000,
+        0x0307900D,
+        0,
+        10,
+        1,
+        num_nodes + 2,
+        0x03078000,
+        0x03078001,
+        0,
+        10,
+        1,
+        num_nodes + 3,
+        class_id,
+    ]);
+    clip.extend(block(num_nodes + 4));
+    clip.extend(u32s(&[
+        0xFACADE01, 0xFFFFFFFF, 0x03078005, 1, 0, 0, 0, 0, 0, 0xFACADE01, 0, 0, 0, 0, 0, 0, 0,
+        0xFACADE01,
+    ]));
+
+    let chunk = u32s(&[0x03043049, 2, 0xFFFFFFFF]);
+    let pos = buf
+        .windows(chunk.len())
+        .position(|window| window == chunk.as_slice())
+        .unwrap();
+    buf.splice(pos + 8..pos + 12, clip);
+
+    buf
+}
+
+/// Read a map written by [`write_intro_media_block`] and get its media block.
+fn read_intro_media_block(
+    class_id: u32,
+    block: impl FnOnce(u32) -> Vec<u8>,
+    num_block_nodes: u32,
+) -> gbx::map::media::Block {
+    let buf = write_intro_media_block(class_id, block, num_block_nodes);
+    let map = Map::reader().read_from(buf.as_slice()).unwrap();
+    map.intro_media.unwrap().tracks[0].blocks[0].clone()
+}
+
+#[test]
+fn read_media_blocks() {
+    use gbx::map::media::Block;
+    use gbx::FileRef;
+
+    // Time, intensity, blend Z, distance and far distance, followed by the near and far
+    // parameters: inverse, hue, saturation, brightness, contrast, RGB and 4 unknown values.
+    let floats = [
+        0.5, 1.0, 0.25, 100.0, 200.0, //
+        0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.0, 0.0, 0.0, 0.0, //
+        0.9, 0.8, 0.7, 0.6, 0.5, 0.4, 0.3, 0.2, 0.0, 0.0, 0.0, 0.0_f32,
+    ];
+    let mut color = [0x03080003_u32, 1]
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect::<Vec<u8>>();
+    color.extend(floats.iter().flat_map(|value| value.to_le_bytes()));
+
+    match read_intro_media_block(0x03080000, |_| color, 0) {
+        Block::Color(block) => {
+            let key = &block.keys[0];
+            assert_eq!(key.time, 0.5);
+            assert_eq!(key.far_distance, 200.0);
+            assert_eq!(key.near.inverse, 0.1);
+            assert_eq!(key.near.hue, 0.2);
+            assert_eq!(key.near.contrast, 0.5);
+            assert_eq!(key.near.color.blue, 0.8);
+            assert_eq!(key.far.inverse, 0.9);
+            assert_eq!(key.far.color.red, 0.4);
+        }
+        _ => panic!("expected color block"),
+    }
+
+    let file_ref = |hash: [u8; 32], path: &str| {
+        let mut bytes = vec![3];
+        bytes.extend_from_slice(&hash);
+        bytes.extend_from_slice(&(path.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(path.as_bytes());
+        bytes.extend_from_slice(&0_u32.to_le_bytes());
+        bytes
+    };
+    let u32s = |values: &[u32]| {
+        values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<u8>>()
+    };
+
+    // Version 6 with an empty entity record node.
+    let entity = |node_index| {
+        let mut entity = u32s(&[0x0329F000, 6, node_index, 0x0911F000, 0x0911F000]);
+        entity.extend(u32s(&[0, 0, 0, 0xFACADE01]));
+        entity.extend(u32s(&[
+            0, 0, 1, 0, 0, 0, 0xFFFFFFFF, 0, 0xFFFFFFFF, 0, 0, 0, 3,
+        ]));
+        let mut internal = [0; 32];
+        internal[0] = 2;
+        entity.extend(file_ref([0; 32], ""));
+        entity.extend(file_ref(internal, "Skins\\Car.zip"));
+        entity.extend(file_ref([0; 32], ""));
+        entity.extend(u32s(&[0, 0]));
+        entity
+    };
+
+    match read_intro_media_block(0x0329F000, entity, 1) {
+        Block::Entity(block) => {
+            assert!(block.no_damage);
+            assert_eq!(block.skins.len(), 3);
+            assert!(block.skins[0].is_none());
+            assert!(matches!(block.skins[1], Some(FileRef::Internal(_))));
+            assert!(block.skins[2].is_none());
+        }
+        _ => panic!("expected entity block"),
+    }
+}
+
+#[test]
+fn media_camera_keys() {
+    use gbx::map::media::block;
+    use gbx::map::media::block::key::{self, CameraTangent, Interpolation};
+    use gbx::Vec3;
+
+    let custom_key = |time: f32, pitch: f32, pitch_tangent: f32| key::CustomCamera {
+        time,
+        interpolation: Interpolation::Hermite,
+        anchor_rotation: false,
+        anchor: None,
+        show_anchor: false,
+        target: None,
+        position: Vec3::new(time, 0.0, 0.0),
+        pitch,
+        yaw: 0.0,
+        roll: 0.0,
+        fov: 90.0,
+        target_position: Vec3::default(),
+        z_near: 0.1,
+        left_tangent: CameraTangent::default(),
+        right_tangent: CameraTangent {
+            pitch: pitch_tangent,
+            ..CameraTangent::default()
+        },
+    };
+
+    let custom_camera = block::CustomCamera {
+        keys: vec![custom_key(0.0, 0.0, 1.0), custom_key(1.0, 1.0, 0.0)],
+    };
+    let key = custom_camera.sample(0.5).unwrap();
+    assert_eq!(key.pitch, 0.625);
+    assert_eq!(key.fov, 90.0);
+    assert_eq!(key.position, Vec3::new(0.5, 0.0, 0.0));
+
+    // Tangents are per second, so they are scaled by the interval between the keys.
+    let custom_camera = block::CustomCamera {
+        keys: vec![custom_key(0.0, 0.0, 1.0), custom_key(2.0, 1.0, 0.0)],
+    };
+    assert_eq!(custom_camera.sample(1.0).unwrap().pitch, 0.75);
+
+    let path_key = |time: f32, right_tangent: Vec3<f32>| key::PathCamera {
+        time,
+        position: Vec3::new(time, 0.0, 0.0),
+        pitch: 0.0,
+        yaw: 0.0,
+        roll: 0.0,
+        fov: 90.0,
+        z_near: 0.1,
+        anchor_rotation: false,
+        anchor: None,
+        show_anchor: false,
+        target: None,
+        target_position: Vec3::default(),
+        weight: 0.0,
+        left_tangent: Vec3::default(),
+        right_tangent,
+    };
+
+    let path_camera = block::PathCamera {
+        keys: vec![
+            path_key(0.0, Vec3::new(0.0, 1.0, 0.0)),
+            path_key(2.0, Vec3::default()),
+        ],
+    };
+    let key = path_camera.sample(1.0).unwrap();
+    assert_eq!(key.position.y, 0.25);
+    assert_eq!(key.position.x, 1.0);
+
+    let orbital_key = |time: f32, distance: f32| key::OrbitalCamera {
+        time,
+        center: Vec3::new(10.0, 20.0, 30.0),
+        horizontal_angle: time,
+        vertical_angle: 0.5,
+        distance,
+        fov: 90.0,
+        z_near: 0.1,
+        anchor_rotation: false,
+        anchor: Some(0),
+        show_anchor: true,
+        target: None,
+        target_position: Vec3::default(),
+    };
+
+    let orbital_camera = block::OrbitalCamera {
+        keys: vec![orbital_key(0.0, 10.0), orbital_key(2.0, 20.0)],
+    };
+    let key = orbital_camera.sample(1.0).unwrap();
+    assert_eq!(key.horizontal_angle, 1.0);
+    assert_eq!(key.distance, 15.0);
+    assert_eq!(key.anchor, Some(0));
+    assert!(key.show_anchor);
+}
+
+#[test]
+fn camera_path_export() {
+    use gbx::map::media::block::key::{self, CameraTangent, Interpolation};
+    use gbx::map::media::export::{CameraKeyframe, CameraPath, ExportError};
+    use gbx::map::media::{block, Block, Clip, Track};
+    use gbx::Vec3;
+
+    fn rotate(q: [f32; 4], v: [f32; 3]) -> [f32; 3] {
+        let [x, y, z, w] = q;
+        let t = [
+            2.0 * (y * v[2] - z * v[1]),
+            2.0 * (z * v[0] - x * v[2]),
+            2.0 * (x * v[1] - y * v[0]),
+        ];
+
+        [
+            v[0] + w * t[0] + (y * t[2] - z * t[1]),
+            v[1] + w * t[1] + (z * t[0] - x * t[2]),
+            v[2] + w * t[2] + (x * t[1] - y * t[0]),
+        ]
+    }
+
+    fn assert_near(actual: [f32; 3], expected: [f32; 3]) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-4, "{actual:?} != {expected:?}");
+        }
+    }
+
+    let position = Vec3::new(1.0, 2.0, 3.0);
+
+    // A positive pitch looks downwards, mirroring along X keeps the forward Z component.
+    let keyframe = CameraKeyframe::from_gbx(0.0, &position, 0.5, 0.0, 0.3, 90.0);
+    assert_eq!(keyframe.position, Vec3::new(-1.0, 2.0, 3.0));
+    assert_near(
+        rotate(keyframe.rotation, [0.0, 0.0, -1.0]),
+        [0.0, -0.47943, 0.87758],
+    );
+    assert_near(
+        rotate(keyframe.rotation, [0.0, 1.0, 0.0]),
+        [0.29552, 0.83838, 0.45801],
+    );
+
+    // A quarter yaw looks along +X in the game and along -X after mirroring.
+    let keyframe =
+        CameraKeyframe::from_gbx(0.0, &position, 0.0, std::f32::consts::FRAC_PI_2, 0.0, 90.0);
+    assert_near(
+        rotate(keyframe.rotation, [0.0, 0.0, -1.0]),
+        [-1.0, 0.0, 0.0],
+    );
+    assert_near(rotate(keyframe.rotation, [0.0, 1.0, 0.0]), [0.0, 1.0, 0.0]);
+
+    let camera_key = |time: f32| key::CustomCamera {
+        time,
+        interpolation: Interpolation::Linear,
+        anchor_rotation: false,
+        anchor: None,
+        show_anchor: false,
+        target: None,
+        position: Vec3::new(time * 10.0, 0.0, 0.0),
+        pitch: 0.0,
+        yaw: 0.0,
+        roll: 0.0,
+        fov: 90.0,
+        target_position: Vec3::default(),
+        z_near: 0.1,
+        left_tangent: CameraTangent::default(),
+        right_tangent: CameraTangent::default(),
+    };
+
+    let clip = Clip {
+        tracks: vec![Track {
+            blocks: vec![Block::CustomCamera(block::CustomCamera {
+                keys: vec![camera_key(0.0), camera_key(1.0)],
+            })],
+            ..Track::default()
+        }],
+        ..Clip::default()
+    };
+
+    for frame_rate in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+        assert!(matches!(
+            CameraPath::from_clip(&clip, frame_rate),
+            Err(ExportError::InvalidFrameRate(_))
+        ));
+    }
+    assert_eq!(
+        CameraPath::from_clip(&clip, f32::MAX).unwrap_err(),
+        ExportError::TooManyFrames
+    );
+
+    let path = CameraPath::from_clip(&clip, 4.0).unwrap();
+    assert_eq!(path.keyframes.len(), 5);
+    assert_eq!(path.keyframes[2].time, 0.5);
+    assert_eq!(path.keyframes[2].position, Vec3::new(-5.0, 0.0, 0.0));
+
+    let mut json = vec![];
+    path.write_json(&mut json).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 5);
+    assert_eq!(json[2]["time"], 0.5);
+    assert_eq!(json[2]["position"], serde_json::json!([-5, 0, 0]));
+    assert_eq!(json[2]["rotation"], serde_json::json!([0, 1, 0, 0]));
+    assert_eq!(json[2]["fov"], 90.0);
+
+    let mut csv = vec![];
+    path.write_csv(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 6);
+    assert_eq!(lines[0], "time,x,y,z,qx,qy,qz,qw,yaw,pitch,roll,fov");
+    assert_eq!(lines[3], "0.5,-5,0,0,0,1,0,0,-0,0,-0,90");
+
+    let mut gltf = vec![];
+    path.write_gltf(&mut gltf).unwrap();
+    let gltf: serde_json::Value = serde_json::from_slice(&gltf).unwrap();
+    assert_eq!(gltf["asset"]["version"], "2.0");
+    assert_eq!(gltf["buffers"][0]["byteLength"], 5 * (4 + 12 + 16));
+    assert_eq!(gltf["accessors"][0]["count"], 5);
+    assert_eq!(gltf["accessors"][0]["max"], serde_json::json!([1]));
+    assert_eq!(
+        gltf["animations"][0]["channels"][1]["target"]["path"],
+        "rotation"
+    );
+
+    assert!(CameraPath::default().write_gltf(vec![]).is_err());
+}
+
+#[test]
+fn media_clip_edit() {
+    use gbx::map::media::{block, Block, Clip, ClipGroup, ClipTrigger, Condition, Track};
+
+    let clip = |end_time: f32| Clip {
+        tracks: vec![Track {
+            blocks: vec![Block::TimeSpeed(block::TimeSpeed {
+                keys: vec![
+                    block::key::TimeSpeed {
+                        time: 0.0,
+                        speed: 0.0,
+                    },
+                    block::key::TimeSpeed {
+                        time: end_time,
+                        speed: 1.0,
+                    },
+                ],
+            })],
+            ..Track::default()
+        }],
+        ..Clip::default()
+    };
+
+    let mut intro = clip(10.0);
+    intro.trim(0.0..5.0);
+    assert_eq!(intro.end_time(), 5.0);
+
+    match intro.tracks[0].blocks[0] {
+        Block::TimeSpeed(ref block) => assert_eq!(block.keys[1].speed, 0.5),
+        _ => panic!("expected time speed block"),
+    }
+
+    intro.scale(2.0);
+    assert_eq!(intro.end_time(), 10.0);
+
+    intro.concat(clip(2.0));
+    assert_eq!(intro.end_time(), 12.0);
+    assert_eq!(intro.tracks[0].blocks.len(), 2);
+
+    let trigger = |clip_index: u32| ClipTrigger {
+        condition: Condition::AlreadyTriggered {
+            clip_index: Some(clip_index),
+        },
+        ..ClipTrigger::default()
+    };
+
+    let mut clip_group = ClipGroup {
+        clips: vec![trigger(1), trigger(0)],
+    };
+
+    clip_group.insert(0, trigger(0));
+    clip_group.remove(1);
+
+    match clip_group.clips[1].condition {
+        Condition::AlreadyTriggered { clip_index } => assert_eq!(clip_index, None),
+        _ => panic!("expected already triggered condition"),
+    }
+    match clip_group.clips[0].condition {
+        Condition::AlreadyTriggered { clip_index } => assert_eq!(clip_index, Some(0)),
+        _ => panic!("expected already triggered condition"),
+    }
+}
+
+#[test]
+fn embed_files() {
+    let mut map = Map::default();
+    let item = b"GBX item data GBX item data GBX item data".to_vec();
+    let embedded_files = map.embedded_files.get_or_insert_with(Default::default);
+    embedded_files
+        .embed("Items/A.Item.Gbx", item.clone(), None)
+        .unwrap();
+    embedded_files
+        .embed("Items/B.Item.Gbx", vec![1, 2, 3], None)
+        .unwrap();
+    assert!(embedded_files.remove("Items\\B.Item.Gbx").unwrap());
+
+    let mut buf = vec![];
+    map.writer().write_to(&mut buf).unwrap();
+    let map = Map::reader().read_from(buf.as_slice()).unwrap();
+    let embedded_files = map.embedded_files.unwrap();
+    assert_eq!(embedded_files.paths.len(), 1);
+    assert_eq!(embedded_files.file("Items/A.Item.Gbx").unwrap(), Some(item));
+
+    let mut embedded_files = embedded_files;
+    embedded_files
+        .embed("Items\\C.Item.Gbx", vec![4, 5, 6], None)
+        .unwrap();
+    assert_eq!(embedded_files.paths[1].as_str(), "Items/C.Item.Gbx");
+    assert_eq!(embedded_files.files().unwrap()[1].path, "Items/C.Item.Gbx");
+    assert_eq!(
+        embedded_files.file("Items\\C.Item.Gbx").unwrap(),
+        Some(vec![4, 5, 6])
+    );
+
+    let archive = &mut embedded_files.archive;
+    let eocd = archive.len() - 22;
+    let central_directory = u32::from_le_bytes(archive[eocd + 16..eocd + 20].try_into().unwrap());
+    let size = central_directory as usize + 24;
+    archive[size..size + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(embedded_files.file("Items/A.Item.Gbx").is_err());
+    let err: gbx::write::Error = embedded_files.remove("Items/A.Item.Gbx").unwrap_err();
+    assert!(err.to_string().contains("invalid embedded files archive"));
+    assert!(embedded_files
+        .embed("Items/D.Item.Gbx", vec![], None)
+        .is_err());
+}
+
+#[test]
+fn map_dependencies() {
+    let mut map = Map::default();
+    for model_id in ["Foo.Item.Gbx", "Sub\\Bar.Item.Gbx", "Sub\\Bar.Item.Gbx"] {
+        map.items.push(gbx::map::Item {
+            model_id: gbx::Id::new(String::from(model_id)),
+            ..Default::default()
+        });
+    }
+    let embedded_files = map.embedded_files.get_or_insert_with(Default::default);
+    embedded_files
+        .embed("Items/Sub/Bar.Item.Gbx", vec![0], None)
+        .unwrap();
+    embedded_files
+        .embed("Items/Unused.Item.Gbx", vec![0], None)
+        .unwrap();
+
+    let dependencies = map.dependencies();
+    assert_eq!(dependencies.models.len(), 2);
+    assert_eq!(
+        dependencies.missing,
+        [gbx::Id::new(String::from("Foo.Item.Gbx"))]
+    );
+    assert_eq!(
+        dependencies.unused,
+        [gbx::Id::new(String::from("Items/Unused.Item.Gbx"))]
+    );
+
+    for model_id in ["RoadTechStraight", "Blocks/Ramp.Block.Gbx_CustomBlock"] {
+        map.blocks
+            .push(gbx::map::BlockType::Normal(gbx::map::Block {
+                model_id: gbx::Id::new(String::from(model_id)),
+                ..Default::default()
+            }));
+    }
+    map.embedded_files
+        .as_mut()
+        .unwrap()
+        .embed(
+            "Blocks/Ramp.Block.Gbx",
+            vec![0],
+            Some(gbx::Id::new(String::from("Someone"))),
+        )
+        .unwrap();
+    let texture_mod = gbx::ExternalFileRef {
+        hash: [0; 32],
+        path: std::path::PathBuf::from("Skins/Stadium/Mod/MyMod.zip"),
+        locator_url: String::new(),
+    };
+    map.music = Some(gbx::FileRef::External(texture_mod.clone()));
+    map.texture_mod = Some(texture_mod);
+
+    let dependencies = map.dependencies();
+    let blocks: Vec<_> = dependencies
+        .models
+        .iter()
+        .filter(|model| model.kind == gbx::map::ModelKind::Block)
+        .map(|model| (model.id.as_str(), model.collection, model.author.as_deref()))
+        .collect();
+    assert_eq!(
+        blocks,
+        [
+            ("RoadTechStraight", 26, Some("Nadeo")),
+            ("Blocks/Ramp.Block.Gbx_CustomBlock", 26, Some("Someone"))
+        ]
+    );
+    assert!(dependencies
+        .missing
+        .iter()
+        .all(|id| id.as_str() != "Blocks/Ramp.Block.Gbx_CustomBlock"));
+    assert_eq!(dependencies.files.len(), 1);
+
+    let model = gbx::map::ModelDependency {
+        kind: gbx::map::ModelKind::Item,
+        id: gbx::Id::new(String::from("Flags/Flag.gbx.Variant")),
+        collection: 26,
+        author: None,
+    };
+    assert!(!model.is_custom());
+}
+
+#[test]
+fn write_read_header_deps() {
+    let mut map = Map::default();
+    map.music = Some(gbx::FileRef::External(gbx::ExternalFileRef {
+        hash: [0; 32],
+        path: std::path::PathBuf::from("Media\\Musics\\Song.ogg"),
+        locator_url: String::from("https://example.com/Song.ogg"),
+    }));
+    let deps = [gbx::map::Dependency {
+        file: String::from("Media\\Musics\\Song.ogg"),
+        url: Some(String::from("https://example.com/Song.ogg")),
+    }];
+    assert_eq!(map.header_deps(), deps);
+    let mut buf = vec![];
+    map.writer().write_to(&mut buf).unwrap();
+    let mut map = Map::reader().read_from(buf.as_slice()).unwrap();
+    assert_eq!(map.xml_header.deps, deps);
+
+    map.music = None;
+    let mut buf = vec![];
+    map.writer().write_to(&mut buf).unwrap();
+    let map = Map::reader().read_from(buf.as_slice()).unwrap();
+    assert!(map.xml_header.deps.is_empty());
+}
+
+#[test]
+fn read_xml_header() {
+    let mut buf = vec![];
+    Map::default().writer().write_to(&mut buf).unwrap();
+    let map = Map::reader().read_from(buf.as_slice()).unwrap();
+    assert_eq!(map.xml_header.title.as_deref(), Some("TMStadium"));
+    assert_eq!(map.xml_header.environment.as_deref(), Some("Stadium"));
+    assert_eq!(map.xml_header.is_validated, Some(false));
+    assert_eq!(map.xml_header.bronze_time, None);
+    assert_eq!(map.xml_header.attributes["times"]["authorscore"], "0");
+
+    let xml = r#"<header type="map" exever="3.3.0" version="6">
+        <!-- comment <ident uid="Commented"/> -->
+        <times bronze="-1" silver="abc" gold="12000" authortime="10000"/>
+        <unknown foo="bar"><nested/></unknown>
+        <desc envir="Stadium" nblaps="x" validated="1"/>
+        <ident name="Name"/>
+        <deps><dep file="Skins\Logo.dds"/></deps>
+        <playermodel/>
+    </header>"#;
+    let header = gbx::map::XmlHeader::parse(xml).unwrap();
+    assert_eq!(header.exe_version.as_deref(), Some("3.3.0"));
+    assert_eq!(header.uid, None);
+    assert_eq!(header.name.as_deref(), Some("Name"));
+    assert_eq!(header.author, None);
+    assert_eq!(header.bronze_time, None);
+    assert_eq!(header.silver_time, None);
+    assert_eq!(header.attributes["times"]["silver"], "abc");
+    assert_eq!(header.gold_time, Some(12000));
+    assert_eq!(header.author_time, Some(10000));
+    assert_eq!(header.num_laps, None);
+    assert_eq!(header.is_validated, Some(true));
+    assert_eq!(header.environment.as_deref(), Some("Stadium"));
+    assert_eq!(header.player_model, None);
+    assert_eq!(header.attributes["unknown"]["foo"], "bar");
+    assert_eq!(header.deps.len(), 1);
+    assert_eq!(header.deps[0].file, "Skins\\Logo.dds");
+    assert_eq!(header.deps[0].url, None);
+    assert!(gbx::map::XmlHeader::parse("<header><ident></header>").is_err());
+}
+
+#[test]
+fn write_read_header_metadata() {
+    let mut map = Map::default();
+    map.is_locked = true;
+    map.password = 0x12345678;
+    map.map_origin = [1.0, 2.0];
+    map.map_target = [3.0, 4.0];
+    map.map_type = gbx::map::MapType::Royal;
+    map.map_style = String::from("Tech");
+    map.lightmap_cache_uid = 42;
+    map.lightmap_version = 8;
+    map.editor_mode = 1;
+    map.author_version = 3;
+    map.author_score = 12345;
+    map.validation = Some(gbx::map::Validation {
+        bronze_time: 4,
+        silver_time: 3,
+        gold_time: 2,
+        author_time: 1,
+        ghost: None,
+    });
+    let mut buf = vec![];
+    map.writer().write_to(&mut buf).unwrap();
+    let mut map = Map::reader().read_from(buf.as_slice()).unwrap();
+    assert_eq!(map.map_kind, 6);
+    assert!(map.is_locked);
+    assert_eq!(map.password, 0x12345678);
+    assert_eq!(map.decoration_author.as_str(), "Nadeo");
+    assert_eq!(map.map_origin, [1.0, 2.0]);
+    assert_eq!(map.map_target, [3.0, 4.0]);
+    assert_eq!(map.map_type, gbx::map::MapType::Royal);
+    assert_eq!(map.map_style, "Tech");
+    assert_eq!(map.lightmap_cache_uid, 42);
+    assert_eq!(map.lightmap_version, 8);
+    assert_eq!(map.title_id.as_str(), "TMStadium");
+    assert_eq!(map.editor_mode, 1);
+    assert_eq!(map.author_version, 3);
+    assert_eq!(map.author_score, 12345);
+    assert!(map.is_validated());
+    assert_eq!(map.xml_header.is_validated, Some(true));
+    assert_eq!(
+        map.xml_header.map_type.as_deref(),
+        Some("TrackMania\\TM_Royal")
+    );
+    assert_eq!(map.xml_header.lightmap, Some(8));
+
+    map.validation = None;
+    let mut buf = vec![];
+    map.writer().write_to(&mut buf).unwrap();
+    let map = Map::reader().read_from(buf.as_slice()).unwrap();
+    assert!(!map.is_validated());
+    assert_eq!(map.xml_header.is_validated, Some(false));
+}
+
+#[test]
+fn validate_map_type() {
+    use gbx::map::{Item, MapType, MapTypeError, RoyalOrder, Validation, WaypointProperty};
+
+    let waypoint = |waypoint_property| Item {
+        waypoint_property: Some(waypoint_property),
+        ..Default::default()
+    };
+
+    let mut map = Map::default();
+    assert_eq!(map.validate_map_type(), Err(MapTypeError::MissingStart));
+    map.items
+        .push(waypoint(WaypointProperty::StartFinish { order: None }));
+    assert_eq!(
+        map.validate_map_type(),
+        Err(MapTypeError::MissingAuthorTime)
+    );
+    map.map_type = MapType::Platform;
+    assert_eq!(map.validate_map_type(), Ok(()));
+    map.map_type = MapType::from("MyGame\\MyMode");
+    assert_eq!(map.map_type.as_str(), "MyGame\\MyMode");
+    assert_eq!(map.validate_map_type(), Ok(()));
+
+    map.map_type = MapType::Royal;
+    map.validation = Some(Validation {
+        bronze_time: 4000,
+        silver_time: 3000,
+        gold_time: 2000,
+        author_time: 1000,
+        ghost: None,
+    });
+    assert_eq!(
+        map.validate_map_type(),
+        Err(MapTypeError::UnorderedRoyalWaypoint)
+    );
+    map.items.clear();
+    for order in [
+        RoyalOrder::White,
+        RoyalOrder::Green,
+        RoyalOrder::Blue,
+        RoyalOrder::Red,
+    ] {
+        map.items
+            .push(waypoint(WaypointProperty::Start { order: Some(order) }));
+        map.items
+            .push(waypoint(WaypointProperty::Finish { order: Some(order) }));
+    }
+    assert_eq!(
+        map.validate_map_type(),
+        Err(MapTypeError::MissingRoyalStart(RoyalOrder::Black))
+    );
+    map.items.push(waypoint(WaypointProperty::StartFinish {
+        order: Some(RoyalOrder::Black),
+    }));
+    assert_eq!(map.validate_map_type(), Ok(()));
+}
+
+#[test]
+fn write_read_script_metadata() {
+    use gbx::map::{ScriptType, ScriptValue};
+    use gbx::Vec3;
+
+    let mut map = Map::default();
+    assert_eq!(
+        map.script_metadata.get("LibMapType_MapTypeVersion"),
+        Some(&ScriptValue::Integer(1))
+    );
+    map.script_metadata.set("Bool", true);
+    map.script_metadata.set("Real", 1.5);
+    map.script_metadata.set("Text", "Hello");
+    map.script_metadata
+        .set("Vec2", ScriptValue::Vec2([1.0, 2.0]));
+    map.script_metadata.set("Vec3", Vec3::new(1.0, 2.0, 3.0));
+    map.script_metadata.set("Int3", Vec3::new(-1, 2, 3));
+    map.script_metadata.set("Int2", ScriptValue::Int2([4, -5]));
+    map.script_metadata.set(
+        "Race_AuthorRaceWaypointTimes",
+        ScriptValue::Array(
+            ScriptType::Integer,
+            vec![ScriptValue::Integer(1000), ScriptValue::Integer(2000)],
+        ),
+    );
+    map.script_metadata.set(
+        "Names",
+        ScriptValue::AssociativeArray(
+            ScriptType::Integer,
+            ScriptType::Text,
+            vec![(ScriptValue::Integer(1), ScriptValue::from("One"))],
+        ),
+    );
+    let mut buf = vec![];
+    map.writer().write_to(&mut buf).unwrap();
+    let read_map = Map::reader().read_from(buf.as_slice()).unwrap();
+    assert_eq!(read_map.script_metadata, map.script_metadata);
+    assert_eq!(read_map.script_metadata.len(), 10);
+
+    let mut map = Map::default();
+    map.map_type = gbx::map::MapType::Royal;
+    let mut buf = vec![];
+    map.writer().write_to(&mut buf).unwrap();
+    let mut map = Map::reader().read_from(buf.as_slice()).unwrap();
+    assert_eq!(map.script_metadata.len(), 1);
+    assert!(map
+        .script_metadata
+        .get("Race_AuthorRaceWaypointTimes")
+        .is_none());
+
+    map.map_type = gbx::map::MapType::Race;
+    let mut buf = vec![];
+    map.writer().write_to(&mut buf).unwrap();
+    let map = Map::reader().read_from(buf.as_slice()).unwrap();
+    assert_eq!(
+        map.script_metadata.get("Race_AuthorRaceWaypointTimes"),
+        Some(&ScriptValue::Array(ScriptType::Integer, vec![]))
+    );
+
+    let write_uncompressed = |map: &Map| {
+        let mut buf = vec![];
+        map.writer()
+            .compress_body(false)
+            .write_to(&mut buf)
+            .unwrap();
+        buf
+    };
+    let metadata_pos = |buf: &[u8]| {
+        buf.windows(4)
+            .position(|bytes| bytes == 0x11002000u32.to_le_bytes())
+            .unwrap()
+    };
+
+    let mut buf = write_uncompressed(&map);
+    let pos = metadata_pos(&buf);
+    buf[pos + 4..pos + 8].copy_from_slice(&7u32.to_le_bytes());
+    let metadata = buf[pos..pos + 30].to_vec();
+    let mut map = Map::reader().read_from(buf.as_slice()).unwrap();
+    assert!(!map.script_metadata.is_decoded());
+    assert!(map.script_metadata.is_empty());
+    let buf = write_uncompressed(&map);
+    let pos = metadata_pos(&buf);
+    assert_eq!(buf[pos..pos + 30], metadata);
+
+    map.script_metadata.set("Bool", true);
+    assert!(map.script_metadata.is_decoded());
+    let mut buf = write_uncompressed(&map);
+    let pos = metadata_pos(&buf);
+    assert_eq!(buf[pos + 4..pos + 8], 6u32.to_le_bytes());
+    buf[pos + 9] = 4;
+    let map = Map::reader().read_from(buf.as_slice()).unwrap();
+    assert!(!map.script_metadata.is_decoded());
+}
+
+#[test]
+fn map_uid() {
+    use gbx::map::{decode_uid, UidError, UidMode, UidStatus};
+
+    let map = Map::default();
+    let mut first = vec![];
+    map.writer_with_uid(UidMode::Deterministic(0))
+        .write_to(&mut first)
+        .unwrap();
+    let mut second = vec![];
+    map.writer_with_uid(UidMode::Deterministic(0))
+        .write_to(&mut second)
+        .unwrap();
+    assert_eq!(first, second);
+    assert_eq!(map.validate_uid(), Ok(UidStatus::Valid));
+    let uid = map.uid().unwrap();
+    assert_eq!(uid.len(), 27);
+    assert!(decode_uid(&uid).is_ok());
+
+    // Random UUIDs often contain a byte of 255, which carries into the checksum digits.
+    for _ in 0..64 {
+        map.writer().write_to(&mut vec![]).unwrap();
+        assert_ne!(map.uid().unwrap(), uid);
+        assert_eq!(map.validate_uid(), Ok(UidStatus::Valid));
+    }
+
+    let mut read_map = Map::reader().read_from(first.as_slice()).unwrap();
+    assert_eq!(read_map.uid(), Some(uid.clone()));
+    read_map
+        .writer_with_uid(UidMode::Keep)
+        .write_to(&mut vec![])
+        .unwrap();
+    assert_eq!(read_map.uid(), Some(uid));
+    assert_eq!(read_map.validate_uid(), Ok(UidStatus::Valid));
+
+    read_map.name = String::from("Changed");
+    assert_eq!(read_map.validate_uid(), Err(UidError::ChecksumMismatch));
+
+    // UIDs made by the game, which may omit trailing zero digits.
+    for uid in [
+        "olsKnq_qAghcVAnEkoeUnVHFZei",
+        "abc",
+        "ZZZZZZZZZZZZZZZZZZZZZZZZZZZ",
+    ] {
+        read_map
+            .writer_with_uid(UidMode::Explicit(gbx::Id::new(String::from(uid))))
+            .write_to(&mut vec![])
+            .unwrap();
+        assert_eq!(read_map.validate_uid(), Ok(UidStatus::UnknownScheme));
+    }
+    read_map
+        .writer_with_uid(UidMode::Explicit(gbx::Id::new("a".repeat(28))))
+        .write_to(&mut vec![])
+        .unwrap();
+    assert_eq!(read_map.validate_uid(), Err(UidError::InvalidLength(28)));
+    assert_eq!(decode_uid(""), Err(UidError::InvalidLength(0)));
+    assert_eq!(
+        decode_uid("abc").unwrap(),
+        decode_uid("abc________________________").unwrap()
+    );
+    assert_eq!(
+        decode_uid("abcdefghijklmnopqrstuvwxyz-"),
+        Err(UidError::InvalidCharacter('-'))
+    );
+    assert_eq!(
+        decode_uid("ZZZZZZZZZZZZZZZZZZZZZZZZZZZ"),
+        Err(UidError::Overflow)
+    );
+
+    // Encoded by the radix 255 Base63 encoder of earlier versions.
+    let bytes: Vec<u8> = (1..=20).collect();
+    assert_eq!(
+        decode_uid("onKIEFPcWLQitREN9DHsjZe6vK0")
+            .unwrap()
+            .as_slice(),
+        bytes
+    );
+    assert_eq!(
+        decode_uid("2rg_Fu0eUxAMUrczeC5vd7uirv9").unwrap(),
+        [0x12, 0x34, 0x56, 0x78].repeat(5).as_slice()
+    );
+
+    let mut map = Map::default();
+    map.script_metadata.set(
+        "Invalid",
+        gbx::map::ScriptValue::Array(
+            gbx::map::ScriptType::Integer,
+            vec![gbx::map::ScriptValue::from("a")],
+        ),
+    );
+    assert!(map.writer().write_to(vec![]).is_err());
+    assert!(map
+        .writer_with_uid(UidMode::Deterministic(0))
+        .write_to(vec![])
+        .is_err());
+    let valid_map = Map::default();
+    valid_map.writer().write_to(vec![]).unwrap();
+    let uid = valid_map.uid().unwrap();
+    assert!(map
+        .writer_with_uid(UidMode::Explicit(uid))
+        .write_to(vec![])
+        .is_err());
+    assert!(matches!(
+        map.validate_uid(),
+        Err(UidError::Unserializable(_))
+    ));
+}
+
+#[test]
+fn map_diff() {
+    use gbx::map::{
+        diff, BlockType, Change, Direction, Item, ObjectKind, Placement, Validation,
+        WaypointProperty,
+    };
+    use gbx::{Id, Vec3};
+
+    let block = |model_id: &str, x: u8| {
+        BlockType::Normal(gbx::map::Block {
+            model_id: Id::new(String::from(model_id)),
+            coord: Vec3 { x, y: 9, z: 0 },
+            ..Default::default()
+        })
+    };
+    let item = |waypoint_property| Item {
+        model_id: Id::new(String::from("Flag")),
+        waypoint_property,
+        ..Default::default()
+    };
+
+    let mut old = Map::default();
+    old.blocks.push(block("RoadTechStraight", 0));
+    old.blocks.push(block("RoadTechCurve1", 1));
+    old.blocks.push(block("RoadTechStart", 2));
+    old.items.push(item(None));
+
+    let mut new = Map::default();
+    new.blocks.push(block("RoadTechStraight", 0));
+    new.blocks.push(block("RoadTechCurve1", 3));
+    new.blocks.push(block("RoadTechFinish", 4));
+    new.items.push(item(Some(WaypointProperty::Checkpoint)));
+    assert!(diff(&old, &old).is_empty());
+
+    new.name = String::from("New");
+    new.validation = Some(Validation {
+        bronze_time: 4000,
+        silver_time: 3000,
+        gold_time: 2000,
+        author_time: 1000,
+        ghost: None,
+    });
+
+    let changes = diff(&old, &new).changes;
+    assert_eq!(changes.len(), 6);
+    assert!(matches!(
+        changes[0],
+        Change::MetadataChanged { field: "name", .. }
+    ));
+    assert!(matches!(
+        changes[1],
+        Change::MedalsChanged {
+            old: None,
+            new: Some(_)
+        }
+    ));
+    assert_eq!(
+        changes[2],
+        Change::Moved {
+            kind: ObjectKind::Block,
+            model_id: Id::new(String::from("RoadTechCurve1")),
+            from: Placement::Grid {
+                coord: Vec3 { x: 1, y: 9, z: 0 },
+                dir: Direction::North,
+            },
+            to: Placement::Grid {
+                coord: Vec3 { x: 3, y: 9, z: 0 },
+                dir: Direction::North,
+            },
+        }
+    );
+    assert!(matches!(
+        changes[3],
+        Change::Added {
+            kind: ObjectKind::Block,
+            ..
+        }
+    ));
+    assert!(matches!(
+        changes[4],
+        Change::Removed {
+            kind: ObjectKind::Block,
+            ..
+        }
+    ));
+    assert!(matches!(
+        changes[5],
+        Change::WaypointChanged {
+            kind: ObjectKind::Item,
+            old: None,
+            ..
+        }
+    ));
+    assert_eq!(
+        diff(&old, &new).to_string().lines().nth(2),
+        Some("block moved: RoadTechCurve1 from (1, 9, 0) North to (3, 9, 0) North")
+    );
+
+    let mut old = Map::default();
+    old.items.push(item(None));
+    let mut new = Map::default();
+    new.items.push(Item {
+        author: Some(Id::new(String::from("Someone"))),
+        ..item(None)
+    });
+    new.is_locked = true;
+    new.validation = Some(Validation {
+        bronze_time: 4000,
+        silver_time: 3000,
+        gold_time: 2000,
+        author_time: 1000,
+        ghost: Some(gbx::Ghost {
+            race_time: Some(1000),
+            login: String::from("player"),
+        }),
+    });
+    new.password = 0x12345678;
+    new.zone_genealogy_mut(1, 2).unwrap().current_zone_id = Id::new(String::from("Dirt"));
+    new.embedded_files
+        .get_or_insert_with(Default::default)
+        .embed("Items/Flag.Item.Gbx", vec![0], None)
+        .unwrap();
+
+    let changes = diff(&old, &new).changes;
+    assert!(matches!(
+        changes[0],
+        Change::MetadataChanged {
+            field: "is_locked",
+            ..
+        }
+    ));
+    assert_eq!(
+        changes[1],
+        Change::MetadataChanged {
+            field: "password",
+            old: String::from("None"),
+            new: String::from("********"),
+        }
+    );
+    assert_eq!(
+        changes[2],
+        Change::MetadataChanged {
+            field: "validation_ghost",
+            old: String::from("None"),
+            new: String::from("1000 ms by \"player\""),
+        }
+    );
+    assert!(matches!(changes[3], Change::MedalsChanged { .. }));
+    assert!(matches!(
+        changes[4],
+        Change::Added {
+            kind: ObjectKind::Item,
+            ..
+        }
+    ));
+    assert!(matches!(
+        changes[5],
+        Change::Removed {
+            kind: ObjectKind::Item,
+            ..
+        }
+    ));
+    assert_eq!(
+        changes[6],
+        Change::ZonesChanged {
+            columns: vec![(1, 2)]
+        }
+    );
+    assert_eq!(
+        changes[7],
+        Change::EmbeddedFileAdded {
+            path: String::from("Items/Flag.Item.Gbx")
+        }
+    );
+    assert_eq!(changes.len(), 8);
+}
+
+#[test]
+fn block_geometry() {
+    use gbx::map::{
+        euler_to_quaternion, grid_to_world, quaternion_to_euler, world_to_grid, Block, Direction,
+        FreeBlock,
+    };
+    use gbx::Vec3;
+
+    assert_eq!(
+        grid_to_world(Vec3::new(1, 9, 2)),
+        Vec3::new(32.0, 8.0, 64.0)
+    );
+    assert_eq!(
+        world_to_grid(&Vec3::new(40.0, 12.0, 70.0)),
+        Some(Vec3::new(1, 9, 2))
+    );
+    assert_eq!(world_to_grid(&Vec3::new(-1.0, 0.0, 0.0)), None);
+
+    let (yaw, pitch, roll) = quaternion_to_euler(&euler_to_quaternion(0.5, -0.25, 1.0));
+    assert!((yaw - 0.5).abs() < 1e-5);
+    assert!((pitch + 0.25).abs() < 1e-5);
+    assert!((roll - 1.0).abs() < 1e-5);
+
+    for dir in [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ] {
+        assert_eq!(Direction::from_yaw(dir.yaw()), Some(dir));
+
+        let block = Block {
+            dir,
+            coord: Vec3::new(3, 10, 5),
+            ..Default::default()
+        };
+        for size in [Vec3::new(1, 1, 1), Vec3::new(2, 1, 3)] {
+            let free_block = block.to_free_block(size);
+            let snapped = free_block.to_block(size).unwrap();
+            assert_eq!(snapped.dir, dir);
+            assert_eq!(snapped.coord, block.coord);
+
+            // The rotated footprint starts at the corner of the block's grid cell.
+            let matrix = free_block.rotation_matrix();
+            let (mut min_x, mut min_z) = (f32::MAX, f32::MAX);
+
+            for (x, z) in [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)] {
+                let x = x * size.x as f32 * 32.0;
+                let z = z * size.z as f32 * 32.0;
+                min_x = min_x.min(free_block.pos.x + matrix[0][0] * x + matrix[0][2] * z);
+                min_z = min_z.min(free_block.pos.z + matrix[2][0] * x + matrix[2][2] * z);
+            }
+
+            assert!((min_x - block.world_pos().x).abs() < 1e-3);
+            assert!((min_z - block.world_pos().z).abs() < 1e-3);
+        }
+    }
+
+    let free_block = FreeBlock {
+        pos: Vec3::new(1.0, 0.0, 0.0),
+        ..Default::default()
+    };
+    assert!(free_block.to_block(Vec3::new(1, 1, 1)).is_none());
+}
+
+#[test]
+fn map_transform() {
+    use gbx::map::{Block, BlockType, Direction, Item, Selection, Transform, TransformError};
+    use gbx::Vec3;
+
+    let mut map = Map::default();
+    map.blocks.push(BlockType::Normal(Block {
+        coord: Vec3::new(0, 9, 0),
+        ..Default::default()
+    }));
+    map.items.push(Item {
+        coord: Vec3::new(0, 9, 0),
+        pos: Vec3::new(16.0, 8.0, 16.0),
+        pivot_pos: Vec3::new(1.0, 0.0, 0.0),
+        ..Default::default()
+    });
+
+    let block = |map: &Map| match map.blocks[0] {
+        BlockType::Normal(ref block) => (block.coord, block.dir),
+        BlockType::Free(_) => unreachable!(),
+    };
+    let unit = |_: &gbx::Id| Vec3::new(1, 1, 1);
+
+    map.transform(&Transform::Rotate(Direction::East), unit)
+        .unwrap();
+    assert_eq!(block(&map), (Vec3::new(47, 9, 0), Direction::East));
+    assert_eq!(map.items[0].coord, Vec3::new(47, 9, 0));
+    assert_eq!(map.items[0].pos, Vec3::new(1520.0, 8.0, 16.0));
+
+    map.transform(&Transform::MirrorX, unit).unwrap();
+    assert_eq!(block(&map), (Vec3::new(0, 9, 0), Direction::West));
+    assert_eq!(map.items[0].pivot_pos, Vec3::new(-1.0, 0.0, 0.0));
+
+    assert_eq!(
+        map.transform(&Transform::Translate(Vec3::new(-1, 0, 0)), unit),
+        Err(TransformError::OutOfBounds)
+    );
+    assert_eq!(block(&map), (Vec3::new(0, 9, 0), Direction::West));
+
+    let selection = Selection {
+        blocks: vec![0],
+        items: vec![],
+    };
+    map.transform_selection(&selection, &Transform::Translate(Vec3::new(2, 1, 3)), unit)
+        .unwrap();
+    assert_eq!(block(&map), (Vec3::new(2, 10, 3), Direction::West));
+    assert_eq!(map.items[0].coord, Vec3::new(0, 9, 0));
+
+    // A block spanning 2 by 3 cells when facing north.
+    let mut map = Map::default();
+    map.blocks.push(BlockType::Normal(Block {
+        coord: Vec3::new(0, 9, 0),
+        ..Default::default()
+    }));
+    let size = |_: &gbx::Id| Vec3::new(2, 1, 3);
+    map.transform(&Transform::Rotate(Direction::East), size)
+        .unwrap();
+    assert_eq!(block(&map), (Vec3::new(45, 9, 0), Direction::East));
+    map.transform(&Transform::MirrorX, size).unwrap();
+    assert_eq!(block(&map), (Vec3::new(0, 9, 0), Direction::West));
+    map.transform(&Transform::MirrorZ, size).unwrap();
+    assert_eq!(block(&map), (Vec3::new(0, 9, 46), Direction::West));
+    assert_eq!(
+        map.transform(&Transform::Translate(Vec3::new(0, 0, 1)), size),
+        Err(TransformError::OutOfBounds)
+    );
+}
+
+#[test]
+fn spatial_index() {
+    use gbx::map::{Aabb, Block, BlockType, Direction, FreeBlock, Item, ObjectRef};
+    use gbx::Vec3;
+
+    let block = |x| {
+        BlockType::Normal(Block {
+            coord: Vec3::new(x, 12, 4),
+            ..Default::default()
+        })
+    };
+
+    let mut map = Map::default();
+    map.blocks.push(block(4));
+    map.blocks.push(block(5));
+    map.blocks.push(BlockType::Free(FreeBlock {
+        pos: Vec3::new(140.0, 32.0, 130.0),
+        ..Default::default()
+    }));
+    map.items.push(Item {
+        coord: Vec3::new(4, 12, 4),
+        pos: Vec3::new(140.0, 34.0, 140.0),
+        ..Default::default()
+    });
+
+    let unit = |_: &gbx::Id| Vec3::new(1, 1, 1);
+    let mut index = map.spatial_index(unit);
+    assert_eq!(
+        index.at(Vec3::new(4, 12, 4)),
+        [ObjectRef::Block(0), ObjectRef::Block(2), ObjectRef::Item(0)]
+    );
+    assert_eq!(
+        index.neighbors(Vec3::new(4, 12, 4), Direction::West),
+        [ObjectRef::Block(1)]
+    );
+    assert!(index
+        .neighbors(Vec3::new(4, 12, 4), Direction::East)
+        .is_empty());
+    assert_eq!(
+        index.query(&Aabb::new(
+            Vec3::new(130.0, 33.0, 135.0),
+            Vec3::new(150.0, 35.0, 145.0)
+        )),
+        [ObjectRef::Block(0), ObjectRef::Block(2), ObjectRef::Item(0)]
+    );
+    assert_eq!(
+        index.overlapping_blocks(),
+        [
+            (ObjectRef::Block(0), ObjectRef::Block(2)),
+            (ObjectRef::Block(1), ObjectRef::Block(2))
+        ]
+    );
+
+    if let BlockType::Free(ref mut free_block) = map.blocks[2] {
+        free_block.pos = Vec3::new(300.0, 32.0, 300.0);
+    }
+    index.update(&map, ObjectRef::Block(2), unit);
+    assert!(index.overlapping_blocks().is_empty());
+    assert_eq!(
+        index.at(Vec3::new(4, 12, 4)),
+        [ObjectRef::Block(0), ObjectRef::Item(0)]
+    );
+
+    let mut indexed = map.indexed(unit);
+    let ghost = indexed.push_block(BlockType::Normal(Block {
+        coord: Vec3::new(4, 12, 4),
+        is_ghost: true,
+        ..Default::default()
+    }));
+    assert_eq!(ghost, ObjectRef::Block(3));
+    assert_eq!(
+        indexed.index().at(Vec3::new(4, 12, 4)),
+        [ObjectRef::Block(0), ObjectRef::Item(0), ObjectRef::Block(3)]
+    );
+    assert!(indexed.index().overlapping_blocks().is_empty());
+
+    indexed.edit_block(3, |block| {
+        if let BlockType::Normal(ref mut block) = *block {
+            block.is_ghost = false;
+        }
+    });
+    assert_eq!(
+        indexed.index().overlapping_blocks(),
+        [(ObjectRef::Block(0), ObjectRef::Block(3))]
+    );
+
+    indexed.remove_block(0);
+    assert_eq!(
+        indexed.index().at(Vec3::new(4, 12, 4)),
+        [ObjectRef::Item(0), ObjectRef::Block(2)]
+    );
+    assert_eq!(
+        indexed.index().at(Vec3::new(5, 12, 4)),
+        [ObjectRef::Block(0)]
+    );
+    assert!(indexed.index().bounds(ObjectRef::Block(3)).is_none());
+
+    indexed.edit_item(0, |item| item.coord = Vec3::new(6, 12, 4));
+    assert_eq!(
+        indexed.index().at(Vec3::new(6, 12, 4)),
+        [ObjectRef::Item(0)]
+    );
+
+    let index = indexed.into_index();
+    assert_eq!(map.blocks.len(), 3);
+    assert_eq!(index.at(Vec3::new(4, 12, 4)), [ObjectRef::Block(2)]);
+
+    // Blocks spanning 3 cells along X when facing north.
+    let wide = |coord: Vec3<u8>, dir: Direction| {
+        BlockType::Normal(Block {
+            model_id: gbx::Id::new(String::from("Wide")),
+            coord,
+            dir,
+            ..Default::default()
+        })
+    };
+    let size = |id: &gbx::Id| match id.as_str() {
+        "Wide" => Vec3::new(3, 1, 1),
+        _ => Vec3::new(1, 1, 1),
+    };
+
+    let mut map = Map::default();
+    map.blocks.push(wide(Vec3::new(4, 12, 4), Direction::North));
+    map.blocks.push(block(6));
+    map.blocks
+        .push(wide(Vec3::new(10, 12, 10), Direction::East));
+    map.blocks.push(BlockType::Normal(Block {
+        coord: Vec3::new(10, 12, 12),
+        ..Default::default()
+    }));
+    assert!(map.spatial_index(unit).overlapping_blocks().is_empty());
+
+    let index = map.spatial_index(size);
+    assert_eq!(
+        index.overlapping_blocks(),
+        [
+            (ObjectRef::Block(0), ObjectRef::Block(1)),
+            (ObjectRef::Block(2), ObjectRef::Block(3))
+        ]
+    );
+    assert_eq!(
+        index.query(&Aabb::new(
+            Vec3::new(200.0, 40.0, 140.0),
+            Vec3::new(201.0, 41.0, 141.0)
+        )),
+        [ObjectRef::Block(0), ObjectRef::Block(1)]
+    );
+}
+
+#[test]
+fn map_check() {
+    use gbx::map::{Block, BlockType, Item, Problem, Skin, Validation, WaypointProperty};
+    use gbx::Vec3;
+
+    let waypoint = |waypoint_property| Item {
+        waypoint_property: Some(waypoint_property),
+        ..Default::default()
+    };
+
+    let mut map = Map::default();
+    map.num_cps = 2;
+    map.items
+        .push(waypoint(WaypointProperty::Start { order: None }));
+    map.items
+        .push(waypoint(WaypointProperty::Finish { order: None }));
+    map.items.push(waypoint(WaypointProperty::Checkpoint));
+    map.items
+        .push(waypoint(WaypointProperty::LinkedCheckpoint { group: 0 }));
+    map.items
+        .push(waypoint(WaypointProperty::LinkedCheckpoint { group: 0 }));
+    map.validation = Some(Validation {
+        bronze_time: 4000,
+        silver_time: 3000,
+        gold_time: 2000,
+        author_time: 1000,
+        ghost: None,
+    });
+    assert_eq!(map.check(), []);
+
+    map.num_laps = Some(3);
+    map.items
+        .push(waypoint(WaypointProperty::LinkedCheckpoint { group: 1 }));
+    for _ in 0..2 {
+        map.blocks.push(BlockType::Normal(Block {
+            coord: Vec3::new(48, 9, 0),
+            ..Default::default()
+        }));
+    }
+    map.items.push(Item {
+        skin: Some(Skin::default()),
+        ..Default::default()
+    });
+    map.validation.as_mut().unwrap().author_time = 2500;
+
+    assert_eq!(
+        map.check(),
+        [
+            Problem::CheckpointCount {
+                expected: 2,
+                actual: 3
+            },
+            Problem::MissingStartFinish,
+            Problem::UnlinkedCheckpointGroup { group: 1 },
+            Problem::BlockOutOfBounds { index: 0 },
+            Problem::BlockOutOfBounds { index: 1 },
+            Problem::DuplicateBlock {
+                first: 0,
+                second: 1
+            },
+            Problem::MissingSkinFile { index: 6 },
+            Problem::UnorderedMedals,
+        ]
+    );
+    assert_eq!(
+        map.check()[0].to_string(),
+        "map has 3 checkpoints but num_cps is 2"
+    );
+}
+
+#[test]
+fn waypoint_summary() {
+    use gbx::map::{Block, BlockType, Item, ObjectRef, WaypointProperty};
+
+    let waypoint = |waypoint_property| Item {
+        waypoint_property: Some(waypoint_property),
+        ..Default::default()
+    };
+
+    let mut map = Map::default();
+    map.blocks.push(BlockType::Normal(Block::default()));
+    map.items
+        .push(waypoint(WaypointProperty::StartFinish { order: None }));
+    map.items.push(waypoint(WaypointProperty::Checkpoint));
+    map.items
+        .push(waypoint(WaypointProperty::LinkedCheckpoint { group: 3 }));
+    map.items
+        .push(waypoint(WaypointProperty::LinkedCheckpoint { group: 3 }));
+    map.items.push(Item::default());
+
+    let objects: Vec<ObjectRef> = map.waypoints().map(|waypoint| waypoint.object).collect();
+    assert_eq!(
+        objects,
+        [
+            ObjectRef::Item(0),
+            ObjectRef::Item(1),
+            ObjectRef::Item(2),
+            ObjectRef::Item(3)
+        ]
+    );
+
+    let summary = map.waypoint_summary();
+    assert_eq!(summary.num_checkpoints, 2);
+    assert_eq!(summary.num_start_finishes, 1);
+    assert!(summary.has_start() && summary.has_finish());
+
+    let mut map = Map::default();
+    map.num_laps = Some(3);
+    assert!(map
+        .writer()
+        .recompute_num_cps(true)
+        .write_to(&mut vec![])
+        .is_err());
+
+    map.num_laps = None;
+    let mut buf = vec![];
+    map.writer()
+        .recompute_num_cps(true)
+        .write_to(&mut buf)
+        .unwrap();
+    let read_map = Map::reader().read_from(buf.as_slice()).unwrap();
+    assert_eq!(read_map.num_cps, 0);
+
+    buf.clear();
+    map.writer().write_to(&mut buf).unwrap();
+    let read_map = Map::reader().read_from(buf.as_slice()).unwrap();
+    assert_eq!(read_map.num_cps, 1);
+
+    // Options of one builder do not leak into other builders of the same map.
+    let recomputing = map.writer().recompute_num_cps(true);
+    let plain = map.writer();
+    buf.clear();
+    recomputing.write_to(&mut buf).unwrap();
+    let read_map = Map::reader().read_from(buf.as_slice()).unwrap();
+    assert_eq!(read_map.num_cps, 0);
+
+    buf.clear();
+    plain.write_to(&mut buf).unwrap();
+    let read_map = Map::reader().read_from(buf.as_slice()).unwrap();
+    assert_eq!(read_map.num_cps, 1);
+}
+
+#[test]
+fn styled_text() {
+    use gbx::text::{strip, Style, StyledText, TextColor, Width};
+
+    let text = StyledText::parse("$<$w$F00Big$>$nsmall $lwww.example.com$l $$5");
+    assert_eq!(text.plain(), "Bigsmall www.example.com $5");
+    assert_eq!(
+        text.spans[0].style,
+        Style::new()
+            .width(Width::Wide)
+            .color(TextColor::new(15, 0, 0))
+    );
+    assert_eq!(text.spans[1].style, Style::new().width(Width::Narrow));
+    assert_eq!(text.spans[2].style.link.as_deref(), Some("www.example.com"));
+    assert_eq!(StyledText::parse(&text.to_string()), text);
+
+    assert_eq!(strip("$i$3f3Green$z $$"), "Green $");
+    assert_eq!(
+        StyledText::parse("$o<a>$l[https://x.org]link").to_html(),
+        "<span style=\"font-weight:bold;\">&lt;a&gt;</span>\
+         <a href=\"https://x.org\"><span style=\"font-weight:bold;\">link</span></a>"
+    );
+    assert_eq!(StyledText::parse("$tup$f00").to_ansi(), "UP\x1b[0m");
+
+    let text = StyledText::parse("$h[Menu]open$h $p[Tools]");
+    assert_eq!(text.spans[0].style, Style::new().manialink("Menu"));
+    assert_eq!(StyledText::parse(&text.to_string()), text);
+    assert_eq!(text.to_html(), "<span>open</span><span> </span>");
+    assert_eq!(text.to_ansi(), "open\x1b[0m \x1b[0m");
+
+    let text = StyledText::new()
+        .push("a]b", Style::new().link("a]b"))
+        .push("c", Style::new().link("https://x.org/[d]"));
+    assert_eq!(text.to_string(), "$la]b$l$z$l[https://x.org/[d%5D]c$l");
+    let parsed = StyledText::parse(&text.to_string());
+    assert_eq!(parsed.spans[0], text.spans[0]);
+    assert_eq!(
+        parsed.spans[1].style.link.as_deref(),
+        Some("https://x.org/[d%5D")
+    );
+    assert_eq!(parsed.plain(), "a]bc");
+
+    let text = StyledText::parse("$l[https://x.org\x1b\\\x07\u{9c}/]a\x1b[2J\u{9b}b\tc$l");
+    assert_eq!(
+        text.to_ansi(),
+        "\x1b]8;;https://x.org\\/\x1b\\a[2Jb\tc\x1b[0m\x1b]8;;\x1b\\"
+    );
+}
+
+#[test]
+fn map_preview() {
+    use gbx::map::{
+        Block, BlockType, Color, PreviewError, PreviewOptions, Projection, WaypointProperty,
+    };
+    use gbx::Vec3;
+
+    let unit = |_: &gbx::Id| Vec3::new(1, 1, 1);
+    let mut map = Map::default();
+    map.blocks.push(BlockType::Normal(Block {
+        coord: Vec3::new(0, 12, 0),
+        color: Color::Red,
+        ..Default::default()
+    }));
+    map.blocks.push(BlockType::Normal(Block {
+        coord: Vec3::new(1, 12, 0),
+        waypoint_property: Some(WaypointProperty::Start { order: None }),
+        ..Default::default()
+    }));
+    map.blocks.push(BlockType::Normal(Block {
+        coord: Vec3::new(2, 12, 0),
+        color: Color::Red,
+        is_ghost: true,
+        ..Default::default()
+    }));
+
+    let preview = map
+        .render_preview(
+            &PreviewOptions {
+                projection: Projection::TopDown,
+                cell_size: 4,
+            },
+            unit,
+        )
+        .unwrap();
+    assert_eq!(preview.width, map.size.x * 4);
+    assert_eq!(preview.height, map.size.z * 4);
+
+    let ground = preview.pixel(0, 0);
+    let cell = |x: u32| preview.pixel(preview.width - 4 * x - 2, preview.height - 2);
+    let (red, start, ghost) = (cell(0), cell(1), cell(2));
+    assert_eq!(ground[3], 255);
+    assert!(red[0] > red[1] && red[0] > red[2]);
+    assert!(start[1] > start[0] && start[1] > start[2]);
+    assert!(ghost[0] < red[0] && ghost != ground);
+
+    let png = preview.to_png();
+    assert_eq!(png[..8], [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]);
+    assert_eq!(&png[12..16], b"IHDR");
+    assert_eq!(png[16..20], preview.width.to_be_bytes());
+    assert_eq!(png[20..24], preview.height.to_be_bytes());
+    assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+
+    let preview = map
+        .render_preview(
+            &PreviewOptions {
+                projection: Projection::Isometric,
+                cell_size: 4,
+            },
+            unit,
+        )
+        .unwrap();
+    assert_eq!(preview.width, (map.size.x + map.size.z) * 4);
+    assert_eq!(preview.pixel(0, 0)[3], 0);
+
+    assert_eq!(
+        map.render_preview(
+            &PreviewOptions {
+                projection: Projection::TopDown,
+                cell_size: u32::MAX,
+            },
+            unit,
+        ),
+        Err(PreviewError::TooLarge {
+            width: u32::MAX,
+            height: u32::MAX
+        })
+    );
+
+    let wide = |id: &gbx::Id| {
+        if id.as_str() == "Wide" {
+            Vec3::new(2, 1, 1)
+        } else {
+            Vec3::new(1, 1, 1)
+        }
+    };
+    map.blocks.clear();
+    map.blocks.push(BlockType::Normal(Block {
+        model_id: gbx::Id::new(String::from("Wide")),
+        coord: Vec3::new(0, 12, 0),
+        color: Color::Red,
+        ..Default::default()
+    }));
+    let options = PreviewOptions {
+        projection: Projection::TopDown,
+        cell_size: 4,
+    };
+    let preview = map.render_preview(&options, wide).unwrap();
+    let cell = |x: u32| preview.pixel(preview.width - 4 * x - 2, preview.height - 2);
+    assert_eq!(cell(1), cell(0));
+    assert_ne!(cell(2), cell(0));
+    let preview = map.render_preview(&options, unit).unwrap();
+    let cell = |x: u32| preview.pixel(preview.width - 4 * x - 2, preview.height - 2);
+    assert_ne!(cell(1), cell(0));
+
+    map.size.y = 9;
+    map.blocks[0] = BlockType::Normal(Block {
+        coord: Vec3::new(0, 8, 0),
+        color: Color::Red,
+        ..Default::default()
+    });
+    let preview = map.render_preview(&options, unit).unwrap();
+    let red = preview.pixel(preview.width - 2, preview.height - 2);
+    assert!(red[0] > red[1] && red[0] > red[2]);
+    assert_eq!(red[3], 255);
+
+    map.no_stadium = true;
+    let preview = map
+        .render_preview(&PreviewOptions::default(), unit)
+        .unwrap();
+    assert_eq!(preview.pixel(0, 0)[3], 0);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_map() {
+    use gbx::map::UidMode;
+
+    let mut map = Map::default();
+    map.name = String::from("Serde");
+    map.intro_media = Some(gbx::map::media::Clip::default());
+    map.script_metadata.set("Text", "Value");
+    let mut buf = vec![];
+    map.writer_with_uid(UidMode::Deterministic(0))
+        .write_to(&mut buf)
+        .unwrap();
+
+    let json = serde_json::to_string(&map).unwrap();
+    let map: Map = serde_json::from_str(&json).unwrap();
+    let mut other_buf = vec![];
+    map.writer_with_uid(UidMode::Keep)
+        .write_to(&mut other_buf)
+        .unwrap();
+    assert_eq!(buf, other_buf);
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn cli() {
+    use std::process::Command;
+
+    let dir = std::env::temp_dir().join(format!("gbx-cli-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("Cli.Map.Gbx");
+    let path = path.to_str().unwrap();
+    Map::default().writer().write_to_file(path).unwrap();
+
+    let gbx = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_gbx"))
+            .args(args)
+            .output()
+            .unwrap()
+    };
+
+    let output = gbx(&["info", path]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("03043000 (Map)"));
+    assert!(stdout.contains("Body:          compressed"));
+
+    assert!(gbx(&["set-name", path, "Renamed"]).status.success());
+    assert!(gbx(&["set-medals", path, "4000", "3000", "2000", "1000"])
+        .status
+        .success());
+    let map = Map::reader().read_from_file(path).unwrap();
+    assert_eq!(map.name, "Renamed");
+    assert_eq!(map.validation.unwrap().author_time, 1000);
+
+    assert!(gbx(&["recompress", "--force", "--uncompressed", path])
+        .status
+        .success());
+    assert!(Map::reader().read_from_file(path).is_ok());
+
+    let color = |_| {
+        [0x03080003_u32, 0]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    };
+    std::fs::write(path, write_intro_media_block(0x03080000, color, 0)).unwrap();
+    let output = gbx(&["set-name", path, "Dropped"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("1 MediaTracker clip(s)"));
+    let output = gbx(&["set-name", "--force", path, "Forced"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("warning: "));
+
+    let output = gbx(&["validate", path]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("map has no start"));
+
+    let output = gbx(&["info", "does-not-exist.Map.Gbx"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: "));
+    assert_eq!(gbx(&["unknown"]).status.code(), Some(2));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}